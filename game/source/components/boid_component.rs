@@ -0,0 +1,30 @@
+/// Flocks with every other boid within its neighbour radius, simulated on the gpu by
+/// BoidsSystem - steers the entity's movement, so it needs a transform and movement too
+pub struct BoidComponent {
+    pub max_speed: f32,
+    pub neighbour_radius: f32,
+    // how strongly it steers away from close neighbours, towards their heading, and towards
+    // their centre
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+impl BoidComponent {
+    pub fn new(max_speed: f32, neighbour_radius: f32) -> Self {
+        Self {
+            max_speed,
+            neighbour_radius,
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0,
+        }
+    }
+
+    pub fn with_weights(mut self, separation: f32, alignment: f32, cohesion: f32) -> Self {
+        self.separation = separation;
+        self.alignment = alignment;
+        self.cohesion = cohesion;
+        self
+    }
+}
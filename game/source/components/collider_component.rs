@@ -1,10 +1,16 @@
 use crate::core::geometry;
+use crate::core::tilemap::TileContact;
 
 pub struct ColliderComponent {
     pub aabb: Option<geometry::BoundingBox>,
     pub obb: Option<geometry::BoundingBox>,
     pub needs_aabb_update: bool,
     pub needs_obb_update: bool,
+    // a box around the transform's position, scaled by it, instead of the mesh's bounds - for
+    // entities without meshes, such as sprites
+    pub half_extents: Option<geometry::Vector3>,
+    // solid tilemap tiles the collider was pushed out of this frame
+    pub tile_contacts: Vec<TileContact>,
 }
 
 impl ColliderComponent {
@@ -14,9 +20,16 @@ impl ColliderComponent {
             obb: None,
             needs_aabb_update: true,
             needs_obb_update: true,
+            half_extents: None,
+            tile_contacts: Vec::new(),
         }
     }
 
+    pub fn with_half_extents(mut self, half_extents: geometry::Vector3) -> Self {
+        self.half_extents = Some(half_extents);
+        self
+    }
+
     pub fn invalidate_bounds(&mut self) {
         self.needs_aabb_update = true;
         self.needs_obb_update = true;
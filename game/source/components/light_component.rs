@@ -0,0 +1,66 @@
+use crate::core::geometry;
+
+/// the shape of a light - directional and spot lights point along their transform's -Z axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    // cone angles in radians, measured from the centre of the cone
+    Spot { inner_angle: f32, outer_angle: f32 },
+}
+
+pub struct LightComponent {
+    pub kind: LightKind,
+    pub colour: geometry::Colour,
+    pub intensity: f32,
+    // distance at which point and spot lights fall off to nothing - ignored for directional lights
+    pub range: f32,
+    // only directional and spot lights can cast shadows
+    pub casts_shadows: bool,
+}
+
+impl LightComponent {
+    pub fn directional(colour: geometry::Colour, intensity: f32) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            colour,
+            intensity,
+            range: f32::MAX,
+            casts_shadows: false,
+        }
+    }
+
+    pub fn point(colour: geometry::Colour, intensity: f32, range: f32) -> Self {
+        Self {
+            kind: LightKind::Point,
+            colour,
+            intensity,
+            range,
+            casts_shadows: false,
+        }
+    }
+
+    pub fn spot(
+        colour: geometry::Colour,
+        intensity: f32,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                inner_angle,
+                outer_angle,
+            },
+            colour,
+            intensity,
+            range,
+            casts_shadows: false,
+        }
+    }
+
+    pub fn with_shadows(mut self) -> Self {
+        self.casts_shadows = true;
+        self
+    }
+}
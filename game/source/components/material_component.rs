@@ -0,0 +1,335 @@
+use crate::core::texture::{ColourSpace, TextureOptions};
+use crate::utils::log;
+use wgpu::{BindGroup, Buffer};
+
+/// number of vec4 custom parameters available to material shaders
+pub const MATERIAL_PARAM_COUNT: usize = 4;
+
+/// number of texture slots in a material
+pub const MATERIAL_TEXTURE_COUNT: usize = 5;
+
+/// texture slots in a material, following the glTF 2.0 metallic-roughness model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaterialTextureSlot {
+    BaseColour,
+    // metalness in the blue channel, roughness in the green channel
+    MetallicRoughness,
+    Normal,
+    // ambient occlusion in the red channel
+    Occlusion,
+    Emissive,
+}
+
+impl MaterialTextureSlot {
+    pub const ALL: [MaterialTextureSlot; MATERIAL_TEXTURE_COUNT] = [
+        MaterialTextureSlot::BaseColour,
+        MaterialTextureSlot::MetallicRoughness,
+        MaterialTextureSlot::Normal,
+        MaterialTextureSlot::Occlusion,
+        MaterialTextureSlot::Emissive,
+    ];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// colour textures are stored as sRGB, data textures linearly
+    pub fn colour_space(&self) -> ColourSpace {
+        match self {
+            MaterialTextureSlot::BaseColour | MaterialTextureSlot::Emissive => ColourSpace::Srgb,
+            _ => ColourSpace::Linear,
+        }
+    }
+
+    /// texel used when the slot has no texture, chosen so it leaves the material's factors unchanged
+    pub fn default_texel(&self) -> [u8; 4] {
+        match self {
+            MaterialTextureSlot::Normal => [128, 128, 255, 255],
+            _ => [255, 255, 255, 255],
+        }
+    }
+}
+
+/// how lighting is calculated for a material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShadingModel {
+    BlinnPhong,
+    // cook-torrance ggx with image based lighting, see pbr.wgsl
+    Pbr,
+}
+
+/// how a material's output is combined with what's already in the colour target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Opaque,
+    AlphaBlend,
+    #[allow(dead_code)]
+    Additive,
+}
+
+impl BlendMode {
+    /// transparent materials are drawn after opaque ones, sorted back to front, without writing depth
+    pub fn is_transparent(&self) -> bool {
+        !matches!(self, BlendMode::Opaque)
+    }
+}
+
+/// which triangle faces are discarded when drawing with a material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    None,
+    Back,
+    #[allow(dead_code)]
+    Front,
+}
+
+/// uniform layout for a material - must match the Material struct in fragment.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MaterialUniforms {
+    pub base_colour: [f32; 4],
+    // specular strength, shininess, 1.0 if lit, alpha cutoff
+    pub lighting: [f32; 4],
+    pub params: [[f32; 4]; MATERIAL_PARAM_COUNT],
+    // metallic, roughness, occlusion strength, normal scale
+    pub pbr: [f32; 4],
+    // rgb emissive factor
+    pub emissive: [f32; 4],
+}
+
+/// everything that requires a distinct render pipeline - materials sharing a key share a pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaterialPipelineKey {
+    pub blend_mode: BlendMode,
+    pub cull_mode: CullMode,
+    pub shading: ShadingModel,
+    pub fragment_shader: Option<&'static str>,
+    // #define'd in both stages, @see ShaderLibrary::preprocess
+    pub defines: Vec<&'static str>,
+}
+
+impl Default for MaterialPipelineKey {
+    fn default() -> Self {
+        Self {
+            blend_mode: BlendMode::Opaque,
+            cull_mode: CullMode::None,
+            shading: ShadingModel::BlinnPhong,
+            fragment_shader: None,
+            defines: Vec::new(),
+        }
+    }
+}
+
+pub struct MaterialComponent {
+    pub base_colour: [f32; 4],
+    // path or registered name of the texture in each slot, indexed by MaterialTextureSlot
+    pub textures: [Option<String>; MATERIAL_TEXTURE_COUNT],
+    // colour space is chosen per slot, the rest of these options apply to every texture
+    pub texture_options: TextureOptions,
+    pub shading: ShadingModel,
+    // metallic-roughness factors, multiplied with the matching textures
+    pub metallic: f32,
+    pub roughness: f32,
+    pub occlusion_strength: f32,
+    pub normal_scale: f32,
+    pub emissive: [f32; 3],
+    // blinn-phong specular highlight strength and exponent
+    pub specular: f32,
+    pub shininess: f32,
+    // unlit materials output their colour as-is, ignoring lights
+    pub lit: bool,
+    // fragments with alpha below this are discarded, for cutouts such as foliage in opaque materials
+    pub alpha_cutoff: Option<f32>,
+    // free-form parameters for custom fragment shaders
+    pub params: [[f32; 4]; MATERIAL_PARAM_COUNT],
+    pub blend_mode: BlendMode,
+    pub cull_mode: CullMode,
+    // wgsl source for a custom fragment shader, using the same bindings as fragment.wgsl - it can
+    // #include their declarations from shaders/common, and returns FragmentOutput to be pickable
+    pub fragment_shader: Option<&'static str>,
+    // shader feature toggles - materials with different defines get their own pipelines
+    pub defines: Vec<&'static str>,
+    pub uniform_buffer: Option<Buffer>,
+    pub bind_group: Option<BindGroup>,
+    pub needs_rebuffer: bool,
+}
+
+// builders and setters for game code, the test world only uses some of them
+#[allow(dead_code)]
+impl MaterialComponent {
+    pub fn new(base_colour: [f32; 4]) -> Self {
+        Self {
+            base_colour,
+            textures: Default::default(),
+            texture_options: TextureOptions::default(),
+            shading: ShadingModel::BlinnPhong,
+            metallic: 0.0,
+            roughness: 0.5,
+            occlusion_strength: 1.0,
+            normal_scale: 1.0,
+            emissive: [0.0, 0.0, 0.0],
+            specular: 0.5,
+            shininess: 32.0,
+            lit: true,
+            alpha_cutoff: None,
+            params: [[0.0; 4]; MATERIAL_PARAM_COUNT],
+            blend_mode: BlendMode::Opaque,
+            cull_mode: CullMode::None,
+            fragment_shader: None,
+            defines: Vec::new(),
+            uniform_buffer: None,
+            bind_group: None,
+            needs_rebuffer: true,
+        }
+    }
+
+    /// Create a physically based material using the metallic-roughness model
+    pub fn pbr(base_colour: [f32; 4], metallic: f32, roughness: f32) -> Self {
+        let mut material = Self::new(base_colour);
+
+        material.shading = ShadingModel::Pbr;
+        material.metallic = metallic;
+        material.roughness = roughness;
+
+        material
+    }
+
+    pub fn with_texture(self, path: &str) -> Self {
+        self.with_slot_texture(MaterialTextureSlot::BaseColour, path)
+    }
+
+    pub fn with_slot_texture(mut self, slot: MaterialTextureSlot, path: &str) -> Self {
+        self.textures[slot.index()] = Some(path.to_string());
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    pub fn with_texture_options(mut self, texture_options: TextureOptions) -> Self {
+        self.texture_options = texture_options;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f32, shininess: f32) -> Self {
+        self.specular = specular;
+        self.shininess = shininess;
+        self
+    }
+
+    pub fn unlit(mut self) -> Self {
+        self.lit = false;
+        self
+    }
+
+    pub fn with_alpha_cutoff(mut self, alpha_cutoff: f32) -> Self {
+        self.alpha_cutoff = Some(alpha_cutoff);
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: CullMode) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_fragment_shader(mut self, fragment_shader: &'static str) -> Self {
+        self.fragment_shader = Some(fragment_shader);
+        self
+    }
+
+    /// Turn on a feature in the material's shaders, checked with #ifdef
+    pub fn with_define(mut self, define: &'static str) -> Self {
+        if !self.defines.contains(&define) {
+            self.defines.push(define);
+        }
+
+        self
+    }
+
+    pub fn set_base_colour(&mut self, base_colour: [f32; 4]) {
+        self.base_colour = base_colour;
+
+        self.needs_rebuffer = true;
+    }
+
+    pub fn set_base_colour_texture(&mut self, path: Option<&str>) {
+        self.set_slot_texture(MaterialTextureSlot::BaseColour, path);
+    }
+
+    pub fn set_slot_texture(&mut self, slot: MaterialTextureSlot, path: Option<&str>) {
+        self.textures[slot.index()] = path.map(|path| path.to_string());
+
+        self.needs_rebuffer = true;
+    }
+
+    pub fn set_metallic_roughness(&mut self, metallic: f32, roughness: f32) {
+        self.metallic = metallic;
+        self.roughness = roughness;
+
+        self.needs_rebuffer = true;
+    }
+
+    pub fn set_alpha_cutoff(&mut self, alpha_cutoff: Option<f32>) {
+        self.alpha_cutoff = alpha_cutoff;
+
+        self.needs_rebuffer = true;
+    }
+
+    pub fn set_param(&mut self, index: usize, value: [f32; 4]) {
+        if index >= MATERIAL_PARAM_COUNT {
+            log::warn(&format!(
+                "material param {} is out of range, there are {}",
+                index, MATERIAL_PARAM_COUNT
+            ));
+            return;
+        }
+
+        self.params[index] = value;
+
+        self.needs_rebuffer = true;
+    }
+
+    pub fn uniforms(&self) -> MaterialUniforms {
+        MaterialUniforms {
+            base_colour: self.base_colour,
+            lighting: [
+                self.specular,
+                self.shininess,
+                if self.lit { 1.0 } else { 0.0 },
+                self.alpha_cutoff.unwrap_or(0.0),
+            ],
+            params: self.params,
+            pbr: [
+                self.metallic,
+                self.roughness,
+                self.occlusion_strength,
+                self.normal_scale,
+            ],
+            emissive: [self.emissive[0], self.emissive[1], self.emissive[2], 0.0],
+        }
+    }
+
+    pub fn pipeline_key(&self) -> MaterialPipelineKey {
+        let mut defines = self.defines.clone();
+
+        // only cutouts pay for the alpha test
+        if self.alpha_cutoff.is_some() && !defines.contains(&"ALPHA_CUTOUT") {
+            defines.push("ALPHA_CUTOUT");
+        }
+
+        MaterialPipelineKey {
+            blend_mode: self.blend_mode,
+            cull_mode: self.cull_mode,
+            shading: self.shading,
+            fragment_shader: self.fragment_shader,
+            defines,
+        }
+    }
+}
@@ -1,6 +1,36 @@
 use crate::core::geometry;
 use wgpu::Buffer;
 
+/// when a level of detail takes over from the one before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LodThreshold {
+    // the camera is at least this far from the mesh, in world units
+    Distance(f32),
+    // the mesh's bounding sphere covers at most this fraction of the screen's height
+    ScreenSize(f32),
+}
+
+impl LodThreshold {
+    /// Whether a mesh at this distance and screen size is past the threshold - scale moves the
+    /// threshold further away above 1.0 and closer below it
+    pub fn passed(&self, distance: f32, screen_size: f32, scale: f32) -> bool {
+        match *self {
+            LodThreshold::Distance(threshold) => distance >= threshold * scale,
+            LodThreshold::ScreenSize(threshold) => screen_size <= threshold / scale,
+        }
+    }
+}
+
+/// a cheaper version of a mesh, for drawing it further away
+pub struct MeshLod {
+    pub vertices: Vec<geometry::Vertex>,
+    pub indices: Vec<u16>,
+    pub threshold: LodThreshold,
+    pub vertex_buffer: Option<Buffer>,
+    pub index_buffer: Option<Buffer>,
+    pub num_indices: u32,
+}
+
 pub struct MeshComponent {
     pub last_vertices: Option<Vec<geometry::Vertex>>,
     pub last_indices: Option<Vec<u16>>,
@@ -8,6 +38,24 @@ pub struct MeshComponent {
     pub index_buffer: Option<Buffer>,
     pub needs_rebuffer: bool,
     pub num_indices: u32,
+    pub casts_shadows: bool,
+    pub receives_shadows: bool,
+    // coarser meshes after this one, in order - level 0 is this mesh, level 1 the first of these
+    pub lods: Vec<MeshLod>,
+    // fraction a threshold has to be passed by before switching, so meshes near it don't flicker
+    // between levels
+    pub lod_hysteresis: f32,
+    // seconds to dither from one level to the next, 0.0 to switch straight away
+    pub lod_fade_time: f32,
+
+    // set by the MeshRenderer
+    pub lod: usize,
+    // the level being faded out, and how far through the fade it is from 0.0 to 1.0
+    pub previous_lod: Option<usize>,
+    pub lod_fade: f32,
+    // world space bounds, and the position, rotation and scale they were calculated with - so
+    // they're only calculated again when the mesh or its transform changes
+    pub bounds: Option<([geometry::Vector3; 3], geometry::BoundingBox)>,
 }
 
 impl MeshComponent {
@@ -19,13 +67,155 @@ impl MeshComponent {
             index_buffer: None,
             needs_rebuffer: true,
             num_indices: 0,
+            casts_shadows: true,
+            receives_shadows: true,
+            lods: Vec::new(),
+            lod_hysteresis: 0.1,
+            lod_fade_time: 0.0,
+            lod: 0,
+            previous_lod: None,
+            lod_fade: 1.0,
+            bounds: None,
+        }
+    }
+
+    pub fn with_shadows(mut self, casts_shadows: bool, receives_shadows: bool) -> Self {
+        self.casts_shadows = casts_shadows;
+        self.receives_shadows = receives_shadows;
+        self
+    }
+
+    /// Add the next level of detail, used once the threshold is passed
+    pub fn with_lod(
+        mut self,
+        vertices: Vec<geometry::Vertex>,
+        indices: Vec<u16>,
+        threshold: LodThreshold,
+    ) -> Self {
+        self.lods.push(MeshLod {
+            vertices,
+            indices,
+            threshold,
+            vertex_buffer: None,
+            index_buffer: None,
+            num_indices: 0,
+        });
+        self.needs_rebuffer = true;
+        self
+    }
+
+    pub fn with_lod_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.lod_hysteresis = hysteresis;
+        self
+    }
+
+    pub fn with_lod_fade(mut self, seconds: f32) -> Self {
+        self.lod_fade_time = seconds;
+        self
+    }
+
+    /// Vertex buffer, index buffer and index count for a level of detail, once buffered
+    pub fn buffers(&self, lod: usize) -> Option<(&Buffer, &Buffer, u32)> {
+        let (vertex_buffer, index_buffer, num_indices) = match lod {
+            0 => (&self.vertex_buffer, &self.index_buffer, self.num_indices),
+            _ => {
+                let level = self.lods.get(lod - 1)?;
+                (&level.vertex_buffer, &level.index_buffer, level.num_indices)
+            }
+        };
+
+        Some((vertex_buffer.as_ref()?, index_buffer.as_ref()?, num_indices))
+    }
+
+    /// The level of detail for a mesh at this distance and screen size, given the level it's at -
+    /// levels further from the current one must pass their thresholds by the hysteresis
+    pub fn select_lod(&self, distance: f32, screen_size: f32) -> usize {
+        let mut selected = 0;
+
+        for (index, level) in self.lods.iter().enumerate() {
+            let lod = index + 1;
+
+            // going coarser needs to pass the threshold by more, going finer to come back by more
+            let scale = if lod > self.lod {
+                1.0 + self.lod_hysteresis
+            } else {
+                1.0 - self.lod_hysteresis
+            };
+
+            if level.threshold.passed(distance, screen_size, scale) {
+                selected = lod;
+            }
         }
+
+        selected
     }
 
     pub fn _update(&mut self, vertices: Vec<geometry::Vertex>, indices: Vec<u16>) {
         self.last_vertices = Some(vertices);
         self.last_indices = Some(indices);
+        self.bounds = None;
 
         self.needs_rebuffer = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Levels taking over at the given thresholds, with 10% hysteresis
+    fn mesh(thresholds: &[LodThreshold]) -> MeshComponent {
+        thresholds
+            .iter()
+            .fold(
+                MeshComponent::new(Vec::new(), Vec::new()),
+                |mesh, &threshold| mesh.with_lod(Vec::new(), Vec::new(), threshold),
+            )
+            .with_lod_hysteresis(0.1)
+    }
+
+    #[test]
+    fn distance_lods_switch_past_the_hysteresis() {
+        let mut mesh = mesh(&[LodThreshold::Distance(10.0), LodThreshold::Distance(20.0)]);
+
+        assert_eq!(mesh.select_lod(10.5, 1.0), 0);
+        assert_eq!(mesh.select_lod(11.0, 1.0), 1);
+
+        mesh.lod = 1;
+
+        // coming back needs to be closer than the threshold by as much
+        assert_eq!(mesh.select_lod(9.5, 1.0), 1);
+        assert_eq!(mesh.select_lod(8.9, 1.0), 0);
+    }
+
+    #[test]
+    fn distance_lods_can_skip_levels() {
+        let mut mesh = mesh(&[LodThreshold::Distance(10.0), LodThreshold::Distance(20.0)]);
+
+        assert_eq!(mesh.select_lod(25.0, 1.0), 2);
+
+        mesh.lod = 2;
+
+        assert_eq!(mesh.select_lod(1.0, 1.0), 0);
+    }
+
+    #[test]
+    fn screen_size_lods_switch_past_the_hysteresis() {
+        let mut mesh = mesh(&[LodThreshold::ScreenSize(0.5)]);
+
+        assert_eq!(mesh.select_lod(0.0, 0.46), 0);
+        assert_eq!(mesh.select_lod(0.0, 0.45), 1);
+
+        mesh.lod = 1;
+
+        assert_eq!(mesh.select_lod(0.0, 0.54), 1);
+        assert_eq!(mesh.select_lod(0.0, 0.56), 0);
+    }
+
+    #[test]
+    fn meshes_without_lods_stay_at_the_base_level() {
+        let mesh = mesh(&[]);
+
+        assert_eq!(mesh.select_lod(1000.0, 0.0), 0);
+    }
+}
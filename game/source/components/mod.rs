@@ -1,4 +1,13 @@
+pub mod boid_component;
 pub mod collider_component;
+pub mod light_component;
+pub mod material_component;
 pub mod mesh_component;
 pub mod movement_component;
+pub mod particle_emitter_component;
+pub mod sprite_animation_component;
+pub mod sprite_component;
+pub mod text_label_component;
+pub mod tilemap_component;
 pub mod transform_component;
+pub mod ui_node_component;
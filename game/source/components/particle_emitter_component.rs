@@ -0,0 +1,160 @@
+use crate::core::particles::{Curve, Particle, ParticleBuffers};
+
+/// a number of particles spawned at once, some time after the emitter starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleBurst {
+    // seconds after the emitter starts
+    pub time: f32,
+    pub count: u32,
+    // repeats this often after the first, if set
+    pub interval: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticleBlend {
+    // brightens what's behind - fire, sparks, magic
+    Additive,
+    // covers what's behind - smoke, dust. only sorted back to front when simulated on the cpu,
+    // gpu simulated particles are drawn in the order they sit in the buffer
+    Alpha,
+}
+
+/// where particles are moved each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleSimulation {
+    // simple, and the particles can be read back by other systems
+    Cpu,
+    // a compute shader moves them, for emitters with many particles - spawning stays on the cpu.
+    // they aren't sorted, so alpha blended emitters should simulate on the cpu
+    Gpu,
+}
+
+/// spawns camera facing particles from the entity's position
+pub struct ParticleEmitterComponent {
+    // particles per second while emitting
+    pub spawn_rate: f32,
+    pub bursts: Vec<ParticleBurst>,
+    // min and max seconds each particle lives
+    pub lifetime: [f32; 2],
+    // min and max starting speed, in world units per second
+    pub speed: [f32; 2],
+    // particles leave along this direction, spread randomly within the cone angle, in radians
+    pub direction: [f32; 3],
+    pub cone_angle: f32,
+    // acceleration applied to every particle
+    pub gravity: [f32; 3],
+    pub colour: Curve<[f32; 4]>,
+    // world units across
+    pub size: Curve<f32>,
+    // most particles alive at once - on the cpu no more spawn, on the gpu the oldest are replaced
+    pub max_particles: u32,
+    pub blend: ParticleBlend,
+    pub simulation: ParticleSimulation,
+    // stops the spawn rate and bursts, leaving live particles to finish
+    pub emitting: bool,
+
+    // set by the ParticleSystem
+    // seconds since the emitter started
+    pub age: f32,
+    // fractional particles carried over between frames
+    pub spawn_accumulator: f32,
+    // times each burst has fired
+    pub bursts_fired: Vec<u32>,
+    pub random_state: u32,
+    // live particles, when simulated on the cpu
+    pub particles: Vec<Particle>,
+    // spawned since the ParticleBufferer last ran, and how far the gpu has to simulate
+    pub pending_spawns: Vec<Particle>,
+    pub pending_time: f32,
+    // next slot in the storage buffer for gpu particles, which is used as a ring
+    pub next_slot: u32,
+
+    // set by the ParticleBufferer
+    pub buffers: Option<ParticleBuffers>,
+}
+
+impl ParticleEmitterComponent {
+    pub fn new(spawn_rate: f32, max_particles: u32) -> Self {
+        Self {
+            spawn_rate,
+            bursts: Vec::new(),
+            lifetime: [1.0, 2.0],
+            speed: [1.0, 2.0],
+            direction: [0.0, 1.0, 0.0],
+            cone_angle: 0.3,
+            gravity: [0.0, -9.81, 0.0],
+            colour: Curve::linear([1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 0.0]),
+            size: Curve::constant(0.1),
+            max_particles,
+            blend: ParticleBlend::Additive,
+            simulation: ParticleSimulation::Cpu,
+            emitting: true,
+            age: 0.0,
+            spawn_accumulator: 0.0,
+            bursts_fired: Vec::new(),
+            random_state: 0x9e37_79b9,
+            particles: Vec::new(),
+            pending_spawns: Vec::new(),
+            pending_time: 0.0,
+            next_slot: 0,
+            buffers: None,
+        }
+    }
+
+    pub fn with_burst(mut self, time: f32, count: u32, interval: Option<f32>) -> Self {
+        self.bursts.push(ParticleBurst {
+            time,
+            count,
+            interval,
+        });
+        self
+    }
+
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = [min, max];
+        self
+    }
+
+    pub fn with_velocity(mut self, direction: [f32; 3], cone_angle: f32, speed: [f32; 2]) -> Self {
+        self.direction = direction;
+        self.cone_angle = cone_angle;
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_gravity(mut self, gravity: [f32; 3]) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_colour(mut self, colour: Curve<[f32; 4]>) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    pub fn with_size(mut self, size: Curve<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_blend(mut self, blend: ParticleBlend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_simulation(mut self, simulation: ParticleSimulation) -> Self {
+        self.simulation = simulation;
+        self
+    }
+
+    /// A pseudo random number from 0.0 to 1.0 - xorshift, so emitters are repeatable
+    pub fn random(&mut self) -> f32 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+/// shortest time a frame is shown, so a clip always takes time to play through
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+/// a sequence of regions of a sprite's texture, each shown for a time
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteClip {
+    // x, y, width and height in texels
+    pub frames: Vec<[u32; 4]>,
+    // seconds each frame is shown - the last duration is used for any frames past the end
+    pub durations: Vec<f32>,
+    pub looping: bool,
+}
+
+impl SpriteClip {
+    pub fn new(frames: Vec<[u32; 4]>, frame_duration: f32, looping: bool) -> Self {
+        Self {
+            frames,
+            durations: vec![frame_duration.max(MIN_FRAME_DURATION)],
+            looping,
+        }
+    }
+
+    /// Frames from a sprite sheet laid out in a grid, counting left to right then top to bottom
+    pub fn from_grid(
+        frame_size: [u32; 2],
+        columns: u32,
+        first: u32,
+        count: u32,
+        frames_per_second: f32,
+        looping: bool,
+    ) -> Self {
+        let frames = (first..first + count)
+            .map(|index| {
+                [
+                    (index % columns.max(1)) * frame_size[0],
+                    (index / columns.max(1)) * frame_size[1],
+                    frame_size[0],
+                    frame_size[1],
+                ]
+            })
+            .collect();
+
+        Self::new(frames, 1.0 / frames_per_second.max(f32::EPSILON), looping)
+    }
+
+    pub fn with_durations(mut self, durations: Vec<f32>) -> Self {
+        self.durations = durations
+            .into_iter()
+            .map(|duration| duration.max(MIN_FRAME_DURATION))
+            .collect();
+        self
+    }
+
+    /// Seconds a frame is shown - never zero, durations can be set directly
+    pub fn duration(&self, frame: usize) -> f32 {
+        self.durations
+            .get(frame)
+            .or(self.durations.last())
+            .copied()
+            .unwrap_or(0.1)
+            .max(MIN_FRAME_DURATION)
+    }
+}
+
+/// plays clips on the entity's SpriteComponent by changing its region
+pub struct SpriteAnimationComponent {
+    pub clips: HashMap<String, SpriteClip>,
+    pub current: Option<String>,
+    // multiplies the clip's frame times
+    pub speed: f32,
+    pub playing: bool,
+
+    // set by the SpriteSystem
+    pub frame: usize,
+    // seconds into the current frame
+    pub frame_time: f32,
+    // a clip that doesn't loop has reached its last frame
+    pub finished: bool,
+}
+
+impl SpriteAnimationComponent {
+    pub fn new() -> Self {
+        Self {
+            clips: HashMap::new(),
+            current: None,
+            speed: 1.0,
+            playing: true,
+            frame: 0,
+            frame_time: 0.0,
+            finished: false,
+        }
+    }
+
+    pub fn with_clip(mut self, name: &str, clip: SpriteClip) -> Self {
+        // the first clip added plays straight away
+        if self.current.is_none() {
+            self.current = Some(name.to_string());
+        }
+
+        self.clips.insert(name.to_string(), clip);
+        self
+    }
+
+    /// Switch to a clip from its first frame - carrying on if it's already the current clip, so it's
+    /// safe to call every frame, and a clip that doesn't loop stays finished
+    pub fn play(&mut self, name: &str) {
+        self.playing = true;
+
+        if self.current.as_deref() == Some(name) {
+            return;
+        }
+
+        self.current = Some(name.to_string());
+        self.frame = 0;
+        self.frame_time = 0.0;
+        self.finished = false;
+    }
+}
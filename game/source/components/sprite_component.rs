@@ -0,0 +1,82 @@
+use crate::core::texture::Texture;
+use std::sync::Arc;
+
+/// a textured quad in the 2d world, drawn with the orthographic camera
+pub struct SpriteComponent {
+    pub texture: String,
+    // x, y, width and height in texels, the whole texture if none
+    pub region: Option<[u32; 4]>,
+    pub tint: [f32; 4],
+    pub flip_x: bool,
+    pub flip_y: bool,
+    // the point on the sprite at the entity's position - [0, 0] bottom left, [1, 1] top right
+    pub anchor: [f32; 2],
+    // sprites with higher z orders are drawn over lower ones
+    pub z_order: i32,
+    // texels per world unit, scaled further by the transform
+    pub pixels_per_unit: f32,
+    // nearest filtering, for pixel art
+    pub pixelated: bool,
+
+    // loaded by the SpriteSystem
+    pub loaded_texture: Option<Arc<Texture>>,
+    pub needs_texture: bool,
+}
+
+impl SpriteComponent {
+    pub fn new(texture: &str) -> Self {
+        Self {
+            texture: texture.to_string(),
+            region: None,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            flip_x: false,
+            flip_y: false,
+            anchor: [0.5, 0.5],
+            z_order: 0,
+            pixels_per_unit: 1.0,
+            pixelated: false,
+            loaded_texture: None,
+            needs_texture: true,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_region(mut self, region: [u32; 4]) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_anchor(mut self, anchor: [f32; 2]) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn with_z_order(mut self, z_order: i32) -> Self {
+        self.z_order = z_order;
+        self
+    }
+
+    pub fn with_pixels_per_unit(mut self, pixels_per_unit: f32) -> Self {
+        self.pixels_per_unit = pixels_per_unit;
+        self
+    }
+
+    pub fn pixelated(mut self) -> Self {
+        self.pixelated = true;
+        self
+    }
+
+    /// Use a different texture, loaded on the next update
+    #[allow(dead_code)]
+    pub fn set_texture(&mut self, texture: &str) {
+        self.texture = texture.to_string();
+        self.needs_texture = true;
+    }
+}
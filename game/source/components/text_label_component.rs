@@ -0,0 +1,32 @@
+use crate::core::text::TextStyle;
+
+/// text drawn in the world above an entity, always facing the camera
+pub struct TextLabelComponent {
+    pub text: String,
+    pub style: TextStyle,
+    // from the entity's transform position, in world units
+    pub offset: [f32; 3],
+    // world units for each pixel of the style's size - sdf fonts stay sharp when this is large
+    pub scale: f32,
+}
+
+impl TextLabelComponent {
+    pub fn new(text: &str, style: TextStyle) -> Self {
+        Self {
+            text: text.to_string(),
+            style,
+            offset: [0.0, 1.0, 0.0],
+            scale: 0.01,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: [f32; 3]) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
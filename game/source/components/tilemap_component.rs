@@ -0,0 +1,323 @@
+use crate::core::texture::Texture;
+use crate::core::tilemap::{TileCollision, TilemapChunk, CHUNK_SIZE};
+use std::sync::Arc;
+
+/// a grid of tiles from a tileset texture, with tile [0, 0] at the bottom left corner at the
+/// entity's position and rows running up. the transform's rotation is ignored.
+pub struct TilemapComponent {
+    pub tileset: String,
+    // texels per tile in the tileset, whose tiles are indexed left to right then top to bottom
+    pub tile_size: [u32; 2],
+    // in tiles
+    pub width: u32,
+    pub height: u32,
+    // tileset index of each tile, row by row from the bottom, none for empty
+    tiles: Vec<Option<u32>>,
+    // texels per world unit, scaled further by the transform
+    pub pixels_per_unit: f32,
+    pub collision: TileCollision,
+    pub tint: [f32; 4],
+    // drawn in order with the sprites
+    pub z_order: i32,
+    // nearest filtering - linear filtering can bleed neighbouring tiles in unpadded tilesets
+    pub pixelated: bool,
+
+    // set by the TilemapBufferer
+    pub loaded_texture: Option<Arc<Texture>>,
+    pub needs_texture: bool,
+    pub chunks: Vec<TilemapChunk>,
+    // position and scale the chunks were meshed with, so moving the tilemap meshes them again
+    pub meshed_transform: Option<([f32; 3], [f32; 3])>,
+}
+
+impl TilemapComponent {
+    /// Panics if either side of the tile size is zero, as tiles would have no size in the world
+    pub fn new(tileset: &str, tile_size: [u32; 2], width: u32, height: u32) -> Self {
+        assert!(
+            tile_size[0] > 0 && tile_size[1] > 0,
+            "tilemap tiles need a size, got {:?}",
+            tile_size
+        );
+
+        let chunk_count = width.div_ceil(CHUNK_SIZE) * height.div_ceil(CHUNK_SIZE);
+
+        Self {
+            tileset: tileset.to_string(),
+            tile_size,
+            width,
+            height,
+            tiles: vec![None; (width * height) as usize],
+            pixels_per_unit: tile_size[0].max(1) as f32,
+            collision: TileCollision::All,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            z_order: 0,
+            pixelated: true,
+            loaded_texture: None,
+            needs_texture: true,
+            chunks: (0..chunk_count).map(|_| TilemapChunk::new()).collect(),
+            meshed_transform: None,
+        }
+    }
+
+    /// Tiles given as rows of tileset indices from the top down, as they'd be laid out in a level
+    /// file - negative indices are empty
+    #[allow(dead_code)]
+    pub fn with_rows(mut self, rows: &[&[i32]]) -> Self {
+        for (row, tiles) in rows.iter().enumerate() {
+            let y = match (self.height as usize).checked_sub(row + 1) {
+                Some(y) => y as u32,
+                None => break,
+            };
+
+            for (x, &tile) in tiles.iter().enumerate() {
+                self.set_tile(x as u32, y, u32::try_from(tile).ok());
+            }
+        }
+
+        self
+    }
+
+    pub fn with_pixels_per_unit(mut self, pixels_per_unit: f32) -> Self {
+        self.pixels_per_unit = pixels_per_unit;
+        self
+    }
+
+    pub fn with_collision(mut self, collision: TileCollision) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    pub fn with_z_order(mut self, z_order: i32) -> Self {
+        self.z_order = z_order;
+        self
+    }
+
+    pub fn tile(&self, x: u32, y: u32) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    /// Change a tile, meshing its chunk again before the next draw
+    pub fn set_tile(&mut self, x: u32, y: u32, tile: Option<u32>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = (y * self.width + x) as usize;
+
+        if self.tiles[index] != tile {
+            self.tiles[index] = tile;
+
+            let chunk = self.chunk_index(x / CHUNK_SIZE, y / CHUNK_SIZE);
+            self.chunks[chunk].dirty = true;
+        }
+    }
+
+    /// Set every tile from min to max, inclusive
+    pub fn fill(&mut self, min: [u32; 2], max: [u32; 2], tile: Option<u32>) {
+        for y in min[1]..=max[1].min(self.height.saturating_sub(1)) {
+            for x in min[0]..=max[0].min(self.width.saturating_sub(1)) {
+                self.set_tile(x, y, tile);
+            }
+        }
+    }
+
+    pub fn is_solid(&self, x: u32, y: u32) -> bool {
+        match (self.tile(x, y), &self.collision) {
+            (None, _) | (_, TileCollision::None) => false,
+            (Some(_), TileCollision::All) => true,
+            (Some(tile), TileCollision::Tiles(solid)) => solid.contains(&tile),
+        }
+    }
+
+    /// Chunks across and up
+    pub fn chunk_counts(&self) -> [u32; 2] {
+        [
+            self.width.div_ceil(CHUNK_SIZE),
+            self.height.div_ceil(CHUNK_SIZE),
+        ]
+    }
+
+    pub fn chunk_index(&self, chunk_x: u32, chunk_y: u32) -> usize {
+        (chunk_y * self.chunk_counts()[0] + chunk_x) as usize
+    }
+
+    /// A tile's width and height in world units, with the transform's scale
+    pub fn tile_world_size(&self, scale: [f32; 3]) -> [f32; 2] {
+        let pixels_per_unit = self.pixels_per_unit.max(f32::EPSILON);
+
+        [
+            self.tile_size[0] as f32 / pixels_per_unit * scale[0],
+            self.tile_size[1] as f32 / pixels_per_unit * scale[1],
+        ]
+    }
+
+    /// Min and max corners of a tile in world units
+    pub fn tile_bounds(
+        &self,
+        position: [f32; 3],
+        scale: [f32; 3],
+        x: u32,
+        y: u32,
+    ) -> [[f32; 2]; 2] {
+        let size = self.tile_world_size(scale);
+        let min = [
+            position[0] + x as f32 * size[0],
+            position[1] + y as f32 * size[1],
+        ];
+
+        [min, [min[0] + size[0], min[1] + size[1]]]
+    }
+
+    /// The tile under a world position, if it's on the map
+    #[allow(dead_code)]
+    pub fn world_to_tile(
+        &self,
+        position: [f32; 3],
+        scale: [f32; 3],
+        point: [f32; 2],
+    ) -> Option<[u32; 2]> {
+        let size = self.tile_world_size(scale);
+
+        if size[0] <= 0.0 || size[1] <= 0.0 {
+            return None;
+        }
+
+        let x = ((point[0] - position[0]) / size[0]).floor();
+        let y = ((point[1] - position[1]) / size[1]).floor();
+
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+
+        Some([x as u32, y as u32])
+    }
+
+    /// Tiles overlapping a world space rectangle, clamped to the map - min and max inclusive
+    pub fn tiles_overlapping(
+        &self,
+        position: [f32; 3],
+        scale: [f32; 3],
+        [min, max]: [[f32; 2]; 2],
+    ) -> Option<[[u32; 2]; 2]> {
+        let size = self.tile_world_size(scale);
+
+        // nothing to overlap, and a zero scale would divide by zero
+        if self.width == 0 || self.height == 0 || size[0] <= 0.0 || size[1] <= 0.0 {
+            return None;
+        }
+
+        let first = [
+            ((min[0] - position[0]) / size[0]).floor(),
+            ((min[1] - position[1]) / size[1]).floor(),
+        ];
+        let last = [
+            ((max[0] - position[0]) / size[0]).ceil() - 1.0,
+            ((max[1] - position[1]) / size[1]).ceil() - 1.0,
+        ];
+
+        if last[0] < 0.0
+            || last[1] < 0.0
+            || first[0] >= self.width as f32
+            || first[1] >= self.height as f32
+        {
+            return None;
+        }
+
+        Some([
+            [first[0].max(0.0) as u32, first[1].max(0.0) as u32],
+            [
+                (last[0] as u32).min(self.width - 1),
+                (last[1] as u32).min(self.height - 1),
+            ],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: [f32; 3] = [0.0, 0.0, 0.0];
+    const SCALE: [f32; 3] = [1.0, 1.0, 1.0];
+
+    /// Tiles of 16 texels, 2 texels to a world unit, so each is 8 units across
+    fn tilemap(width: u32, height: u32) -> TilemapComponent {
+        TilemapComponent::new("tileset.png", [16, 16], width, height).with_pixels_per_unit(2.0)
+    }
+
+    #[test]
+    fn with_rows_reads_from_the_top_down() {
+        let tilemap = tilemap(4, 3).with_rows(&[&[1, -1], &[], &[0, 2, 3, 4, 5]]);
+
+        assert_eq!(tilemap.tile(0, 2), Some(1));
+        assert_eq!(tilemap.tile(1, 2), None);
+        assert_eq!(tilemap.tile(0, 0), Some(0));
+        assert_eq!(tilemap.tile(3, 0), Some(4));
+    }
+
+    #[test]
+    fn tiles_overlapping_covers_partly_overlapped_tiles() {
+        let tilemap = tilemap(4, 3);
+
+        assert_eq!(
+            tilemap.tiles_overlapping(ORIGIN, SCALE, [[4.0, 4.0], [17.0, 8.0]]),
+            Some([[0, 0], [2, 0]])
+        );
+    }
+
+    #[test]
+    fn tiles_overlapping_is_clamped_to_the_map() {
+        let tilemap = tilemap(4, 3);
+
+        assert_eq!(
+            tilemap.tiles_overlapping(ORIGIN, SCALE, [[-20.0, -20.0], [100.0, 100.0]]),
+            Some([[0, 0], [3, 2]])
+        );
+        assert_eq!(
+            tilemap.tiles_overlapping(ORIGIN, SCALE, [[40.0, 0.0], [50.0, 8.0]]),
+            None
+        );
+        assert_eq!(
+            tilemap.tiles_overlapping(ORIGIN, SCALE, [[-10.0, 0.0], [0.0, 8.0]]),
+            None
+        );
+    }
+
+    #[test]
+    fn tiles_overlapping_an_empty_map_or_with_no_scale_is_none() {
+        let rect = [[-20.0, -20.0], [100.0, 100.0]];
+
+        assert_eq!(tilemap(0, 3).tiles_overlapping(ORIGIN, SCALE, rect), None);
+        assert_eq!(tilemap(4, 0).tiles_overlapping(ORIGIN, SCALE, rect), None);
+        assert_eq!(
+            tilemap(4, 3).tiles_overlapping(ORIGIN, [0.0, 1.0, 1.0], rect),
+            None
+        );
+    }
+
+    #[test]
+    fn world_to_tile_follows_the_position_and_scale() {
+        let tilemap = tilemap(4, 3);
+
+        assert_eq!(
+            tilemap.world_to_tile(ORIGIN, SCALE, [9.0, 1.0]),
+            Some([1, 0])
+        );
+        assert_eq!(
+            tilemap.world_to_tile([-32.0, 0.0, 0.0], [2.0, 2.0, 1.0], [0.0, 20.0]),
+            Some([2, 1])
+        );
+        assert_eq!(tilemap.world_to_tile(ORIGIN, SCALE, [-1.0, 1.0]), None);
+        assert_eq!(tilemap.world_to_tile(ORIGIN, SCALE, [1.0, 24.0]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "tilemap tiles need a size")]
+    fn tiles_need_a_size() {
+        TilemapComponent::new("tileset.png", [16, 0], 4, 3);
+    }
+}
@@ -31,6 +31,11 @@ impl TransformComponent {
         self.model_matrix.to_cols_array()
     }
 
+    // Inverse transpose of the model matrix, so normals survive non-uniform scaling
+    pub fn normal_matrix_array(&self) -> [f32; 16] {
+        self.model_matrix.inverse().transpose().to_cols_array()
+    }
+
     pub fn translate(&mut self, translation: geometry::Vector3) {
         self.position[0] += translation[0];
         self.position[1] += translation[1];
@@ -0,0 +1,132 @@
+use crate::core::game::EntityId;
+use crate::core::text::TextStyle;
+use crate::core::texture::Texture;
+use crate::core::ui::{UiInteraction, UiRect, UiStyle};
+use std::sync::Arc;
+
+/// what a ui node draws in its rect
+pub enum UiContent {
+    // only lays out its children
+    None,
+    Panel {
+        colour: [f32; 4],
+    },
+    // stretched over the node, which is sized to the image when auto
+    Image {
+        path: String,
+        tint: [f32; 4],
+    },
+    Text {
+        text: String,
+        style: TextStyle,
+    },
+    // a panel that changes colour with the mouse, and reports clicks
+    Button {
+        colour: [f32; 4],
+        hover_colour: [f32; 4],
+        pressed_colour: [f32; 4],
+    },
+}
+
+/// a node in the screen space ui, laid out within its parent node or the window
+pub struct UiNodeComponent {
+    pub parent: Option<EntityId>,
+    pub style: UiStyle,
+    pub content: UiContent,
+    // hidden nodes hide their children too
+    pub visible: bool,
+    // set by the UiSystem each update
+    pub rect: UiRect,
+    pub interaction: UiInteraction,
+    // a button was pressed and released over this update
+    pub clicked: bool,
+    // loaded by the UiSystem for image nodes
+    pub texture: Option<Arc<Texture>>,
+    pub needs_texture: bool,
+}
+
+impl UiNodeComponent {
+    pub fn new(style: UiStyle, content: UiContent) -> Self {
+        let needs_texture = matches!(content, UiContent::Image { .. });
+
+        Self {
+            parent: None,
+            style,
+            content,
+            visible: true,
+            rect: UiRect::default(),
+            interaction: UiInteraction::None,
+            clicked: false,
+            texture: None,
+            needs_texture,
+        }
+    }
+
+    pub fn panel(style: UiStyle, colour: [f32; 4]) -> Self {
+        Self::new(style, UiContent::Panel { colour })
+    }
+
+    pub fn image(style: UiStyle, path: &str) -> Self {
+        Self::new(
+            style,
+            UiContent::Image {
+                path: path.to_string(),
+                tint: [1.0, 1.0, 1.0, 1.0],
+            },
+        )
+    }
+
+    pub fn text(style: UiStyle, text: &str, text_style: TextStyle) -> Self {
+        Self::new(
+            style,
+            UiContent::Text {
+                text: text.to_string(),
+                style: text_style,
+            },
+        )
+    }
+
+    /// A button whose hover and pressed colours are lightened and darkened from its colour
+    pub fn button(style: UiStyle, colour: [f32; 4]) -> Self {
+        let shade = |factor: f32| {
+            [
+                (colour[0] * factor).min(1.0),
+                (colour[1] * factor).min(1.0),
+                (colour[2] * factor).min(1.0),
+                colour[3],
+            ]
+        };
+
+        Self::new(
+            style,
+            UiContent::Button {
+                colour,
+                hover_colour: shade(1.3),
+                pressed_colour: shade(0.7),
+            },
+        )
+    }
+
+    pub fn with_parent(mut self, parent: EntityId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// The colour the node's rect is filled with, if it draws one
+    pub fn fill_colour(&self) -> Option<[f32; 4]> {
+        match &self.content {
+            UiContent::Panel { colour } => Some(*colour),
+            UiContent::Image { tint, .. } => Some(*tint),
+            UiContent::Button {
+                colour,
+                hover_colour,
+                pressed_colour,
+            } => Some(match self.interaction {
+                UiInteraction::None => *colour,
+                UiInteraction::Hovered => *hover_colour,
+                UiInteraction::Pressed => *pressed_colour,
+            }),
+            UiContent::None | UiContent::Text { .. } => None,
+        }
+    }
+}
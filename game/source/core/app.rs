@@ -1,5 +1,9 @@
 use crate::core::game;
+use crate::core::picking::PendingPick;
 use crate::core::renderer::Renderer;
+use crate::core::sky::{GradientSky, Sky};
+use crate::core::view_mode::ViewMode;
+use crate::utils::log;
 
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
@@ -16,6 +20,18 @@ impl App {
         return event_loop;
     }
 
+    /// Draw a sky, and light pbr materials with it when it's a cubemap so reflections match
+    fn set_sky(renderer: &mut Renderer, sky: &Sky) {
+        match sky {
+            Sky::Cubemap { texture, intensity } => {
+                renderer.set_environment_texture(texture.clone(), *intensity)
+            }
+            _ => renderer.disable_environment(),
+        }
+
+        renderer.set_sky(sky.clone());
+    }
+
     pub async fn run() {
         // init winit event loop
         let event_loop = Self::init_event_loop();
@@ -30,13 +46,57 @@ impl App {
         let renderer = Arc::new(RwLock::new(Renderer::new(&window).await));
         let world = Arc::new(RwLock::new(game::World::new()));
 
+        // 4x msaa is available on every adapter
+        if let Err(e) = renderer.write().unwrap().set_msaa_samples(4) {
+            log::error(&e);
+        }
+
+        // skies F9 cycles through - the procedural gradient, and any of the cubemap loaded from six
+        // faces or the hdr panorama that loaded
+        let skies = {
+            let mut renderer = renderer.write().unwrap();
+            let mut skies = vec![Sky::Gradient(GradientSky::default())];
+
+            let faces = [
+                "assets/sky/px.png",
+                "assets/sky/nx.png",
+                "assets/sky/py.png",
+                "assets/sky/ny.png",
+                "assets/sky/pz.png",
+                "assets/sky/nz.png",
+            ];
+
+            match renderer.set_sky_cubemap(&faces, 1.0) {
+                Ok(()) => skies.push(renderer.sky().clone()),
+                Err(e) => log::error(&e),
+            }
+
+            match renderer.set_sky_equirect("assets/sky/dusk.hdr", 256, 1.0) {
+                Ok(()) => skies.push(renderer.sky().clone()),
+                Err(e) => log::error(&e),
+            }
+
+            skies
+        };
+
+        // start on the first sky loaded from disk, if any were
+        let mut sky_index = skies.len().min(2) - 1;
+        Self::set_sky(&mut renderer.write().unwrap(), &skies[sky_index]);
+
+        // so clicking on the world picks the entity under the cursor
+        renderer.write().unwrap().set_picking_enabled(true);
+
         {
             let mut world = world.write().unwrap();
-            world.test_world();
+            let mut renderer = renderer.write().unwrap();
+            world.test_world(&mut renderer);
         }
 
         let mut last_frame = Instant::now();
 
+        // the last click's pick, until it's been read back
+        let mut pending_pick: Option<PendingPick> = None;
+
         // Add debug before each system run
         let result = event_loop.run(move |event, event_loop_window_target| {
             // calculate time since last frame
@@ -56,7 +116,18 @@ impl App {
                     // run update systems
                     let mut world = world.write().unwrap();
                     let mut renderer = renderer.write().unwrap();
+
+                    if let Some(pick) = pending_pick.as_mut() {
+                        if let Some(picked) = pick.poll(renderer.device()) {
+                            world.state().mouse.picked = picked;
+                            pending_pick = None;
+
+                            // @todo dispatch pick event to game event system
+                        }
+                    }
+
                     world.run_update_systems(&mut renderer);
+                    world.state().mouse.end_frame();
                 }
 
                 // handle events
@@ -94,6 +165,130 @@ impl App {
                                             // leaving this as an example
                                         }
 
+                                        // toggle drawing every collider's aabb
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F1,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let debug_draw = renderer.debug_draw_mut();
+
+                                            debug_draw.draw_colliders = !debug_draw.draw_colliders;
+                                        }
+
+                                        // debug view modes, pressing the current one again goes back to lit
+                                        winit::keyboard::PhysicalKey::Code(
+                                            code @ (winit::keyboard::KeyCode::F2
+                                            | winit::keyboard::KeyCode::F3
+                                            | winit::keyboard::KeyCode::F4
+                                            | winit::keyboard::KeyCode::F5),
+                                        ) => {
+                                            let view_mode = match code {
+                                                winit::keyboard::KeyCode::F2 => ViewMode::Wireframe,
+                                                winit::keyboard::KeyCode::F3 => ViewMode::Normals,
+                                                winit::keyboard::KeyCode::F4 => ViewMode::Depth,
+                                                _ => ViewMode::EntityId,
+                                            };
+
+                                            let mut renderer = renderer.write().unwrap();
+
+                                            if renderer.view_mode() == view_mode {
+                                                renderer.set_view_mode(ViewMode::Lit);
+                                            } else {
+                                                renderer.set_view_mode(view_mode);
+                                            }
+
+                                            println!("View mode: {:?}", renderer.view_mode());
+                                        }
+
+                                        // toggle the profiler overlay
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F6,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let profiler = renderer.profiler_mut();
+
+                                            profiler.set_overlay(!profiler.overlay());
+                                        }
+
+                                        // export the last few seconds of profiling for chrome://tracing
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F7,
+                                        ) => {
+                                            let renderer = renderer.read().unwrap();
+
+                                            match renderer.profiler().export_trace("trace.json") {
+                                                Ok(()) => println!("Wrote trace.json"),
+                                                Err(e) => log::error(&e),
+                                            }
+                                        }
+
+                                        // cycle through the tonemapping curves
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F8,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let mut settings = *renderer.hdr_settings();
+
+                                            settings.tonemapper = settings.tonemapper.next();
+                                            renderer.set_hdr_settings(settings);
+
+                                            println!("Tonemapper: {:?}", settings.tonemapper);
+                                        }
+
+                                        // cycle through the skies
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F9,
+                                        ) => {
+                                            sky_index = (sky_index + 1) % skies.len();
+
+                                            Self::set_sky(
+                                                &mut renderer.write().unwrap(),
+                                                &skies[sky_index],
+                                            );
+                                        }
+
+                                        // cycle the shadow map resolution
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F10,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let mut settings = *renderer.shadow_settings();
+
+                                            settings.resolution = match settings.resolution {
+                                                1024 => 2048,
+                                                2048 => 4096,
+                                                _ => 1024,
+                                            };
+                                            renderer.set_shadow_settings(settings);
+
+                                            println!("Shadow resolution: {}", settings.resolution);
+                                        }
+
+                                        // load the material shaders from the source tree and reload
+                                        // them as they're edited, or go back to the built in ones
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F11,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let hot_reload = !renderer.shader_hot_reload();
+
+                                            renderer.set_shader_hot_reload(hot_reload);
+
+                                            println!("Shader hot reload: {}", hot_reload);
+                                        }
+
+                                        // pause or resume profiling, keeping what's been recorded
+                                        winit::keyboard::PhysicalKey::Code(
+                                            winit::keyboard::KeyCode::F12,
+                                        ) => {
+                                            let mut renderer = renderer.write().unwrap();
+                                            let profiler = renderer.profiler_mut();
+
+                                            profiler.set_enabled(!profiler.enabled());
+
+                                            println!("Profiling: {}", profiler.enabled());
+                                        }
+
                                         _ => (),
                                     }
 
@@ -117,6 +312,33 @@ impl App {
                             // @todo dispatch key down event to game event system
                         }
 
+                        // track the cursor for the ui and picking
+                        winit::event::WindowEvent::CursorMoved { position, .. } => {
+                            let mut world = world.write().unwrap();
+                            world.state().mouse.position = [position.x as f32, position.y as f32];
+                        }
+
+                        // the ui sees every left click, the world only those not on the ui
+                        winit::event::WindowEvent::MouseInput {
+                            state,
+                            button: winit::event::MouseButton::Left,
+                            ..
+                        } => {
+                            let pressed = *state == winit::event::ElementState::Pressed;
+                            let mut world = world.write().unwrap();
+                            let mouse = &mut world.state().mouse;
+
+                            mouse.set_left(pressed);
+
+                            // pick the entity under the cursor, read back on a later frame
+                            if pressed && !mouse.over_ui {
+                                let [x, y] = mouse.position;
+                                let mut renderer = renderer.write().unwrap();
+
+                                pending_pick = renderer.pick(x as u32, y as u32);
+                            }
+                        }
+
                         // handle resize events
                         winit::event::WindowEvent::Resized(physical_size) => {
                             println!("Resized window to {:?}", physical_size);
@@ -0,0 +1,159 @@
+use crate::core::geometry;
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+/// a perspective camera looking at a target point
+pub struct Camera {
+    pub position: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    // vertical field of view in radians
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    pub fn new(position: Vec3, target: Vec3) -> Self {
+        Self {
+            position,
+            target,
+            up: Vec3::Y,
+            fov_y: 45.0_f32.to_radians(),
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at_rh(self.position, self.target, self.up)
+    }
+
+    pub fn projection_matrix(&self, aspect_ratio: f32) -> Mat4 {
+        Mat4::perspective_rh(self.fov_y, aspect_ratio, self.near, self.far)
+    }
+
+    /// Projection for a slice of the view frustum between two view distances
+    pub fn projection_matrix_between(&self, aspect_ratio: f32, near: f32, far: f32) -> Mat4 {
+        Mat4::perspective_rh(self.fov_y, aspect_ratio, near, far)
+    }
+
+    pub fn frustum(&self, aspect_ratio: f32) -> Frustum {
+        Frustum::from_view_projection(self.projection_matrix(aspect_ratio) * self.view_matrix())
+    }
+}
+
+/// a 2d camera looking down -z at the xy plane, with y up - used for sprites
+pub struct OrthographicCamera {
+    pub position: Vec2,
+    // radians, anticlockwise
+    pub rotation: f32,
+    // window pixels per world unit
+    pub zoom: f32,
+}
+
+impl Default for OrthographicCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl OrthographicCamera {
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::from_rotation_z(-self.rotation) * Mat4::from_translation(-self.position.extend(0.0))
+    }
+
+    /// Projection showing the window's size in pixels, divided by the zoom, around the camera
+    pub fn projection_matrix(&self, width: f32, height: f32) -> Mat4 {
+        let half_width = width * 0.5 / self.zoom;
+        let half_height = height * 0.5 / self.zoom;
+
+        Mat4::orthographic_rh(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            -1000.0,
+            1000.0,
+        )
+    }
+
+    pub fn view_projection_matrix(&self, width: f32, height: f32) -> Mat4 {
+        self.projection_matrix(width, height) * self.view_matrix()
+    }
+
+    /// The world position under a pixel in a window of the given size
+    #[allow(dead_code)]
+    pub fn screen_to_world(&self, position: [f32; 2], width: f32, height: f32) -> Vec2 {
+        let ndc = Vec2::new(
+            position[0] / width * 2.0 - 1.0,
+            1.0 - position[1] / height * 2.0,
+        );
+
+        self.view_projection_matrix(width, height)
+            .inverse()
+            .project_point3(ndc.extend(0.0))
+            .truncate()
+    }
+}
+
+/// the six planes bounding a camera's view volume, with normals pointing inwards
+pub struct Frustum {
+    // xyz normal, w distance
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a view projection matrix, using wgpu's 0 to 1 clip depth
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let row_x = view_projection.row(0);
+        let row_y = view_projection.row(1);
+        let row_z = view_projection.row(2);
+        let row_w = view_projection.row(3);
+
+        let planes = [
+            row_w + row_x,
+            row_w - row_x,
+            row_w + row_y,
+            row_w - row_y,
+            row_z,
+            row_w - row_z,
+        ]
+        .map(|plane| plane / plane.truncate().length());
+
+        Self { planes }
+    }
+
+    /// Check if any part of a world space bounding box is inside the frustum
+    pub fn intersects_aabb(&self, aabb: &geometry::BoundingBox) -> bool {
+        for plane in self.planes.iter() {
+            // the corner furthest along the plane's normal
+            let corner = Vec3::new(
+                if plane.x >= 0.0 {
+                    aabb.max[0]
+                } else {
+                    aabb.min[0]
+                },
+                if plane.y >= 0.0 {
+                    aabb.max[1]
+                } else {
+                    aabb.min[1]
+                },
+                if plane.z >= 0.0 {
+                    aabb.max[2]
+                } else {
+                    aabb.min[2]
+                },
+            );
+
+            if plane.truncate().dot(corner) + plane.w < 0.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,351 @@
+use crate::core::game::{ComponentEnum, ComponentType, EntityId, World};
+use crate::core::renderer::Renderer;
+use crate::utils::log;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// workgroup size compute shaders are expected to use along x, for working out dispatch sizes
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// a buffer compute shaders bind, which can be read back from the gpu
+struct StorageBuffer {
+    buffer: wgpu::Buffer,
+    // bytes written, the buffer can be larger
+    size: u64,
+    // entities each element was gathered from, @see Compute::gather
+    entities: Vec<EntityId>,
+    readback: Option<Readback>,
+}
+
+/// a copy of a storage buffer on its way back from the gpu
+struct Readback {
+    buffer: wgpu::Buffer,
+    size: u64,
+    // the storage buffer's entities when the copy was made, so results land on the entities they
+    // were computed for even if entities come and go in the meantime
+    entities: Vec<EntityId>,
+    receiver: tokio::sync::oneshot::Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// Compute shaders and the buffers they work on, for systems running gameplay on the gpu - both
+/// are kept by name, so systems can find them again every frame
+///
+/// a frame's work is usually to gather component data into buffers, dispatch a kernel over them,
+/// and start reading the results back - which arrive a frame or more later, to be scattered back
+/// into the components. the device and queue are locked by each call, so don't hold them while
+/// using this.
+pub struct Compute {
+    device: Arc<Mutex<wgpu::Device>>,
+    queue: Arc<Mutex<wgpu::Queue>>,
+    kernels: HashMap<String, wgpu::ComputePipeline>,
+    buffers: HashMap<String, StorageBuffer>,
+}
+
+impl Compute {
+    pub fn new(device: Arc<Mutex<wgpu::Device>>, queue: Arc<Mutex<wgpu::Queue>>) -> Self {
+        Self {
+            device,
+            queue,
+            kernels: HashMap::new(),
+            buffers: HashMap::new(),
+        }
+    }
+
+    /// Compile a compute shader whose entry point is cs_main, if it isn't loaded already - its
+    /// bindings are in group 0, and filled by the buffers given to dispatch
+    pub fn load_kernel(&mut self, name: &str, source: &str) -> Result<(), String> {
+        if self.kernels.contains_key(name) {
+            return Ok(());
+        }
+
+        let device = self.device.lock().unwrap();
+        let shader = Renderer::compile_shader(&device, name, source)?;
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        // the layout comes from the shader's bindings
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(name),
+            layout: None,
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        if let Some(e) = pollster::block_on(device.pop_error_scope()) {
+            return Err(format!("failed to create compute kernel {}: {}", name, e));
+        }
+
+        self.kernels.insert(name.to_string(), pipeline);
+
+        Ok(())
+    }
+
+    /// Upload data into a buffer, creating it or growing it as needed - it can be bound as
+    /// storage, or as a uniform if it's small
+    pub fn write_buffer<T: bytemuck::Pod>(&mut self, name: &str, data: &[T]) {
+        let mut bytes = bytemuck::cast_slice::<T, u8>(data).to_vec();
+
+        // copies work in multiples of four bytes
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+
+        let size = bytes.len() as u64;
+        let device = self.device.lock().unwrap();
+
+        let buffer = match self.buffers.get_mut(name) {
+            Some(buffer) if buffer.buffer.size() >= size => buffer,
+            _ => {
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(name),
+                    // never empty, and aligned for binding as a uniform
+                    size: size.next_power_of_two().max(16),
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::UNIFORM
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                });
+
+                // a read back of the old buffer still arrives
+                let readback = self.buffers.remove(name).and_then(|buffer| buffer.readback);
+
+                self.buffers
+                    .entry(name.to_string())
+                    .or_insert(StorageBuffer {
+                        buffer,
+                        size: 0,
+                        entities: Vec::new(),
+                        readback,
+                    })
+            }
+        };
+
+        buffer.size = size;
+        buffer.entities.clear();
+
+        self.queue
+            .lock()
+            .unwrap()
+            .write_buffer(&buffer.buffer, 0, &bytes);
+    }
+
+    /// Write a buffer with one element for each entity that has every one of the component types,
+    /// made from its components - the entities are remembered so results can be scattered back
+    pub fn gather<T: bytemuck::Pod>(
+        &mut self,
+        name: &str,
+        world: &World,
+        types: &[ComponentType],
+        element: impl Fn(&[ComponentEnum]) -> T,
+    ) {
+        // kept in step, so an entity without components doesn't shift the results of the rest
+        let (entities, data): (Vec<EntityId>, Vec<T>) = world
+            .get_entities_with_components(types)
+            .into_iter()
+            .filter_map(|entity_id| {
+                let components = world.get_entity_components(entity_id)?;
+                Some((entity_id, element(components)))
+            })
+            .unzip();
+
+        self.write_buffer(name, &data);
+
+        if let Some(buffer) = self.buffers.get_mut(name) {
+            buffer.entities = entities;
+        }
+    }
+
+    /// Run a kernel over a number of workgroups, with the named buffers bound to group 0 in order
+    pub fn dispatch(
+        &self,
+        kernel: &str,
+        buffers: &[&str],
+        workgroups: [u32; 3],
+    ) -> Result<(), String> {
+        let pipeline = match self.kernels.get(kernel) {
+            Some(pipeline) => pipeline,
+            None => return Err(format!("compute kernel {} isn't loaded", kernel)),
+        };
+
+        let mut entries = Vec::new();
+
+        for (binding, &name) in buffers.iter().enumerate() {
+            let buffer = match self.buffers.get(name) {
+                Some(buffer) => buffer,
+                None => return Err(format!("compute buffer {} hasn't been written", name)),
+            };
+
+            // only the written part, so array lengths in the shader match what was written
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer.buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(buffer.size.max(16)),
+                }),
+            });
+        }
+
+        let device = self.device.lock().unwrap();
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(kernel),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compute Encoder"),
+        });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(kernel),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+        }
+
+        self.queue
+            .lock()
+            .unwrap()
+            .submit(std::iter::once(encoder.finish()));
+
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(e) => Err(format!("failed to dispatch {}: {}", kernel, e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Start copying a buffer back from the gpu, after the work submitted so far - does nothing
+    /// if a copy is already on its way
+    pub fn read_back(&mut self, name: &str) -> Result<(), String> {
+        let buffer = match self.buffers.get_mut(name) {
+            Some(buffer) => buffer,
+            None => return Err(format!("compute buffer {} hasn't been written", name)),
+        };
+
+        if buffer.readback.is_some() {
+            return Ok(());
+        }
+
+        let device = self.device.lock().unwrap();
+
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compute Readback Buffer"),
+            size: buffer.size.max(4),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compute Readback Encoder"),
+        });
+
+        encoder.copy_buffer_to_buffer(&buffer.buffer, 0, &readback, 0, buffer.size);
+
+        self.queue
+            .lock()
+            .unwrap()
+            .submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+
+        readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+
+        buffer.readback = Some(Readback {
+            buffer: readback,
+            size: buffer.size,
+            entities: buffer.entities.clone(),
+            receiver,
+        });
+
+        Ok(())
+    }
+
+    /// Whether a read back of a buffer has been started and not taken yet
+    pub fn reading_back(&self, name: &str) -> bool {
+        self.buffers
+            .get(name)
+            .is_some_and(|buffer| buffer.readback.is_some())
+    }
+
+    /// A buffer's contents once a read back has arrived, along with the entities they were
+    /// gathered from - none while it's on its way, so check every frame
+    pub fn take_read_back<T: bytemuck::Pod>(
+        &mut self,
+        name: &str,
+    ) -> Option<(Vec<T>, Vec<EntityId>)> {
+        let buffer = self.buffers.get_mut(name)?;
+        let readback = buffer.readback.as_mut()?;
+
+        // native backends only run map callbacks while the device is polled
+        self.device.lock().unwrap().poll(wgpu::Maintain::Poll);
+
+        let result = match readback.receiver.try_recv() {
+            Ok(result) => result,
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return None,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => Err(wgpu::BufferAsyncError),
+        };
+
+        let readback = buffer.readback.take()?;
+
+        if let Err(e) = result {
+            log::warn(&format!(
+                "failed to read back compute buffer {}: {}",
+                name, e
+            ));
+            return None;
+        }
+
+        let data = {
+            let bytes = readback.buffer.slice(..).get_mapped_range();
+            let size = readback.size as usize;
+
+            // whole elements only, ignoring the padding
+            bytemuck::pod_collect_to_vec::<u8, T>(
+                &bytes[..size - size % std::mem::size_of::<T>().max(1)],
+            )
+        };
+
+        readback.buffer.unmap();
+
+        Some((data, readback.entities))
+    }
+
+    /// Write a read back buffer's elements into the components of the entities they were gathered
+    /// from, if it's arrived - returns whether it had
+    pub fn scatter<T: bytemuck::Pod>(
+        &mut self,
+        name: &str,
+        world: &mut World,
+        types: &[ComponentType],
+        mut apply: impl FnMut(&mut [&mut ComponentEnum], &T),
+    ) -> bool {
+        let (data, entities) = match self.take_read_back::<T>(name) {
+            Some(read_back) => read_back,
+            None => return false,
+        };
+
+        for (entity_id, element) in entities.into_iter().zip(data.iter()) {
+            let mut components = world.get_entity_components_mut(entity_id, types);
+
+            // removed, or lost a component since it was gathered
+            if components.len() == types.len() {
+                apply(&mut components, element);
+            }
+        }
+
+        true
+    }
+}
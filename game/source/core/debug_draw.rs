@@ -0,0 +1,275 @@
+use crate::core::camera::Camera;
+use crate::core::geometry::{self, BoundingBox, Colour};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use std::f32::consts::TAU;
+
+/// segments used for each circle of a debug sphere
+const SPHERE_SEGMENTS: usize = 24;
+
+/// a line end as laid out in the debug line vertex buffer, @see debug.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DebugVertex {
+    pub position: geometry::Vector3,
+    pub colour: Colour,
+}
+
+impl DebugVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+struct DebugLine {
+    start: Vec3,
+    end: Vec3,
+    colour: Colour,
+    // time after which the line is no longer drawn
+    expires: f32,
+}
+
+/// Immediate mode lines and shapes for visualising things while debugging, drawn over the scene
+/// by the debug draw pass
+///
+/// anything drawn with a duration of 0 is shown for a single frame, so call it every frame to keep
+/// it on screen - longer durations are in seconds.
+pub struct DebugDraw {
+    lines: Vec<DebugLine>,
+    // time of the frame being drawn, from GameState::total_time
+    time: f32,
+    pub enabled: bool,
+    // draw every collider's aabb each frame, @see DebugDrawSystem
+    pub draw_colliders: bool,
+    pub collider_colour: Colour,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            time: 0.0,
+            enabled: true,
+            draw_colliders: false,
+            collider_colour: [0.0, 1.0, 0.0],
+        }
+    }
+}
+
+impl DebugDraw {
+    pub fn line(&mut self, start: Vec3, end: Vec3, colour: Colour, duration: f32) {
+        if !self.enabled {
+            return;
+        }
+
+        self.lines.push(DebugLine {
+            start,
+            end,
+            colour,
+            expires: self.time + duration,
+        });
+    }
+
+    /// A box aligned to the world axes
+    pub fn aabb(&mut self, aabb: &BoundingBox, colour: Colour, duration: f32) {
+        let min = Vec3::from_array(aabb.min);
+        let max = Vec3::from_array(aabb.max);
+
+        self.oriented_box(
+            (min + max) * 0.5,
+            (max - min) * 0.5,
+            Quat::IDENTITY,
+            colour,
+            duration,
+        );
+    }
+
+    /// A box around a centre, with half its size along each of its rotated axes
+    pub fn oriented_box(
+        &mut self,
+        centre: Vec3,
+        half_extents: Vec3,
+        rotation: Quat,
+        colour: Colour,
+        duration: f32,
+    ) {
+        // corner i has its x, y and z at the max when bits 0, 1 and 2 are set
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                let sign = Vec3::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                );
+
+                centre + rotation * (sign * half_extents)
+            })
+            .collect();
+
+        self.corners(&corners, colour, duration);
+    }
+
+    /// Three circles around a centre, one in each axis plane
+    #[allow(dead_code)]
+    pub fn sphere(&mut self, centre: Vec3, radius: f32, colour: Colour, duration: f32) {
+        for (a, b) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+            self.circle(centre, a * radius, b * radius, colour, duration);
+        }
+    }
+
+    /// A line with a head at the end, e.g. for velocities and directions
+    #[allow(dead_code)]
+    pub fn arrow(&mut self, start: Vec3, end: Vec3, colour: Colour, duration: f32) {
+        self.line(start, end, colour, duration);
+
+        let direction = end - start;
+        let length = direction.length();
+
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let direction = direction / length;
+        let head_length = length * 0.2;
+        let (side, up) = direction.any_orthonormal_pair();
+        let back = end - direction * head_length;
+
+        for offset in [side, -side, up, -up] {
+            self.line(end, back + offset * head_length * 0.5, colour, duration);
+        }
+    }
+
+    /// A square grid on the xz plane, `size` across and split into `divisions` cells each way
+    #[allow(dead_code)]
+    pub fn grid(&mut self, centre: Vec3, size: f32, divisions: u32, colour: Colour, duration: f32) {
+        let divisions = divisions.max(1);
+        let half = size * 0.5;
+        let step = size / divisions as f32;
+
+        for i in 0..=divisions {
+            let offset = -half + step * i as f32;
+
+            self.line(
+                centre + Vec3::new(offset, 0.0, -half),
+                centre + Vec3::new(offset, 0.0, half),
+                colour,
+                duration,
+            );
+            self.line(
+                centre + Vec3::new(-half, 0.0, offset),
+                centre + Vec3::new(half, 0.0, offset),
+                colour,
+                duration,
+            );
+        }
+    }
+
+    /// The volume a camera can see
+    #[allow(dead_code)]
+    pub fn camera_frustum(
+        &mut self,
+        camera: &Camera,
+        aspect_ratio: f32,
+        colour: Colour,
+        duration: f32,
+    ) {
+        let view_projection = camera.projection_matrix(aspect_ratio) * camera.view_matrix();
+
+        self.frustum(view_projection, colour, duration);
+    }
+
+    /// The volume inside a view projection matrix's clip space, using wgpu's 0 to 1 clip depth
+    #[allow(dead_code)]
+    pub fn frustum(&mut self, view_projection: Mat4, colour: Colour, duration: f32) {
+        let inverse = view_projection.inverse();
+
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                let clip = Vec4::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { 0.0 } else { 1.0 },
+                    1.0,
+                );
+                let world = inverse * clip;
+
+                world.truncate() / world.w
+            })
+            .collect();
+
+        self.corners(&corners, colour, duration);
+    }
+
+    /// Lines to upload this frame, two vertices each
+    pub fn vertices(&self) -> Vec<DebugVertex> {
+        self.lines
+            .iter()
+            .flat_map(|line| {
+                [
+                    DebugVertex {
+                        position: line.start.to_array(),
+                        colour: line.colour,
+                    },
+                    DebugVertex {
+                        position: line.end.to_array(),
+                        colour: line.colour,
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    /// Set the time new lines' durations are counted from
+    pub fn begin_frame(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Drop lines whose duration has run out, once they've been drawn
+    pub fn end_frame(&mut self) {
+        let time = self.time;
+
+        self.lines.retain(|line| line.expires > time);
+    }
+
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// The twelve edges between eight corners, indexed by bits as in oriented_box
+    fn corners(&mut self, corners: &[Vec3], colour: Colour, duration: f32) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corners[i], corners[i | bit], colour, duration);
+                }
+            }
+        }
+    }
+
+    fn circle(&mut self, centre: Vec3, a: Vec3, b: Vec3, colour: Colour, duration: f32) {
+        let point = |segment: usize| {
+            let angle = segment as f32 / SPHERE_SEGMENTS as f32 * TAU;
+
+            centre + a * angle.cos() + b * angle.sin()
+        };
+
+        for segment in 0..SPHERE_SEGMENTS {
+            self.line(point(segment), point(segment + 1), colour, duration);
+        }
+    }
+}
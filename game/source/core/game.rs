@@ -1,18 +1,36 @@
+use crate::components::boid_component;
 use crate::components::collider_component;
+use crate::components::light_component;
+use crate::components::material_component;
 use crate::components::mesh_component;
 use crate::components::movement_component;
+use crate::components::particle_emitter_component;
+use crate::components::sprite_animation_component;
+use crate::components::sprite_component;
+use crate::components::text_label_component;
+use crate::components::tilemap_component;
 use crate::components::transform_component;
+use crate::components::ui_node_component;
 
 use crate::core::renderer;
 use crate::core::state;
+use crate::core::text::{FontId, FontRendering, TextAlign, TextStyle};
+use crate::core::ui::{UiAlign, UiDirection, UiEdges, UiJustify, UiStyle, UiVal};
 use crate::systems::movement_system;
-use crate::systems::{collision_system, mesh_bufferer_system, mesh_renderer_system};
+use crate::systems::{
+    boids_system, collision_system, debug_draw_system, hud_system, light_gatherer_system,
+    material_bufferer_system, mesh_bufferer_system, mesh_renderer_system, particle_bufferer_system,
+    particle_system, sprite_system, sprite_walker_system, tilemap_bufferer_system, ui_system,
+};
+use crate::utils::log;
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Mutex;
 
 use super::geometry;
+use super::particles::Curve;
+use super::tilemap::TileCollision;
 
 // Entity ID type
 pub type EntityId = u32;
@@ -26,6 +44,15 @@ pub enum ComponentType {
     Transform,
     Movement,
     Collider,
+    Material,
+    Light,
+    TextLabel,
+    UiNode,
+    ParticleEmitter,
+    Sprite,
+    SpriteAnimation,
+    Tilemap,
+    Boid,
 }
 
 // Define an enum to hold different component types
@@ -34,6 +61,15 @@ pub enum ComponentEnum {
     Transform(transform_component::TransformComponent),
     Movement(movement_component::MovementComponent),
     Collider(collider_component::ColliderComponent),
+    Material(material_component::MaterialComponent),
+    Light(light_component::LightComponent),
+    TextLabel(text_label_component::TextLabelComponent),
+    UiNode(ui_node_component::UiNodeComponent),
+    ParticleEmitter(particle_emitter_component::ParticleEmitterComponent),
+    Sprite(sprite_component::SpriteComponent),
+    SpriteAnimation(sprite_animation_component::SpriteAnimationComponent),
+    Tilemap(tilemap_component::TilemapComponent),
+    Boid(boid_component::BoidComponent),
     // Add other component types here
 }
 
@@ -44,6 +80,15 @@ impl ComponentEnum {
             ComponentEnum::Transform(_) => ComponentType::Transform,
             ComponentEnum::Movement(_) => ComponentType::Movement,
             ComponentEnum::Collider(_) => ComponentType::Collider,
+            ComponentEnum::Material(_) => ComponentType::Material,
+            ComponentEnum::Light(_) => ComponentType::Light,
+            ComponentEnum::TextLabel(_) => ComponentType::TextLabel,
+            ComponentEnum::UiNode(_) => ComponentType::UiNode,
+            ComponentEnum::ParticleEmitter(_) => ComponentType::ParticleEmitter,
+            ComponentEnum::Sprite(_) => ComponentType::Sprite,
+            ComponentEnum::SpriteAnimation(_) => ComponentType::SpriteAnimation,
+            ComponentEnum::Tilemap(_) => ComponentType::Tilemap,
+            ComponentEnum::Boid(_) => ComponentType::Boid,
         }
     }
 }
@@ -190,6 +235,12 @@ impl Entity {
 // System trait for implementing systems that act on entities and components
 pub trait System {
     fn run(&self, world: &mut World, renderer: &mut renderer::Renderer);
+
+    /// Shown by the profiler - the type's name by default
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
 
 /// Storage for entities, components, and systems
@@ -232,7 +283,9 @@ impl World {
         let systems = std::mem::take(&mut self.update_systems);
 
         for system in systems.iter() {
+            let start = renderer.profiler().now();
             system.run(self, renderer);
+            renderer.profiler_mut().record_cpu(system.name(), start);
         }
 
         self.update_systems = systems;
@@ -241,12 +294,33 @@ impl World {
     pub fn run_draw_systems(&mut self, renderer: &mut renderer::Renderer) {
         let systems = std::mem::take(&mut self.draw_systems);
         for system in systems.iter() {
+            let start = renderer.profiler().now();
             system.run(self, renderer);
+            renderer.profiler_mut().record_cpu(system.name(), start);
         }
         self.draw_systems = systems;
+
+        // drawing ends the frame
+        renderer.profiler_mut().end_frame();
     }
 
-    pub fn test_world(&mut self) {
+    pub fn test_world(&mut self, renderer: &mut renderer::Renderer) {
+        // the same font twice - bitmaps for small screen text, a distance field for labels in the
+        // world that are scaled and billboarded
+        let screen_font = renderer
+            .text_mut()
+            .load_font("assets/fonts/DejaVuSansMono.ttf", FontRendering::Bitmap)
+            .map_err(|e| log::error(&e))
+            .ok();
+
+        let label_font = renderer
+            .text_mut()
+            .load_font("assets/fonts/DejaVuSansMono.ttf", FontRendering::Sdf)
+            .map_err(|e| log::error(&e))
+            .ok();
+
+        renderer.profiler_mut().set_overlay_font(screen_font);
+
         // create ground
 
         let ground_entity = Entity::new();
@@ -258,7 +332,10 @@ impl World {
 
         self.add_component(
             ground_entity_id,
-            ComponentEnum::Mesh(mesh_component::MeshComponent::new(vertices, indices)),
+            // nothing is below the ground for it to shadow
+            ComponentEnum::Mesh(
+                mesh_component::MeshComponent::new(vertices, indices).with_shadows(false, true),
+            ),
         );
 
         self.add_component(
@@ -270,6 +347,15 @@ impl World {
             )),
         );
 
+        self.add_component(
+            ground_entity_id,
+            ComponentEnum::Material(material_component::MaterialComponent::pbr(
+                [1.0, 1.0, 1.0, 1.0],
+                0.0,
+                0.8,
+            )),
+        );
+
         // create cube
 
         let cube_entity = Entity::new();
@@ -293,6 +379,13 @@ impl World {
             )),
         );
 
+        self.add_component(
+            cube_entity_id,
+            ComponentEnum::Material(material_component::MaterialComponent::new([
+                1.0, 0.9, 0.8, 1.0,
+            ])),
+        );
+
         self.add_component(
             cube_entity_id,
             ComponentEnum::Movement(movement_component::MovementComponent::new(
@@ -301,13 +394,580 @@ impl World {
             )),
         );
 
+        // create glass cube
+
+        let glass_entity = Entity::new();
+        let glass_entity_id = glass_entity.id;
+
+        self.insert_entity(glass_entity);
+
+        let (vertices, indices) = geometry::get_cube();
+
+        self.add_component(
+            glass_entity_id,
+            ComponentEnum::Mesh(mesh_component::MeshComponent::new(vertices, indices)),
+        );
+
+        self.add_component(
+            glass_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [1.5, -1.5, 0.5],
+                [0.0, 0.6, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            glass_entity_id,
+            ComponentEnum::Material(
+                material_component::MaterialComponent::new([0.4, 0.7, 1.0, 0.4])
+                    .with_blend_mode(material_component::BlendMode::AlphaBlend),
+            ),
+        );
+
+        if let Some(font) = label_font {
+            self.add_component(
+                glass_entity_id,
+                ComponentEnum::TextLabel(
+                    text_label_component::TextLabelComponent::new(
+                        "glass",
+                        TextStyle::new(font, 32.0)
+                            .with_colour([0.7, 0.9, 1.0, 1.0])
+                            .with_align(TextAlign::Centre),
+                    )
+                    .with_offset([0.0, 0.8, 0.0])
+                    .with_scale(0.008),
+                ),
+            );
+        }
+
+        // create a metal ball, with coarser meshes swapped in as the camera gets further away
+
+        let ball_entity = Entity::new();
+        let ball_entity_id = ball_entity.id;
+
+        self.insert_entity(ball_entity);
+
+        let (vertices, indices) = geometry::get_sphere(32, 16);
+        let (middle_vertices, middle_indices) = geometry::get_sphere(12, 6);
+        let (far_vertices, far_indices) = geometry::get_sphere(6, 3);
+
+        self.add_component(
+            ball_entity_id,
+            ComponentEnum::Mesh(
+                mesh_component::MeshComponent::new(vertices, indices)
+                    .with_lod(
+                        middle_vertices,
+                        middle_indices,
+                        mesh_component::LodThreshold::Distance(6.0),
+                    )
+                    .with_lod(
+                        far_vertices,
+                        far_indices,
+                        mesh_component::LodThreshold::ScreenSize(0.05),
+                    )
+                    .with_lod_hysteresis(0.15)
+                    .with_lod_fade(0.4),
+            ),
+        );
+
+        self.add_component(
+            ball_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [2.5, -1.5, -1.5],
+                [0.0, 0.0, 0.0],
+                [5.0, 5.0, 5.0],
+            )),
+        );
+
+        self.add_component(
+            ball_entity_id,
+            ComponentEnum::Material(
+                material_component::MaterialComponent::pbr([0.95, 0.9, 0.85, 1.0], 1.0, 0.25)
+                    .with_cull_mode(material_component::CullMode::Back),
+            ),
+        );
+
+        // create lights
+
+        let sun_entity = Entity::new();
+        let sun_entity_id = sun_entity.id;
+
+        self.insert_entity(sun_entity);
+
+        self.add_component(
+            sun_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [0.0, 0.0, 0.0],
+                [-0.8, 0.4, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            sun_entity_id,
+            ComponentEnum::Light(
+                light_component::LightComponent::directional([1.0, 0.95, 0.9], 0.8).with_shadows(),
+            ),
+        );
+
+        let lamp_entity = Entity::new();
+        let lamp_entity_id = lamp_entity.id;
+
+        self.insert_entity(lamp_entity);
+
+        self.add_component(
+            lamp_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [1.0, -1.0, 1.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            lamp_entity_id,
+            ComponentEnum::Light(light_component::LightComponent::point(
+                [1.0, 0.5, 0.2],
+                2.0,
+                4.0,
+            )),
+        );
+
+        // a spot light above the cube, pointing down so the cube shadows the ground
+        let spot_entity = Entity::new();
+        let spot_entity_id = spot_entity.id;
+
+        self.insert_entity(spot_entity);
+
+        self.add_component(
+            spot_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-0.5, 3.0, 0.0],
+                [-1.4, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            spot_entity_id,
+            ComponentEnum::Light(
+                light_component::LightComponent::spot([0.6, 0.8, 1.0], 3.0, 8.0, 0.3, 0.5)
+                    .with_shadows(),
+            ),
+        );
+
+        // create sparks
+
+        let sparks_entity = Entity::new();
+        let sparks_entity_id = sparks_entity.id;
+
+        self.insert_entity(sparks_entity);
+
+        self.add_component(
+            sparks_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-1.5, -2.0, 0.5],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            sparks_entity_id,
+            ComponentEnum::ParticleEmitter(
+                particle_emitter_component::ParticleEmitterComponent::new(200.0, 2048)
+                    .with_burst(0.0, 200, Some(3.0))
+                    .with_lifetime(0.8, 1.6)
+                    .with_velocity([0.0, 1.0, 0.0], 0.35, [2.5, 4.0])
+                    .with_colour(Curve::new(vec![
+                        (0.0, [4.0, 2.4, 0.8, 1.0]),
+                        (0.5, [2.0, 0.6, 0.1, 0.8]),
+                        (1.0, [0.5, 0.1, 0.0, 0.0]),
+                    ]))
+                    .with_size(Curve::linear(0.08, 0.02))
+                    .with_gravity([0.0, -6.0, 0.0])
+                    .with_blend(particle_emitter_component::ParticleBlend::Additive)
+                    .with_simulation(particle_emitter_component::ParticleSimulation::Gpu),
+            ),
+        );
+
+        // create smoke, rising and drifting away from the sparks
+
+        let smoke_entity = Entity::new();
+        let smoke_entity_id = smoke_entity.id;
+
+        self.insert_entity(smoke_entity);
+
+        self.add_component(
+            smoke_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-2.5, -2.0, 0.5],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            smoke_entity_id,
+            ComponentEnum::ParticleEmitter(
+                particle_emitter_component::ParticleEmitterComponent::new(12.0, 128)
+                    .with_lifetime(2.5, 4.0)
+                    .with_velocity([0.0, 1.0, 0.0], 0.4, [0.3, 0.6])
+                    // a light wind instead of falling
+                    .with_gravity([0.15, 0.2, 0.0])
+                    .with_colour(Curve::new(vec![
+                        (0.0, [0.35, 0.35, 0.35, 0.0]),
+                        (0.2, [0.3, 0.3, 0.3, 0.6]),
+                        (1.0, [0.5, 0.5, 0.5, 0.0]),
+                    ]))
+                    .with_size(Curve::linear(0.2, 0.9))
+                    .with_blend(particle_emitter_component::ParticleBlend::Alpha),
+            ),
+        );
+
+        // create a small flock of boids, starting around a ring so they steer into each other
+
+        for index in 0..12 {
+            let boid_entity = Entity::new();
+            let boid_entity_id = boid_entity.id;
+
+            self.insert_entity(boid_entity);
+
+            let angle = index as f32 / 12.0 * std::f32::consts::TAU;
+            let (vertices, indices) = geometry::get_cube();
+
+            self.add_component(
+                boid_entity_id,
+                ComponentEnum::Mesh(mesh_component::MeshComponent::new(vertices, indices)),
+            );
+
+            self.add_component(
+                boid_entity_id,
+                ComponentEnum::Transform(transform_component::TransformComponent::new(
+                    [angle.cos() * 2.0, 2.0, angle.sin() * 2.0 - 3.0],
+                    [0.0, 0.0, 0.0],
+                    [0.1, 0.1, 0.1],
+                )),
+            );
+
+            self.add_component(
+                boid_entity_id,
+                ComponentEnum::Material(material_component::MaterialComponent::new([
+                    0.9, 0.9, 1.0, 1.0,
+                ])),
+            );
+
+            self.add_component(
+                boid_entity_id,
+                ComponentEnum::Movement(movement_component::MovementComponent::new(
+                    [-angle.sin(), 0.2, angle.cos()],
+                    [0.0, 0.0, 0.0],
+                )),
+            );
+
+            self.add_component(
+                boid_entity_id,
+                ComponentEnum::Boid(
+                    boid_component::BoidComponent::new(2.0, 1.5).with_weights(2.0, 1.0, 0.5),
+                ),
+            );
+        }
+
+        // create a tilemap with a floor and a wall, and a robot walking along it into the wall
+
+        let tilemap_entity = Entity::new();
+        let tilemap_entity_id = tilemap_entity.id;
+
+        self.insert_entity(tilemap_entity);
+
+        // tile 0 is ground, 1 is wall and 2 is grass, which is walked through
+        let mut tilemap =
+            tilemap_component::TilemapComponent::new("assets/tiles/tileset.png", [16, 16], 20, 4)
+                .with_pixels_per_unit(1.0)
+                .with_collision(TileCollision::Tiles(vec![0, 1]));
+
+        tilemap.fill([0, 0], [19, 0], Some(0));
+        tilemap.fill([19, 1], [19, 3], Some(1));
+        tilemap.fill([6, 1], [9, 1], Some(2));
+
+        self.add_component(tilemap_entity_id, ComponentEnum::Tilemap(tilemap));
+
+        self.add_component(
+            tilemap_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-160.0, -200.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        let backdrop_entity = Entity::new();
+        let backdrop_entity_id = backdrop_entity.id;
+
+        self.insert_entity(backdrop_entity);
+
+        // drawn behind, and never collided with
+        let mut backdrop =
+            tilemap_component::TilemapComponent::new("assets/tiles/tileset.png", [16, 16], 20, 4)
+                .with_pixels_per_unit(1.0)
+                .with_collision(TileCollision::None)
+                .with_z_order(-1);
+
+        backdrop.fill([0, 1], [18, 3], Some(3));
+
+        self.add_component(backdrop_entity_id, ComponentEnum::Tilemap(backdrop));
+
+        self.add_component(
+            backdrop_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-160.0, -200.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        let robot_entity = Entity::new();
+        let robot_entity_id = robot_entity.id;
+
+        self.insert_entity(robot_entity);
+
+        self.add_component(
+            robot_entity_id,
+            ComponentEnum::Transform(transform_component::TransformComponent::new(
+                [-140.0, -160.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 1.0],
+            )),
+        );
+
+        self.add_component(
+            robot_entity_id,
+            ComponentEnum::Movement(movement_component::MovementComponent::new(
+                [40.0, 0.0, 0.0],
+                [0.0, -400.0, 0.0],
+            )),
+        );
+
+        self.add_component(
+            robot_entity_id,
+            ComponentEnum::Collider(
+                collider_component::ColliderComponent::new().with_half_extents([8.0, 8.0, 8.0]),
+            ),
+        );
+
+        // walks until it reaches the wall, then stands idle
+        self.add_component(
+            robot_entity_id,
+            ComponentEnum::Sprite(
+                sprite_component::SpriteComponent::new("assets/sprites/robot.png")
+                    .with_pixels_per_unit(1.0)
+                    .with_z_order(1)
+                    .pixelated(),
+            ),
+        );
+
+        self.add_component(
+            robot_entity_id,
+            ComponentEnum::SpriteAnimation(
+                sprite_animation_component::SpriteAnimationComponent::new()
+                    .with_clip(
+                        "walk",
+                        sprite_animation_component::SpriteClip::from_grid(
+                            [16, 16],
+                            4,
+                            0,
+                            4,
+                            8.0,
+                            true,
+                        ),
+                    )
+                    // a long look, then a quick blink
+                    .with_clip(
+                        "idle",
+                        sprite_animation_component::SpriteClip::new(
+                            vec![[0, 16, 16, 16], [16, 16, 16, 16]],
+                            0.1,
+                            true,
+                        )
+                        .with_durations(vec![2.0, 0.15]),
+                    ),
+            ),
+        );
+
         self.add_update_system(mesh_bufferer_system::MeshBufferer {});
+        self.add_update_system(material_bufferer_system::MaterialBufferer {});
+        self.add_update_system(boids_system::BoidsSystem {});
         self.add_update_system(movement_system::MovementSystem {});
         self.add_update_system(collision_system::CollisionSystem {});
+        self.add_update_system(particle_system::ParticleSystem {});
+        self.add_update_system(sprite_walker_system::SpriteWalkerSystem {});
+        self.add_update_system(sprite_system::SpriteSystem {});
+        self.add_update_system(ui_system::UiSystem {});
+
+        self.add_draw_system(light_gatherer_system::LightGatherer {});
+        self.add_draw_system(debug_draw_system::DebugDrawSystem {});
+        self.add_draw_system(particle_bufferer_system::ParticleBufferer {});
+        self.add_draw_system(tilemap_bufferer_system::TilemapBufferer {});
+
+        if let Some(font) = screen_font {
+            let (profiler_button, colliders_button) = self.test_hud(font);
+
+            self.add_update_system(hud_system::HudButtonSystem {
+                profiler_button,
+                colliders_button,
+            });
+            self.add_draw_system(hud_system::HudSystem { font });
+        }
 
         self.add_draw_system(mesh_renderer_system::MeshRenderer {});
     }
 
+    /// A panel in the top left with a title and buttons for the profiler overlay and colliders,
+    /// returning the two buttons
+    fn test_hud(&mut self, font: FontId) -> (EntityId, EntityId) {
+        let panel_entity = Entity::new();
+        let panel_entity_id = panel_entity.id;
+
+        self.insert_entity(panel_entity);
+
+        self.add_component(
+            panel_entity_id,
+            ComponentEnum::UiNode(ui_node_component::UiNodeComponent::panel(
+                UiStyle::default()
+                    .with_size(UiVal::Px(240.0), UiVal::Auto)
+                    .with_padding(UiEdges::all(8.0))
+                    .with_gap(8.0)
+                    .with_align(UiAlign::Stretch)
+                    .anchored([0.0, 0.0], [8.0, 8.0]),
+                [0.05, 0.05, 0.08, 0.75],
+            )),
+        );
+
+        // the title, the game's name with an icon at the far end
+        let title_entity = Entity::new();
+        let title_entity_id = title_entity.id;
+
+        self.insert_entity(title_entity);
+
+        self.add_component(
+            title_entity_id,
+            ComponentEnum::UiNode(
+                ui_node_component::UiNodeComponent::new(
+                    UiStyle::default()
+                        .with_size(UiVal::Percent(100.0), UiVal::Auto)
+                        .with_direction(UiDirection::Row)
+                        .with_margin(UiEdges {
+                            bottom: 4.0,
+                            ..Default::default()
+                        })
+                        .with_justify(UiJustify::SpaceBetween)
+                        .with_align(UiAlign::Centre),
+                    ui_node_component::UiContent::None,
+                )
+                .with_parent(panel_entity_id),
+            ),
+        );
+
+        let name_entity = Entity::new();
+        let name_entity_id = name_entity.id;
+
+        self.insert_entity(name_entity);
+
+        self.add_component(
+            name_entity_id,
+            ComponentEnum::UiNode(
+                ui_node_component::UiNodeComponent::text(
+                    UiStyle::default(),
+                    "bideobame",
+                    TextStyle::new(font, 18.0),
+                )
+                .with_parent(title_entity_id),
+            ),
+        );
+
+        let icon_entity = Entity::new();
+        let icon_entity_id = icon_entity.id;
+
+        self.insert_entity(icon_entity);
+
+        self.add_component(
+            icon_entity_id,
+            ComponentEnum::UiNode(
+                ui_node_component::UiNodeComponent::image(UiStyle::default(), "assets/ui/icon.png")
+                    .with_parent(title_entity_id),
+            ),
+        );
+
+        // the buttons, sharing the panel's width in a row
+        let row_entity = Entity::new();
+        let row_entity_id = row_entity.id;
+
+        self.insert_entity(row_entity);
+
+        self.add_component(
+            row_entity_id,
+            ComponentEnum::UiNode(
+                ui_node_component::UiNodeComponent::new(
+                    UiStyle::default()
+                        .with_size(UiVal::Percent(100.0), UiVal::Auto)
+                        .with_direction(UiDirection::Row)
+                        .with_gap(8.0),
+                    ui_node_component::UiContent::None,
+                )
+                .with_parent(panel_entity_id),
+            ),
+        );
+
+        // a button with its label centred inside
+        let mut add_button = |label: &str| {
+            let button_entity = Entity::new();
+            let button_entity_id = button_entity.id;
+
+            self.insert_entity(button_entity);
+
+            self.add_component(
+                button_entity_id,
+                ComponentEnum::UiNode(
+                    ui_node_component::UiNodeComponent::button(
+                        UiStyle::default()
+                            .with_size(UiVal::Auto, UiVal::Px(28.0))
+                            .with_padding(UiEdges::symmetric(8.0, 4.0))
+                            .with_grow(1.0)
+                            .with_justify(UiJustify::Centre)
+                            .with_align(UiAlign::Centre),
+                        [0.2, 0.3, 0.5, 1.0],
+                    )
+                    .with_parent(row_entity_id),
+                ),
+            );
+
+            let label_entity = Entity::new();
+            let label_entity_id = label_entity.id;
+
+            self.insert_entity(label_entity);
+
+            self.add_component(
+                label_entity_id,
+                ComponentEnum::UiNode(
+                    ui_node_component::UiNodeComponent::text(
+                        UiStyle::default(),
+                        label,
+                        TextStyle::new(font, 14.0),
+                    )
+                    .with_parent(button_entity_id),
+                ),
+            );
+
+            button_entity_id
+        };
+
+        let profiler_button = add_button("Profiler");
+        let colliders_button = add_button("Colliders");
+
+        (profiler_button, colliders_button)
+    }
+
     // accessors
 
     pub fn entities(&self) -> &HashMap<EntityId, Entity> {
@@ -1,7 +1,7 @@
 use bytemuck;
 use wgpu;
 
-// type Vector2 = [f32; 2];
+pub type Vector2 = [f32; 2];
 pub type Vector3 = [f32; 3];
 pub type Colour = [f32; 3];
 
@@ -10,11 +10,18 @@ pub type Colour = [f32; 3];
 pub struct Vertex {
     pub position: Vector3,
     pub color: Colour,
+    pub uv: Vector2,
+    pub should_wave: u32,
+    pub normal: Vector3,
 }
 
 impl PartialEq for Vertex {
     fn eq(&self, other: &Self) -> bool {
-        return self.position == other.position && self.color == other.color;
+        return self.position == other.position
+            && self.color == other.color
+            && self.uv == other.uv
+            && self.should_wave == other.should_wave
+            && self.normal == other.normal;
     }
 }
 
@@ -37,15 +44,28 @@ impl Vertex {
                 },
                 // Should wave
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Uint32,
                 },
+                // UV
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // Normal
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct BoundingBox {
     pub min: Vector3,
     pub max: Vector3,
@@ -62,14 +82,23 @@ pub fn get_triangle(centre_x: f32, centre_y: f32, size: f32) -> Vec<Vertex> {
         Vertex {
             position: [x, y + half_size, 0.0],
             color: [1.0, 0.0, 0.0], // Pure red
+            uv: [0.5, 0.0],
+            should_wave: 0,
+            normal: [0.0, 0.0, 1.0],
         },
         Vertex {
             position: [x - half_size, y - half_size, 0.0],
             color: [0.0, 1.0, 0.0], // Pure green
+            uv: [0.0, 1.0],
+            should_wave: 0,
+            normal: [0.0, 0.0, 1.0],
         },
         Vertex {
             position: [x + half_size, y - half_size, 0.0],
             color: [0.0, 0.0, 1.0], // Pure blue
+            uv: [1.0, 1.0],
+            should_wave: 0,
+            normal: [0.0, 0.0, 1.0],
         },
     ];
 }
@@ -83,18 +112,30 @@ pub fn get_ground_quad() -> (Vec<Vertex>, Vec<u16>) {
     vertices.push(Vertex {
         position: [-0.1, 0.0, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.0, -0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.0, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.0, 0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
 
     indices.push(0);
@@ -117,108 +158,180 @@ pub fn get_cube() -> (Vec<Vertex>, Vec<u16>) {
     vertices.push(Vertex {
         position: [-0.1, -0.1, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, -1.0],
     });
     vertices.push(Vertex {
         position: [0.1, -0.1, -0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, -1.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, -0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, -1.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.1, -0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, -1.0],
     });
 
     // back
     vertices.push(Vertex {
         position: [-0.1, -0.1, 0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, 1.0],
     });
     vertices.push(Vertex {
         position: [0.1, -0.1, 0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, 1.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, 1.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.1, 0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 0.0, 1.0],
     });
 
     // top
     vertices.push(Vertex {
         position: [-0.1, 0.1, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, -0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.1, 0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, 1.0, 0.0],
     });
 
     // bottom
     vertices.push(Vertex {
         position: [-0.1, -0.1, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, -1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, -0.1, -0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [0.0, -1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, -0.1, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, -1.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, -0.1, 0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [0.0, -1.0, 0.0],
     });
 
     // left
     vertices.push(Vertex {
         position: [-0.1, -0.1, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [-1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, -0.1, 0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [-1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.1, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [-1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [-0.1, 0.1, -0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [-1.0, 0.0, 0.0],
     });
 
     // right
     vertices.push(Vertex {
         position: [0.1, -0.1, -0.1],
         color: [1.0, 0.0, 0.0],
+        uv: [0.0, 1.0],
+        should_wave: 0,
+        normal: [1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, -0.1, 0.1],
         color: [0.0, 1.0, 0.0],
+        uv: [1.0, 1.0],
+        should_wave: 0,
+        normal: [1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, 0.1],
         color: [0.0, 0.0, 1.0],
+        uv: [1.0, 0.0],
+        should_wave: 0,
+        normal: [1.0, 0.0, 0.0],
     });
     vertices.push(Vertex {
         position: [0.1, 0.1, -0.1],
         color: [1.0, 1.0, 0.0],
+        uv: [0.0, 0.0],
+        should_wave: 0,
+        normal: [1.0, 0.0, 0.0],
     });
 
     // front
@@ -271,3 +384,48 @@ pub fn get_cube() -> (Vec<Vertex>, Vec<u16>) {
 
     (vertices, indices)
 }
+
+/// A uv sphere the same size as the cube, with more segments around and rings down for a smoother
+/// but more expensive mesh
+pub fn get_sphere(segments: u16, rings: u16) -> (Vec<Vertex>, Vec<u16>) {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+
+    let mut vertices: Vec<Vertex> = vec![];
+    let mut indices: Vec<u16> = vec![];
+
+    // the seam has two columns of vertices, so its uvs can wrap
+    for ring in 0..=rings {
+        let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+
+        for segment in 0..=segments {
+            let phi = std::f32::consts::TAU * segment as f32 / segments as f32;
+            let normal = [
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            ];
+
+            vertices.push(Vertex {
+                position: normal.map(|axis| axis * 0.1),
+                color: [1.0, 1.0, 1.0],
+                uv: [segment as f32 / segments as f32, ring as f32 / rings as f32],
+                should_wave: 0,
+                normal,
+            });
+        }
+    }
+
+    // counter clockwise from outside
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let top = ring * (segments + 1) + segment;
+            let bottom = top + segments + 1;
+
+            indices.extend([top, bottom + 1, bottom]);
+            indices.extend([top, top + 1, bottom + 1]);
+        }
+    }
+
+    (vertices, indices)
+}
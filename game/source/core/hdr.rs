@@ -0,0 +1,89 @@
+/// format of the scene colour target, so lighting can go above 1.0 before tonemapping
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// number of downsampled textures in the bloom chain, each half the size of the last
+pub const BLOOM_MIP_COUNT: usize = 5;
+
+/// curve used to map hdr colours into the displayable range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapper {
+    // no curve, values above 1.0 clip
+    None,
+    Reinhard,
+    // narkowicz's fit of the aces filmic curve
+    Aces,
+    // troy sobotka's agx, with a polynomial fit of the default contrast curve
+    AgX,
+}
+
+impl Tonemapper {
+    pub const ALL: [Tonemapper; 4] = [
+        Tonemapper::None,
+        Tonemapper::Reinhard,
+        Tonemapper::Aces,
+        Tonemapper::AgX,
+    ];
+
+    /// The curve after this one, wrapping around - for cycling through them
+    pub fn next(&self) -> Tonemapper {
+        Self::ALL[(self.index() as usize + 1) % Self::ALL.len()]
+    }
+
+    /// matches the branches in tonemap.wgsl
+    pub fn index(&self) -> u32 {
+        match self {
+            Tonemapper::None => 0,
+            Tonemapper::Reinhard => 1,
+            Tonemapper::Aces => 2,
+            Tonemapper::AgX => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HdrSettings {
+    pub tonemapper: Tonemapper,
+    // linear multiplier applied before tonemapping
+    pub exposure: f32,
+    pub bloom: bool,
+    // brightness above which pixels start to bloom, and the width of the soft transition around it
+    pub bloom_threshold: f32,
+    pub bloom_knee: f32,
+    pub bloom_intensity: f32,
+    // spread of the upsampling filter, in texels of the smaller texture
+    pub bloom_radius: f32,
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self {
+            tonemapper: Tonemapper::Aces,
+            exposure: 1.0,
+            bloom: true,
+            bloom_threshold: 1.0,
+            bloom_knee: 0.5,
+            bloom_intensity: 0.05,
+            bloom_radius: 1.0,
+        }
+    }
+}
+
+/// uniform layout for the bloom shader - must match the Bloom struct in bloom.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BloomUniforms {
+    // threshold, knee, radius
+    pub settings: [f32; 4],
+    // size of one texel in the source texture
+    pub texel_size: [f32; 4],
+}
+
+/// uniform layout for the tonemap shader - must match the Tonemap struct in tonemap.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TonemapUniforms {
+    // exposure, bloom intensity, 1.0 if the output needs gamma encoding
+    pub settings: [f32; 4],
+    pub tonemapper: u32,
+    pub _padding: [u32; 3],
+}
@@ -1,5 +1,22 @@
 pub mod app;
+pub mod camera;
+pub mod compute;
+pub mod debug_draw;
 pub mod game;
 pub mod geometry;
+pub mod hdr;
+pub mod particles;
+pub mod picking;
+pub mod post;
+pub mod profiler;
+pub mod render_graph;
 pub mod renderer;
+pub mod shaders;
+pub mod shadows;
+pub mod sky;
 pub mod state;
+pub mod text;
+pub mod texture;
+pub mod tilemap;
+pub mod ui;
+pub mod view_mode;
@@ -0,0 +1,309 @@
+use std::mem;
+
+/// samples each curve is baked into for the shaders, @see particle.wgsl
+pub const CURVE_SAMPLES: usize = 16;
+
+/// workgroup size of the simulation compute shader, @see particle_simulate.wgsl
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// values a curve can blend between
+pub trait Lerp: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Lerp for [f32; 4] {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+    }
+}
+
+/// a value over a particle's life, from 0.0 when it spawns to 1.0 when it dies, linearly
+/// interpolated between keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve<T: Lerp> {
+    // sorted by time
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    /// Panics without any keys, as there'd be nothing to sample
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "a particle curve needs at least one key");
+
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    pub fn linear(from: T, to: T) -> Self {
+        Self::new(vec![(0.0, from), (1.0, to)])
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let first = self.keys[0];
+
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for pair in self.keys.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+
+            if t <= end.0 {
+                let span = (end.0 - start.0).max(f32::EPSILON);
+                return T::lerp(start.1, end.1, (t - start.0) / span);
+            }
+        }
+
+        self.keys[self.keys.len() - 1].1
+    }
+
+    /// Evenly spaced samples from the start of life to the end
+    pub fn bake(&self) -> [T; CURVE_SAMPLES] {
+        let mut samples = [self.keys[0].1; CURVE_SAMPLES];
+
+        for (index, sample) in samples.iter_mut().enumerate() {
+            *sample = self.sample(index as f32 / (CURVE_SAMPLES - 1) as f32);
+        }
+
+        samples
+    }
+}
+
+/// a particle as laid out in an emitter's storage buffer - must match Particle in the particle
+/// shaders. dead once its age reaches its lifetime.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particle {
+    // in world space
+    pub position: [f32; 3],
+    pub age: f32,
+    pub velocity: [f32; 3],
+    pub lifetime: f32,
+}
+
+/// uniform layout shared by the particle shaders - must match Emitter in them
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleUniforms {
+    // xyz gravity, w seconds to simulate on the gpu this frame
+    pub gravity: [f32; 4],
+    // number of particles in the buffer
+    pub count: u32,
+    pub _padding: [u32; 3],
+    pub colours: [[f32; 4]; CURVE_SAMPLES],
+    // sizes packed four to a vector
+    pub sizes: [[f32; 4]; CURVE_SAMPLES / 4],
+}
+
+/// an emitter's buffers on the gpu, created by the ParticleBufferer
+pub struct ParticleBuffers {
+    pub particles: wgpu::Buffer,
+    pub uniforms: wgpu::Buffer,
+    pub compute_bind_group: wgpu::BindGroup,
+    pub render_bind_group: wgpu::BindGroup,
+    // in particles
+    pub capacity: u32,
+    // particles to draw, alive or not
+    pub instance_count: u32,
+}
+
+/// the simulation pipeline and the layouts emitters' buffers are bound with, shared by every emitter
+pub struct ParticleGpu {
+    pub compute_bind_group_layout: wgpu::BindGroupLayout,
+    pub render_bind_group_layout: wgpu::BindGroupLayout,
+    pub simulate_pipeline: wgpu::ComputePipeline,
+}
+
+impl ParticleGpu {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = |label: &str, visibility: wgpu::ShaderStages, read_only: bool| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        let compute_bind_group_layout = layout(
+            "Particle Compute Bind Group Layout",
+            wgpu::ShaderStages::COMPUTE,
+            false,
+        );
+        let render_bind_group_layout = layout(
+            "Particle Render Bind Group Layout",
+            wgpu::ShaderStages::VERTEX,
+            true,
+        );
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Simulate Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../shaders/particle_simulate.wgsl").into(),
+            ),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Simulate Pipeline Layout"),
+            bind_group_layouts: &[&compute_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let simulate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Simulate Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Self {
+            compute_bind_group_layout,
+            render_bind_group_layout,
+            simulate_pipeline,
+        }
+    }
+
+    /// Storage and uniform buffers for an emitter holding up to capacity particles
+    pub fn create_buffers(&self, device: &wgpu::Device, capacity: u32) -> ParticleBuffers {
+        let particles = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Storage Buffer"),
+            size: (capacity.max(1) as usize * mem::size_of::<Particle>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Uniform Buffer"),
+            size: mem::size_of::<ParticleUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = |label: &str, layout: &wgpu::BindGroupLayout| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: particles.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: uniforms.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let compute_bind_group = bind_group(
+            "Particle Compute Bind Group",
+            &self.compute_bind_group_layout,
+        );
+        let render_bind_group =
+            bind_group("Particle Render Bind Group", &self.render_bind_group_layout);
+
+        ParticleBuffers {
+            particles,
+            uniforms,
+            compute_bind_group,
+            render_bind_group,
+            capacity,
+            instance_count: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_interpolates_between_keys() {
+        let curve = Curve::new(vec![(0.0, 0.0), (0.5, 1.0), (1.0, 3.0)]);
+
+        assert_eq!(curve.sample(0.25), 0.5);
+        assert_eq!(curve.sample(0.5), 1.0);
+        assert_eq!(curve.sample(0.75), 2.0);
+    }
+
+    #[test]
+    fn sample_holds_the_end_keys_outside_them() {
+        let curve = Curve::new(vec![(0.2, 1.0), (0.8, 2.0)]);
+
+        assert_eq!(curve.sample(0.0), 1.0);
+        assert_eq!(curve.sample(-1.0), 1.0);
+        assert_eq!(curve.sample(1.0), 2.0);
+        assert_eq!(curve.sample(2.0), 2.0);
+    }
+
+    #[test]
+    fn keys_are_sorted_by_time() {
+        let curve = Curve::new(vec![(1.0, 4.0), (0.0, 0.0)]);
+
+        assert_eq!(curve.sample(0.25), 1.0);
+    }
+
+    #[test]
+    fn keys_at_the_same_time_step_without_dividing_by_zero() {
+        let curve = Curve::new(vec![(0.0, 0.0), (0.5, 1.0), (0.5, 5.0), (1.0, 5.0)]);
+
+        assert_eq!(curve.sample(0.5), 1.0);
+        assert_eq!(curve.sample(0.75), 5.0);
+    }
+
+    #[test]
+    fn constant_curves_sample_the_same_everywhere() {
+        let curve = Curve::constant([1.0, 0.5, 0.25, 1.0]);
+
+        for t in [0.0, 0.3, 1.0] {
+            assert_eq!(curve.sample(t), [1.0, 0.5, 0.25, 1.0]);
+        }
+    }
+
+    #[test]
+    fn bake_samples_from_the_start_of_life_to_the_end() {
+        let samples = Curve::linear(0.0, 15.0).bake();
+
+        assert_eq!(samples[0], 0.0);
+        assert_eq!(samples[1], 1.0);
+        assert_eq!(samples[CURVE_SAMPLES - 1], 15.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one key")]
+    fn curves_need_a_key() {
+        Curve::<f32>::new(Vec::new());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::core::game::EntityId;
+use crate::utils::log;
+use std::sync::{Arc, Mutex};
+
+/// format of the entity id target written alongside the opaque pass's colour - each pixel holds
+/// the id of the entity drawn there plus one, a byte per channel, so 0 is nothing. it's split
+/// over bytes as 32 bit integer formats can't be multisampled
+pub const ENTITY_ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Uint;
+
+/// a pick on its way back from the gpu, @see Renderer::pick
+pub struct PendingPick {
+    buffer: Arc<wgpu::Buffer>,
+    receiver: tokio::sync::oneshot::Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+impl PendingPick {
+    /// The picked entity once the read back has arrived, none while it's on its way - so check
+    /// every frame. Some(None) when nothing was drawn at the pixel, or the read back failed
+    pub fn poll(&mut self, device: &Mutex<wgpu::Device>) -> Option<Option<EntityId>> {
+        // native backends only run map callbacks while the device is polled
+        device.lock().unwrap().poll(wgpu::Maintain::Poll);
+
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return None,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => Err(wgpu::BufferAsyncError),
+        };
+
+        if let Err(e) = result {
+            log::warn(&format!("failed to read back pick: {}", e));
+            return Some(None);
+        }
+
+        let id = {
+            let bytes = self.buffer.slice(..).get_mapped_range();
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        };
+
+        self.buffer.unmap();
+
+        Some(id.checked_sub(1))
+    }
+}
+
+/// a pixel to copy out of the entity id target at the end of the next frame
+struct PickRequest {
+    x: u32,
+    y: u32,
+    buffer: Arc<wgpu::Buffer>,
+    sender: tokio::sync::oneshot::Sender<Result<(), wgpu::BufferAsyncError>>,
+    copied: bool,
+}
+
+/// Picks waiting on the entity id target - the opaque pass writes the ids, and MeshRenderer copies
+/// out the requested pixels after the graph has run and maps them once the frame is submitted
+#[derive(Default)]
+pub struct Picking {
+    pub enabled: bool,
+    requests: Vec<PickRequest>,
+}
+
+impl Picking {
+    /// Ask for the entity at a pixel in the next frame drawn
+    pub fn request(&mut self, device: &wgpu::Device, x: u32, y: u32) -> PendingPick {
+        let buffer = Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Buffer"),
+            size: std::mem::size_of::<u32>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        }));
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+
+        self.requests.push(PickRequest {
+            x,
+            y,
+            buffer: buffer.clone(),
+            sender,
+            copied: false,
+        });
+
+        PendingPick { buffer, receiver }
+    }
+
+    /// Drop picks that haven't been read back, their pending handles report nothing
+    pub fn clear_requests(&mut self) {
+        self.requests.clear();
+    }
+
+    /// Copy each requested pixel out of the frame's entity ids, after the passes that write them
+    pub fn copy_requests(&mut self, encoder: &mut wgpu::CommandEncoder, ids: &wgpu::Texture) {
+        // the window shrank since they were asked for, dropping them reports nothing
+        self.requests
+            .retain(|request| request.x < ids.width() && request.y < ids.height());
+
+        for request in self.requests.iter_mut() {
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture: ids,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: request.x,
+                        y: request.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &request.buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: None,
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            request.copied = true;
+        }
+    }
+
+    /// Start mapping the copied picks once the frame that copies them has been submitted - picks
+    /// that weren't copied, as nothing wrote entity ids this frame, wait for the next one
+    pub fn map_requests(&mut self) {
+        let (copied, waiting): (Vec<PickRequest>, Vec<PickRequest>) =
+            std::mem::take(&mut self.requests)
+                .into_iter()
+                .partition(|request| request.copied);
+
+        self.requests = waiting;
+
+        for request in copied {
+            let sender = request.sender;
+
+            request
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+        }
+    }
+}
@@ -0,0 +1,218 @@
+/// format of the tonemapped scene and the textures the post processing stack ping-pongs between
+pub const LDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// a fullscreen effect applied after tonemapping, @see post.wgsl
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostEffect {
+    // exponential fog by view distance from the depth buffer, starting at `start`
+    Fog {
+        colour: [f32; 3],
+        density: f32,
+        start: f32,
+    },
+    // 2d strip lookup table, N*N wide and N high with blue across the slices - identity if none
+    ColourGrading {
+        lut: Option<String>,
+        strength: f32,
+    },
+    Vignette {
+        intensity: f32,
+        // distance from the centre, as a fraction of the half diagonal, where darkening starts
+        radius: f32,
+        softness: f32,
+    },
+    // fast approximate anti-aliasing
+    Fxaa {
+        span_max: f32,
+        reduce_mul: f32,
+        reduce_min: f32,
+    },
+}
+
+impl PostEffect {
+    pub fn fog() -> Self {
+        PostEffect::Fog {
+            colour: [0.6, 0.65, 0.7],
+            density: 0.05,
+            start: 5.0,
+        }
+    }
+
+    pub fn colour_grading(lut: Option<&str>) -> Self {
+        PostEffect::ColourGrading {
+            lut: lut.map(|lut| lut.to_string()),
+            strength: 1.0,
+        }
+    }
+
+    pub fn vignette() -> Self {
+        PostEffect::Vignette {
+            intensity: 0.5,
+            radius: 0.75,
+            softness: 0.45,
+        }
+    }
+
+    pub fn fxaa() -> Self {
+        PostEffect::Fxaa {
+            span_max: 8.0,
+            reduce_mul: 1.0 / 8.0,
+            reduce_min: 1.0 / 128.0,
+        }
+    }
+
+    /// name used to find the effect in the stack
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostEffect::Fog { .. } => "fog",
+            PostEffect::ColourGrading { .. } => "colour_grading",
+            PostEffect::Vignette { .. } => "vignette",
+            PostEffect::Fxaa { .. } => "fxaa",
+        }
+    }
+
+    /// fragment entry point in post.wgsl
+    pub fn entry_point(&self) -> &'static str {
+        match self {
+            PostEffect::Fog { .. } => "fs_fog",
+            PostEffect::ColourGrading { .. } => "fs_colour_grading",
+            PostEffect::Vignette { .. } => "fs_vignette",
+            PostEffect::Fxaa { .. } => "fs_fxaa",
+        }
+    }
+
+    /// parameters as laid out in PostUniforms
+    pub fn params(&self) -> [[f32; 4]; 2] {
+        match self {
+            PostEffect::Fog {
+                colour,
+                density,
+                start,
+            } => [
+                [colour[0], colour[1], colour[2], 0.0],
+                [*density, *start, 0.0, 0.0],
+            ],
+            PostEffect::ColourGrading { strength, .. } => [[*strength, 0.0, 0.0, 0.0], [0.0; 4]],
+            PostEffect::Vignette {
+                intensity,
+                radius,
+                softness,
+            } => [[*intensity, *radius, *softness, 0.0], [0.0; 4]],
+            PostEffect::Fxaa {
+                span_max,
+                reduce_mul,
+                reduce_min,
+            } => [[*span_max, *reduce_mul, *reduce_min, 0.0], [0.0; 4]],
+        }
+    }
+}
+
+pub struct PostStackEntry {
+    pub effect: PostEffect,
+    pub enabled: bool,
+}
+
+/// effects run in order on the tonemapped scene, the last one writing to the surface
+pub struct PostStack {
+    entries: Vec<PostStackEntry>,
+}
+
+impl Default for PostStack {
+    /// Every built in effect, disabled
+    fn default() -> Self {
+        let entries = [
+            PostEffect::fog(),
+            PostEffect::colour_grading(None),
+            PostEffect::vignette(),
+            PostEffect::fxaa(),
+        ]
+        .into_iter()
+        .map(|effect| PostStackEntry {
+            effect,
+            enabled: false,
+        })
+        .collect();
+
+        Self { entries }
+    }
+}
+
+// for game code to rearrange and tweak the stack, the test world leaves it as it is
+#[allow(dead_code)]
+impl PostStack {
+    /// Add an enabled effect to the end of the stack
+    pub fn push(&mut self, effect: PostEffect) {
+        self.entries.push(PostStackEntry {
+            effect,
+            enabled: true,
+        });
+    }
+
+    /// Add an enabled effect at a position in the stack
+    pub fn insert(&mut self, index: usize, effect: PostEffect) {
+        self.entries.insert(
+            index.min(self.entries.len()),
+            PostStackEntry {
+                effect,
+                enabled: true,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PostEffect> {
+        let index = self.index_of(name)?;
+
+        Some(self.entries.remove(index).effect)
+    }
+
+    /// Move an effect to a new position in the stack
+    pub fn move_to(&mut self, name: &str, index: usize) {
+        if let Some(current) = self.index_of(name) {
+            let entry = self.entries.remove(current);
+            self.entries.insert(index.min(self.entries.len()), entry);
+        }
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        for entry in self.entries.iter_mut() {
+            if entry.effect.name() == name {
+                entry.enabled = enabled;
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.effect.name() == name && entry.enabled)
+    }
+
+    /// The effect's parameters, for changing them at runtime
+    pub fn effect_mut(&mut self, name: &str) -> Option<&mut PostEffect> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.effect.name() == name)
+            .map(|entry| &mut entry.effect)
+    }
+
+    pub fn entries(&self) -> &[PostStackEntry] {
+        &self.entries
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.effect.name() == name)
+    }
+}
+
+/// uniform layout for the post processing shader - must match the Post struct in post.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostUniforms {
+    pub params: [[f32; 4]; 2],
+    // texel width, texel height, 1.0 if the output needs gamma encoding
+    pub output_target: [f32; 4],
+    // camera near and far planes, for linearising depth
+    pub depth: [f32; 4],
+}
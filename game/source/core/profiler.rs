@@ -0,0 +1,481 @@
+use crate::core::text::FontId;
+use std::collections::VecDeque;
+use std::fs;
+use std::time::Instant;
+
+/// frames the timings shown in the overlay are averaged over
+pub const AVERAGE_FRAMES: usize = 60;
+
+/// frames of samples kept for exporting a trace
+pub const TRACE_FRAMES: usize = 300;
+
+/// most render passes timed on the gpu in a frame
+pub const MAX_GPU_SCOPES: u32 = 32;
+
+/// frames of gpu timestamps that can be waiting to be read back at once - frames beyond that
+/// aren't timed on the gpu
+const GPU_READBACK_FRAMES: usize = 3;
+
+/// which timeline a scope was measured on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileTrack {
+    // systems, timed on the thread running them
+    Cpu,
+    // render passes, timed with timestamp queries
+    Gpu,
+}
+
+/// one timed scope, in milliseconds since the profiler was created
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileSample {
+    pub name: &'static str,
+    pub track: ProfileTrack,
+    pub start: f64,
+    pub duration: f64,
+}
+
+/// a scope's time over the last few frames it ran in
+#[derive(Debug, Clone)]
+pub struct ScopeStats {
+    pub name: &'static str,
+    pub track: ProfileTrack,
+    // milliseconds per frame, oldest first
+    history: VecDeque<f64>,
+    last_frame: u64,
+}
+
+impl ScopeStats {
+    /// Average milliseconds per frame
+    pub fn average(&self) -> f64 {
+        self.history.iter().sum::<f64>() / self.history.len().max(1) as f64
+    }
+}
+
+/// a frame's timestamps on their way back from the gpu
+struct GpuReadback {
+    buffer: wgpu::Buffer,
+    // passes in the order their timestamps were written
+    passes: Vec<&'static str>,
+    // when the frame was recorded, to line the gpu track up with the cpu one
+    cpu_start: f64,
+    receiver: Option<tokio::sync::oneshot::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    in_use: bool,
+}
+
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readbacks: Vec<GpuReadback>,
+    // nanoseconds per timestamp tick
+    period: f64,
+    // readback taking this frame's timestamps, none if they're all still in use
+    current: Option<usize>,
+}
+
+impl GpuTimer {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let size = timestamps_size(MAX_GPU_SCOPES as usize);
+
+        let readbacks = (0..GPU_READBACK_FRAMES)
+            .map(|_| GpuReadback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                passes: Vec::new(),
+                cpu_start: 0.0,
+                receiver: None,
+                in_use: false,
+            })
+            .collect();
+
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Profiler Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: MAX_GPU_SCOPES * 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Profiler Resolve Buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readbacks,
+            period: queue.get_timestamp_period() as f64,
+            current: None,
+        }
+    }
+}
+
+/// Times systems on the cpu and render passes on the gpu, keeping rolling averages for the
+/// overlay drawn by the ui pass and recent frames for exporting as a chrome trace
+///
+/// gpu timing needs the adapter to support timestamp queries, including inside encoders since
+/// passes begin their own render passes. without them only the cpu is timed.
+pub struct Profiler {
+    enabled: bool,
+    overlay: bool,
+    // labels need a font, without one the overlay is only bars
+    overlay_font: Option<FontId>,
+    epoch: Instant,
+    frame: u64,
+    frame_start: f64,
+    frame_times: VecDeque<f64>,
+    // samples from the frame in progress, and gpu samples that arrived during it
+    pending: Vec<ProfileSample>,
+    // finished frames, oldest first
+    trace: VecDeque<Vec<ProfileSample>>,
+    // in the order scopes were first seen
+    stats: Vec<ScopeStats>,
+    gpu: Option<GpuTimer>,
+}
+
+impl Profiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let gpu = device
+            .features()
+            .contains(
+                wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+            )
+            .then(|| GpuTimer::new(device, queue));
+
+        Self {
+            enabled: true,
+            overlay: false,
+            overlay_font: None,
+            epoch: Instant::now(),
+            frame: 0,
+            frame_start: 0.0,
+            frame_times: VecDeque::new(),
+            pending: Vec::new(),
+            trace: VecDeque::new(),
+            stats: Vec::new(),
+            gpu,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Stop or start timing - the averages and trace are kept while stopped
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.frame_start = self.now();
+            self.pending.clear();
+        }
+
+        self.enabled = enabled;
+    }
+
+    pub fn overlay(&self) -> bool {
+        self.overlay
+    }
+
+    pub fn set_overlay(&mut self, overlay: bool) {
+        self.overlay = overlay;
+    }
+
+    pub fn overlay_font(&self) -> Option<FontId> {
+        self.overlay_font
+    }
+
+    pub fn set_overlay_font(&mut self, font: Option<FontId>) {
+        self.overlay_font = font;
+    }
+
+    /// Whether render passes are timed on the gpu
+    pub fn gpu_supported(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    /// Milliseconds since the profiler was created, for timing scopes with record_cpu
+    pub fn now(&self) -> f64 {
+        self.epoch.elapsed().as_secs_f64() * 1000.0
+    }
+
+    /// Record a scope on the cpu that started at a time from now, and ends now
+    pub fn record_cpu(&mut self, name: &'static str, start: f64) {
+        let duration = self.now() - start;
+        self.record(name, ProfileTrack::Cpu, start, duration);
+    }
+
+    /// Record a scope timed some other way, in milliseconds from now
+    pub fn record(&mut self, name: &'static str, track: ProfileTrack, start: f64, duration: f64) {
+        if self.enabled {
+            self.pending.push(ProfileSample {
+                name,
+                track,
+                start,
+                duration,
+            });
+        }
+    }
+
+    /// Fold the frame's samples into the averages and trace - called once all the draw systems
+    /// have run
+    pub fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = self.now();
+        let frame = self.frame;
+
+        push_capped(
+            &mut self.frame_times,
+            now - self.frame_start,
+            AVERAGE_FRAMES,
+        );
+
+        // scopes that ran more than once add up
+        let mut totals: Vec<(&'static str, ProfileTrack, f64)> = Vec::new();
+
+        for sample in self.pending.iter() {
+            match totals
+                .iter_mut()
+                .find(|(name, track, _)| *name == sample.name && *track == sample.track)
+            {
+                Some((_, _, total)) => *total += sample.duration,
+                None => totals.push((sample.name, sample.track, sample.duration)),
+            }
+        }
+
+        for (name, track, total) in totals {
+            let index = match self
+                .stats
+                .iter()
+                .position(|stats| stats.name == name && stats.track == track)
+            {
+                Some(index) => index,
+                None => {
+                    self.stats.push(ScopeStats {
+                        name,
+                        track,
+                        history: VecDeque::new(),
+                        last_frame: frame,
+                    });
+                    self.stats.len() - 1
+                }
+            };
+
+            let stats = &mut self.stats[index];
+            push_capped(&mut stats.history, total, AVERAGE_FRAMES);
+            stats.last_frame = frame;
+        }
+
+        // scopes that stopped running, such as disabled passes, drop out of the overlay
+        self.stats
+            .retain(|stats| frame - stats.last_frame < AVERAGE_FRAMES as u64);
+
+        let mut samples = std::mem::take(&mut self.pending);
+
+        samples.push(ProfileSample {
+            name: "frame",
+            track: ProfileTrack::Cpu,
+            start: self.frame_start,
+            duration: now - self.frame_start,
+        });
+
+        push_capped(&mut self.trace, samples, TRACE_FRAMES);
+
+        self.frame += 1;
+        self.frame_start = now;
+    }
+
+    /// Average milliseconds between frames
+    pub fn frame_time(&self) -> f64 {
+        self.frame_times.iter().sum::<f64>() / self.frame_times.len().max(1) as f64
+    }
+
+    /// Rolling averages of every scope run recently, cpu and gpu
+    pub fn stats(&self) -> &[ScopeStats] {
+        &self.stats
+    }
+
+    /// Read back gpu timestamps from earlier frames that have finished, and pick where this
+    /// frame's go - called before the render graph executes
+    pub fn begin_gpu_frame(&mut self, device: &wgpu::Device) {
+        let gpu = match self.gpu.as_mut() {
+            Some(gpu) => gpu,
+            None => return,
+        };
+
+        // native backends only run map callbacks while the device is polled
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut samples = Vec::new();
+
+        for readback in gpu.readbacks.iter_mut() {
+            let mapped = match readback
+                .receiver
+                .as_mut()
+                .map(|receiver| receiver.try_recv())
+            {
+                // still on its way
+                Some(Err(tokio::sync::oneshot::error::TryRecvError::Empty)) => continue,
+                Some(Ok(Ok(()))) => true,
+                _ => false,
+            };
+
+            if mapped {
+                let slice = readback
+                    .buffer
+                    .slice(..timestamps_size(readback.passes.len()));
+
+                {
+                    let data = slice.get_mapped_range();
+                    let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                    let first = timestamps.first().copied().unwrap_or_default();
+                    let to_ms = |ticks: u64| ticks as f64 * gpu.period / 1_000_000.0;
+
+                    for (index, &name) in readback.passes.iter().enumerate() {
+                        let start = timestamps[index * 2];
+                        let end = timestamps[index * 2 + 1];
+
+                        samples.push(ProfileSample {
+                            name,
+                            track: ProfileTrack::Gpu,
+                            start: readback.cpu_start + to_ms(start.saturating_sub(first)),
+                            duration: to_ms(end.saturating_sub(start)),
+                        });
+                    }
+                }
+
+                readback.buffer.unmap();
+            }
+
+            readback.receiver = None;
+            readback.in_use = false;
+        }
+
+        gpu.current = gpu.readbacks.iter().position(|readback| !readback.in_use);
+
+        if self.enabled {
+            self.pending.extend(samples);
+        }
+    }
+
+    /// The query set passes write their timestamps into this frame - none if the gpu isn't being
+    /// timed, or earlier frames' timestamps are still being read back
+    pub fn timestamp_queries(&self) -> Option<&wgpu::QuerySet> {
+        match &self.gpu {
+            Some(gpu) if self.enabled && gpu.current.is_some() => Some(&gpu.query_set),
+            _ => None,
+        }
+    }
+
+    /// Copy this frame's timestamps somewhere they can be read from, given the passes that wrote
+    /// them in order - called once the graph has executed
+    pub fn resolve_gpu_frame(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        passes: Vec<&'static str>,
+    ) {
+        let cpu_start = self.frame_start;
+
+        let gpu = match self.gpu.as_mut() {
+            Some(gpu) if !passes.is_empty() => gpu,
+            _ => return,
+        };
+
+        let readback = match gpu.current.and_then(|index| gpu.readbacks.get_mut(index)) {
+            Some(readback) => readback,
+            None => return,
+        };
+
+        let size = timestamps_size(passes.len());
+
+        encoder.resolve_query_set(
+            &gpu.query_set,
+            0..passes.len() as u32 * 2,
+            &gpu.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(&gpu.resolve_buffer, 0, &readback.buffer, 0, size);
+
+        readback.passes = passes;
+        readback.cpu_start = cpu_start;
+        readback.in_use = true;
+    }
+
+    /// Start reading back this frame's timestamps - called once the frame's been submitted
+    pub fn map_gpu_frame(&mut self) {
+        let gpu = match self.gpu.as_mut() {
+            Some(gpu) => gpu,
+            None => return,
+        };
+
+        let readback = match gpu
+            .current
+            .take()
+            .and_then(|index| gpu.readbacks.get_mut(index))
+        {
+            Some(readback) if readback.in_use && readback.receiver.is_none() => readback,
+            _ => return,
+        };
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+
+        readback
+            .buffer
+            .slice(..timestamps_size(readback.passes.len()))
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+
+        readback.receiver = Some(receiver);
+    }
+
+    /// The recorded frames as chrome trace event json, for chrome://tracing or perfetto
+    pub fn trace_json(&self) -> String {
+        let mut events = vec![
+            r#"{"name":"thread_name","ph":"M","pid":0,"tid":0,"args":{"name":"cpu"}}"#.to_string(),
+            r#"{"name":"thread_name","ph":"M","pid":0,"tid":1,"args":{"name":"gpu"}}"#.to_string(),
+        ];
+
+        for sample in self.trace.iter().flatten() {
+            let (category, thread) = match sample.track {
+                ProfileTrack::Cpu => ("cpu", 0),
+                ProfileTrack::Gpu => ("gpu", 1),
+            };
+
+            // timestamps are in microseconds
+            events.push(format!(
+                r#"{{"name":"{}","cat":"{}","ph":"X","ts":{:.3},"dur":{:.3},"pid":0,"tid":{}}}"#,
+                sample.name.replace('\\', "\\\\").replace('"', "\\\""),
+                category,
+                sample.start * 1000.0,
+                sample.duration * 1000.0,
+                thread
+            ));
+        }
+
+        format!(
+            "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+            events.join(",\n")
+        )
+    }
+
+    /// Write the last TRACE_FRAMES frames to a chrome trace file
+    pub fn export_trace(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.trace_json())
+            .map_err(|e| format!("failed to write trace {}: {}", path, e))
+    }
+}
+
+// bytes taken by the start and end timestamps of a number of passes
+fn timestamps_size(passes: usize) -> wgpu::BufferAddress {
+    (passes * 2) as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress
+}
+
+fn push_capped<T>(queue: &mut VecDeque<T>, value: T, capacity: usize) {
+    if queue.len() == capacity {
+        queue.pop_front();
+    }
+
+    queue.push_back(value);
+}
@@ -0,0 +1,825 @@
+use crate::core::profiler::MAX_GPU_SCOPES;
+use crate::core::renderer::Renderer;
+use crate::core::{game, hdr, post};
+use crate::passes::{
+    bloom_pass, debug_draw_pass, debug_view_pass, depth_resolve_pass, entity_id_resolve_pass,
+    mesh_pass, particle_pass, post_process_pass, shadow_pass, sky_pass, sprite_pass, text_pass,
+    tonemap_pass, ui_pass,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// the swapchain texture being presented this frame
+pub const SURFACE: &str = "surface";
+
+/// the main depth buffer, @see Renderer::depth_view
+pub const DEPTH: &str = "depth";
+
+/// the lit scene, before tonemapping
+pub const HDR: &str = "hdr";
+
+/// the lit scene while multisampling, resolved into HDR - only declared when msaa is enabled
+pub const MSAA_HDR: &str = "hdr_msaa";
+
+/// the scene's depth while multisampling, @see Renderer::msaa_depth_view
+pub const MSAA_DEPTH: &str = "depth_msaa";
+
+/// everything a pass drawing into the scene writes, whether or not msaa is enabled
+pub const SCENE_OUTPUTS: [&str; 4] = [HDR, MSAA_HDR, DEPTH, MSAA_DEPTH];
+
+/// the bloom chain, each half the size of the last - the first holds the finished bloom
+pub const BLOOM_TEXTURES: [&str; hdr::BLOOM_MIP_COUNT] =
+    ["bloom_0", "bloom_1", "bloom_2", "bloom_3", "bloom_4"];
+
+/// the tonemapped scene, before post processing
+pub const LDR: &str = "ldr";
+
+/// textures the post processing stack ping-pongs between
+pub const POST_TEXTURES: [&str; 2] = ["post_a", "post_b"];
+
+/// entity ids for mouse picking, written by the opaque pass and copied out once the graph has run -
+/// only declared while picking is enabled, @see Picking
+pub const ENTITY_IDS: &str = "entity_ids";
+
+/// the entity ids while multisampling, resolved into ENTITY_IDS - only declared while picking and
+/// msaa are both enabled
+pub const MSAA_ENTITY_IDS: &str = "entity_ids_msaa";
+
+/// every light's shadow maps - owned by the renderer and sampled through the global bind group
+pub const SHADOW_MAPS: &str = "shadow_maps";
+
+/// size of a graph texture, relative to the surface so targets follow window resizes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureSize {
+    Surface,
+    Scaled(f32),
+    #[allow(dead_code)]
+    Fixed(u32, u32),
+}
+
+impl TextureSize {
+    pub fn resolve(&self, surface_width: u32, surface_height: u32) -> (u32, u32) {
+        let (width, height) = match self {
+            TextureSize::Surface => (surface_width, surface_height),
+            TextureSize::Scaled(scale) => (
+                (surface_width as f32 * scale) as u32,
+                (surface_height as f32 * scale) as u32,
+            ),
+            TextureSize::Fixed(width, height) => (*width, *height),
+        };
+
+        (width.max(1), height.max(1))
+    }
+}
+
+/// description of a transient texture, allocated by the graph for the passes that use it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureDesc {
+    pub format: wgpu::TextureFormat,
+    pub size: TextureSize,
+    pub sample_count: u32,
+}
+
+impl TextureDesc {
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            size: TextureSize::Surface,
+            sample_count: 1,
+        }
+    }
+
+    pub fn with_size(mut self, size: TextureSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+/// a mesh to draw this frame, with the offset of its transform in the renderer's transform buffer
+#[derive(Debug, Clone, Copy)]
+pub struct MeshDraw {
+    pub entity_id: game::EntityId,
+    pub transform_offset: u32,
+    // level of detail to draw, @see MeshComponent::buffers
+    pub lod: usize,
+}
+
+/// meshes gathered by MeshRenderer for the graph's passes
+#[derive(Default)]
+pub struct DrawLists {
+    // every buffered mesh, including those outside the camera frustum
+    pub shadow_casters: Vec<MeshDraw>,
+    pub opaque: Vec<MeshDraw>,
+    // sorted back to front
+    pub transparent: Vec<MeshDraw>,
+}
+
+/// a step of rendering - passes declare the textures they read and write, and the graph orders them
+pub trait GraphPass: Send + Sync {
+    /// unique name, used to insert passes relative to each other
+    fn name(&self) -> &'static str;
+
+    fn inputs(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<&'static str>;
+
+    /// Called every frame before any pass executes, to create pipelines and write buffers
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        _renderer: &mut Renderer,
+    ) {
+    }
+
+    /// Record the pass's commands
+    fn execute(&mut self, context: &mut PassContext, world: &game::World, renderer: &Renderer);
+}
+
+/// what a pass can see while it prepares
+pub struct PrepareContext<'a> {
+    textures: &'a HashMap<&'static str, TextureDesc>,
+    surface_format: wgpu::TextureFormat,
+}
+
+impl<'a> PrepareContext<'a> {
+    #[allow(dead_code)]
+    pub fn texture_desc(&self, name: &str) -> Option<&'a TextureDesc> {
+        self.textures.get(name)
+    }
+
+    /// Format of a declared or imported texture, for creating pipelines that render into it
+    pub fn format(&self, name: &str) -> wgpu::TextureFormat {
+        match self.textures.get(name) {
+            Some(desc) => desc.format,
+            None if name == DEPTH || name == MSAA_DEPTH => wgpu::TextureFormat::Depth32Float,
+            None => self.surface_format,
+        }
+    }
+}
+
+/// what a pass can see while it executes
+pub struct PassContext<'a> {
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    pub draws: &'a DrawLists,
+    views: &'a HashMap<&'static str, &'a wgpu::TextureView>,
+    sizes: &'a HashMap<&'static str, (u32, u32)>,
+    written: &'a HashSet<&'static str>,
+    generation: u64,
+}
+
+impl<'a> PassContext<'a> {
+    /// The view of a texture declared in, or imported into, the graph
+    pub fn view(&self, name: &str) -> &'a wgpu::TextureView {
+        match self.views.get(name) {
+            Some(view) => view,
+            None => panic!("texture {} is not declared in the render graph", name),
+        }
+    }
+
+    pub fn size(&self, name: &str) -> (u32, u32) {
+        self.sizes.get(name).copied().unwrap_or((1, 1))
+    }
+
+    /// Changes whenever the graph's own textures are recreated or reassigned, so passes can keep
+    /// bind groups over them until it does - imported textures such as the surface aren't covered
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Clear a colour target if no earlier pass has written it this frame, otherwise keep its contents
+    pub fn colour_load(&self, name: &str, clear: wgpu::Color) -> wgpu::LoadOp<wgpu::Color> {
+        if self.written.contains(name) {
+            wgpu::LoadOp::Load
+        } else {
+            wgpu::LoadOp::Clear(clear)
+        }
+    }
+
+    /// Clear a depth target to the far plane if no earlier pass has written it this frame
+    pub fn depth_load(&self, name: &str) -> wgpu::LoadOp<f32> {
+        if self.written.contains(name) {
+            wgpu::LoadOp::Load
+        } else {
+            wgpu::LoadOp::Clear(1.0)
+        }
+    }
+}
+
+struct PhysicalTexture {
+    desc: TextureDesc,
+    width: u32,
+    height: u32,
+    // created on first use, and again when the surface size changes
+    texture: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+/// ordered set of passes that make up a frame
+///
+/// passes run after every pass that writes a texture they read, and passes writing the same texture
+/// run in the order they were added. transient textures are allocated by the graph, and textures
+/// whose lifetimes don't overlap share memory - so they don't keep their contents between frames.
+#[derive(Default)]
+pub struct RenderGraph {
+    passes: Vec<Box<dyn GraphPass>>,
+    disabled: HashSet<&'static str>,
+    textures: HashMap<&'static str, TextureDesc>,
+    // indices into passes, in execution order
+    order: Vec<usize>,
+    // physical texture backing each transient texture
+    assignments: HashMap<&'static str, usize>,
+    physical: Vec<PhysicalTexture>,
+    needs_compile: bool,
+    // bumped when the views behind transient textures change, @see PassContext::generation
+    generation: u64,
+}
+
+impl RenderGraph {
+    /// The graph used by MeshRenderer - shadow maps, opaque meshes, the sky, transparent meshes and
+    /// particles into the hdr target, then bloom, tonemapping and post processing onto the surface, with text and the ui
+    /// drawn over the top
+    pub fn with_default_passes() -> Self {
+        let mut graph = Self::default();
+
+        graph.declare_texture(HDR, TextureDesc::new(hdr::HDR_FORMAT));
+
+        for (level, name) in BLOOM_TEXTURES.iter().enumerate() {
+            graph.declare_texture(
+                name,
+                TextureDesc::new(hdr::HDR_FORMAT)
+                    .with_size(TextureSize::Scaled(0.5_f32.powi(level as i32 + 1))),
+            );
+        }
+
+        graph.declare_texture(LDR, TextureDesc::new(post::LDR_FORMAT));
+
+        for name in POST_TEXTURES {
+            graph.declare_texture(name, TextureDesc::new(post::LDR_FORMAT));
+        }
+
+        graph.add_pass(Box::new(shadow_pass::ShadowPass {}));
+        graph.add_pass(Box::new(mesh_pass::MeshPass::opaque()));
+        graph.add_pass(Box::new(sky_pass::SkyPass::new()));
+        graph.add_pass(Box::new(mesh_pass::MeshPass::transparent()));
+        graph.add_pass(Box::new(particle_pass::ParticlePass::new()));
+        graph.add_pass(Box::new(debug_view_pass::DebugViewPass::new()));
+        graph.add_pass(Box::new(entity_id_resolve_pass::EntityIdResolvePass::new()));
+        graph.add_pass(Box::new(debug_draw_pass::DebugDrawPass::new()));
+        graph.add_pass(Box::new(depth_resolve_pass::DepthResolvePass::new()));
+        graph.add_pass(Box::new(bloom_pass::BloomPass::new()));
+        graph.add_pass(Box::new(tonemap_pass::TonemapPass::new(LDR)));
+        graph.add_pass(Box::new(post_process_pass::PostProcessPass::new()));
+        graph.add_pass(Box::new(sprite_pass::SpritePass::new()));
+        graph.add_pass(Box::new(text_pass::TextPass::new()));
+        graph.add_pass(Box::new(ui_pass::UiPass::new()));
+
+        // swapped in for the mesh passes by Renderer::set_view_mode
+        graph.set_pass_enabled("debug_view", false);
+
+        graph
+    }
+
+    pub fn add_pass(&mut self, pass: Box<dyn GraphPass>) {
+        self.passes.push(pass);
+        self.needs_compile = true;
+    }
+
+    /// Add a pass directly before another, so it runs first when neither depends on the other
+    #[allow(dead_code)]
+    pub fn insert_pass_before(
+        &mut self,
+        before: &str,
+        pass: Box<dyn GraphPass>,
+    ) -> Result<(), String> {
+        let index = self.pass_index(before)?;
+
+        self.passes.insert(index, pass);
+        self.needs_compile = true;
+
+        Ok(())
+    }
+
+    /// Add a pass directly after another, so it runs later when neither depends on the other
+    #[allow(dead_code)]
+    pub fn insert_pass_after(
+        &mut self,
+        after: &str,
+        pass: Box<dyn GraphPass>,
+    ) -> Result<(), String> {
+        let index = self.pass_index(after)?;
+
+        self.passes.insert(index + 1, pass);
+        self.needs_compile = true;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn remove_pass(&mut self, name: &str) -> Option<Box<dyn GraphPass>> {
+        let index = self.pass_index(name).ok()?;
+
+        self.needs_compile = true;
+
+        Some(self.passes.remove(index))
+    }
+
+    #[allow(dead_code)]
+    pub fn has_pass(&self, name: &str) -> bool {
+        self.pass_index(name).is_ok()
+    }
+
+    /// Disabled passes are skipped, along with any ordering they imply
+    pub fn set_pass_enabled(&mut self, name: &'static str, enabled: bool) {
+        let changed = if enabled {
+            self.disabled.remove(name)
+        } else {
+            self.disabled.insert(name)
+        };
+
+        self.needs_compile |= changed;
+    }
+
+    #[allow(dead_code)]
+    pub fn is_pass_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    /// Declare a transient texture that passes can read and write by name
+    pub fn declare_texture(&mut self, name: &'static str, desc: TextureDesc) {
+        if self.textures.insert(name, desc) != Some(desc) {
+            self.needs_compile = true;
+        }
+    }
+
+    /// Stop allocating a transient texture - passes can still name it for ordering
+    pub fn remove_texture(&mut self, name: &str) {
+        if self.textures.remove(name).is_some() {
+            self.needs_compile = true;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn texture_desc(&self, name: &str) -> Option<&TextureDesc> {
+        self.textures.get(name)
+    }
+
+    /// The physical texture behind a transient texture, once it's been allocated - for copying out
+    /// of it after the graph has run, before another frame reuses it
+    pub fn texture(&self, name: &str) -> Option<&wgpu::Texture> {
+        let slot = *self.assignments.get(name)?;
+
+        self.physical[slot]
+            .texture
+            .as_ref()
+            .map(|(texture, _)| texture)
+    }
+
+    /// Names of the enabled passes, in the order they'll execute
+    #[allow(dead_code)]
+    pub fn pass_names(&mut self) -> Result<Vec<&'static str>, String> {
+        self.compile()?;
+
+        Ok(self
+            .order
+            .iter()
+            .map(|&index| self.passes[index].name())
+            .collect())
+    }
+
+    fn pass_index(&self, name: &str) -> Result<usize, String> {
+        self.passes
+            .iter()
+            .position(|pass| pass.name() == name)
+            .ok_or_else(|| format!("no render graph pass named {}", name))
+    }
+
+    /// Order the enabled passes by their dependencies, and assign transient textures to physical ones
+    fn compile(&mut self) -> Result<(), String> {
+        if !self.needs_compile {
+            return Ok(());
+        }
+
+        let enabled: Vec<usize> = (0..self.passes.len())
+            .filter(|&index| !self.disabled.contains(self.passes[index].name()))
+            .collect();
+
+        let inputs: HashMap<usize, Vec<&'static str>> = enabled
+            .iter()
+            .map(|&index| (index, self.passes[index].inputs()))
+            .collect();
+        let outputs: HashMap<usize, Vec<&'static str>> = enabled
+            .iter()
+            .map(|&index| (index, self.passes[index].outputs()))
+            .collect();
+
+        // writers of each texture, in the order they were added
+        let mut writers: HashMap<&'static str, Vec<usize>> = HashMap::new();
+
+        for &index in enabled.iter() {
+            for &name in outputs[&index].iter() {
+                writers.entry(name).or_default().push(index);
+            }
+        }
+
+        let mut dependencies: HashMap<usize, HashSet<usize>> = enabled
+            .iter()
+            .map(|&index| (index, HashSet::new()))
+            .collect();
+
+        for texture_writers in writers.values() {
+            for pair in texture_writers.windows(2) {
+                dependencies.get_mut(&pair[1]).unwrap().insert(pair[0]);
+            }
+        }
+
+        for &index in enabled.iter() {
+            for &name in inputs[&index].iter() {
+                match writers.get(name) {
+                    Some(texture_writers) => {
+                        // a pass that also writes the texture is already ordered among its writers
+                        if outputs[&index].contains(&name) {
+                            continue;
+                        }
+
+                        for &writer in texture_writers.iter() {
+                            dependencies.get_mut(&index).unwrap().insert(writer);
+                        }
+                    }
+                    None if self.textures.contains_key(name) => {
+                        return Err(format!(
+                            "pass {} reads texture {} which no pass writes",
+                            self.passes[index].name(),
+                            name
+                        ));
+                    }
+                    None => (),
+                }
+            }
+        }
+
+        // topological sort, preferring the order passes were added in
+        let mut order = Vec::with_capacity(enabled.len());
+        let mut ready: BTreeSet<usize> = enabled
+            .iter()
+            .copied()
+            .filter(|index| dependencies[index].is_empty())
+            .collect();
+
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+
+            for (&other, other_dependencies) in dependencies.iter_mut() {
+                if other_dependencies.remove(&index) && other_dependencies.is_empty() {
+                    ready.insert(other);
+                }
+            }
+        }
+
+        if order.len() != enabled.len() {
+            let cyclic: Vec<&str> = enabled
+                .iter()
+                .filter(|index| !order.contains(index))
+                .map(|&index| self.passes[index].name())
+                .collect();
+
+            return Err(format!(
+                "render graph has a dependency cycle between passes {:?}",
+                cyclic
+            ));
+        }
+
+        // first and last use of each transient texture
+        let mut lifetimes: HashMap<&'static str, (usize, usize)> = HashMap::new();
+
+        for (position, index) in order.iter().enumerate() {
+            for &name in inputs[index].iter().chain(outputs[index].iter()) {
+                if self.textures.contains_key(name) {
+                    let lifetime = lifetimes.entry(name).or_insert((position, position));
+                    lifetime.1 = position;
+                }
+            }
+        }
+
+        let mut by_first_use: Vec<(&'static str, (usize, usize))> = lifetimes.into_iter().collect();
+        by_first_use.sort_by_key(|&(name, (first, _))| (first, name));
+
+        // alias textures with matching descriptions whose lifetimes don't overlap
+        let mut slots: Vec<(TextureDesc, usize)> = Vec::new();
+        let mut assignments = HashMap::new();
+
+        for (name, (first, last)) in by_first_use {
+            let desc = self.textures[name];
+
+            let slot = match slots
+                .iter()
+                .position(|&(slot_desc, slot_last)| slot_desc == desc && slot_last < first)
+            {
+                Some(slot) => slot,
+                None => {
+                    slots.push((desc, 0));
+                    slots.len() - 1
+                }
+            };
+
+            slots[slot].1 = last;
+            assignments.insert(name, slot);
+        }
+
+        // keep existing textures where the slot's description hasn't changed
+        let mut physical: Vec<PhysicalTexture> = Vec::with_capacity(slots.len());
+
+        for (slot, &(desc, _)) in slots.iter().enumerate() {
+            match self.physical.get_mut(slot) {
+                Some(existing) if existing.desc == desc => physical.push(PhysicalTexture {
+                    desc,
+                    width: existing.width,
+                    height: existing.height,
+                    texture: existing.texture.take(),
+                }),
+                _ => physical.push(PhysicalTexture {
+                    desc,
+                    width: 0,
+                    height: 0,
+                    texture: None,
+                }),
+            }
+        }
+
+        self.order = order;
+        self.assignments = assignments;
+        self.physical = physical;
+        self.needs_compile = false;
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Create physical textures that are missing or the wrong size for the surface
+    fn allocate(&mut self, device: &wgpu::Device, surface_width: u32, surface_height: u32) {
+        for physical in self.physical.iter_mut() {
+            let (width, height) = physical.desc.size.resolve(surface_width, surface_height);
+
+            if physical.texture.is_some() && physical.width == width && physical.height == height {
+                continue;
+            }
+
+            // multisampled textures can't be copied from, so only single sampled ones can be read back
+            let mut usage =
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+
+            if physical.desc.sample_count == 1 {
+                usage |= wgpu::TextureUsages::COPY_SRC;
+            }
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Render Graph Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: physical.desc.sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: physical.desc.format,
+                usage,
+                view_formats: &[],
+            });
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+            physical.width = width;
+            physical.height = height;
+            physical.texture = Some((texture, view));
+            self.generation += 1;
+        }
+    }
+
+    /// Run every enabled pass's prepare step
+    pub fn prepare(&mut self, world: &game::World, renderer: &mut Renderer) -> Result<(), String> {
+        self.compile()?;
+
+        let context = PrepareContext {
+            textures: &self.textures,
+            surface_format: renderer.config().format,
+        };
+
+        for &index in self.order.iter() {
+            self.passes[index].prepare(&context, world, renderer);
+        }
+
+        Ok(())
+    }
+
+    /// Record every enabled pass into the encoder, in dependency order - returns the passes timed
+    /// on the gpu, in the order their timestamps were written
+    pub fn execute(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        world: &game::World,
+        renderer: &Renderer,
+        surface_view: &wgpu::TextureView,
+        draws: &DrawLists,
+    ) -> Result<Vec<&'static str>, String> {
+        self.compile()?;
+
+        let size = renderer.size();
+        self.allocate(&renderer.device().lock().unwrap(), size.width, size.height);
+
+        let mut views: HashMap<&'static str, &wgpu::TextureView> = HashMap::new();
+        let mut sizes: HashMap<&'static str, (u32, u32)> = HashMap::new();
+
+        views.insert(SURFACE, surface_view);
+        views.insert(DEPTH, renderer.depth_view());
+        sizes.insert(SURFACE, (size.width, size.height));
+        sizes.insert(DEPTH, (size.width, size.height));
+
+        if let Some(msaa_depth_view) = renderer.msaa_depth_view() {
+            views.insert(MSAA_DEPTH, msaa_depth_view);
+            sizes.insert(MSAA_DEPTH, (size.width, size.height));
+        }
+
+        for (&name, &slot) in self.assignments.iter() {
+            let physical = &self.physical[slot];
+
+            if let Some((_, view)) = &physical.texture {
+                views.insert(name, view);
+                sizes.insert(name, (physical.width, physical.height));
+            }
+        }
+
+        let mut written = HashSet::new();
+        let mut timed = Vec::new();
+
+        for &index in self.order.iter() {
+            let pass = &mut self.passes[index];
+
+            // each pass's start and end timestamps sit next to each other in the query set
+            let queries = renderer
+                .profiler()
+                .timestamp_queries()
+                .filter(|_| timed.len() < MAX_GPU_SCOPES as usize);
+
+            if let Some(query_set) = queries {
+                encoder.write_timestamp(query_set, timed.len() as u32 * 2);
+            }
+
+            let mut context = PassContext {
+                encoder: &mut *encoder,
+                draws,
+                views: &views,
+                sizes: &sizes,
+                written: &written,
+                generation: self.generation,
+            };
+
+            pass.execute(&mut context, world, renderer);
+
+            if let Some(query_set) = queries {
+                encoder.write_timestamp(query_set, timed.len() as u32 * 2 + 1);
+                timed.push(pass.name());
+            }
+
+            written.extend(pass.outputs());
+        }
+
+        Ok(timed)
+    }
+}
+
+/// Create a pipeline that draws a single fullscreen triangle, for passes that filter whole textures
+pub fn fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    fragment_entry_point: &str,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry_point),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Begin a render pass that draws into the hdr scene with depth testing - into the multisampled
+/// targets when msaa is enabled, resolving into HDR at the end of the pass. with entity_ids the
+/// entity id target is attached second, for pipelines that write ids as well
+pub fn begin_scene_pass<'a>(
+    context: &'a mut PassContext,
+    renderer: &Renderer,
+    label: &str,
+    clear_colour: wgpu::Color,
+    entity_ids: bool,
+) -> wgpu::RenderPass<'a> {
+    let (colour, resolve_target, depth, ids) = if renderer.msaa_samples() > 1 {
+        (
+            MSAA_HDR,
+            Some(context.view(HDR)),
+            MSAA_DEPTH,
+            MSAA_ENTITY_IDS,
+        )
+    } else {
+        (HDR, None, DEPTH, ENTITY_IDS)
+    };
+
+    let colour_load = context.colour_load(colour, clear_colour);
+    let depth_load = context.depth_load(depth);
+    let colour_view = context.view(colour);
+    let depth_view = context.view(depth);
+
+    let mut colour_attachments = vec![Some(wgpu::RenderPassColorAttachment {
+        view: colour_view,
+        resolve_target,
+        ops: wgpu::Operations {
+            load: colour_load,
+            store: wgpu::StoreOp::Store,
+        },
+    })];
+
+    // 0 is nothing drawn - attached only when asked for, as pipelines must match the attachments
+    if entity_ids {
+        colour_attachments.push(Some(wgpu::RenderPassColorAttachment {
+            view: context.view(ids),
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: context.colour_load(ids, wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        }));
+    }
+
+    context
+        .encoder
+        .begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &colour_attachments,
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: depth_load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+}
+
+/// Begin a render pass that draws into a single colour target with no depth
+pub fn begin_fullscreen_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    label: &str,
+    target: &wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    })
+}
@@ -1,10 +1,43 @@
+use crate::components::material_component::{
+    BlendMode, CullMode, MaterialComponent, MaterialPipelineKey, MaterialTextureSlot,
+    MaterialUniforms, ShadingModel,
+};
+use crate::core::camera::{Camera, OrthographicCamera};
+use crate::core::compute::Compute;
+use crate::core::debug_draw::DebugDraw;
 use crate::core::geometry;
+use crate::core::hdr::{self, HdrSettings};
+use crate::core::particles::ParticleGpu;
+use crate::core::picking::{self, PendingPick, Picking};
+use crate::core::post::PostStack;
+use crate::core::profiler::Profiler;
+use crate::core::render_graph::{self, RenderGraph, TextureDesc};
+use crate::core::shaders::{ProcessedShader, ShaderLibrary};
+use crate::core::shadows::{self, ShadowMaps, ShadowSettings, ShadowUniforms};
+use crate::core::sky::Sky;
+use crate::core::text::TextRenderer;
+use crate::core::texture::{self, Texture};
+use crate::core::view_mode::ViewMode;
+use crate::utils::log;
+use std::collections::HashMap;
 use std::mem;
 use std::num::NonZeroU64;
 use std::sync::{Arc, Mutex};
 use wgpu;
 use winit;
 
+/// counts from the last frame drawn, for profiling
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenderStats {
+    // meshes drawn in the main passes
+    pub drawn: u32,
+    // meshes skipped for being outside the camera frustum
+    pub culled: u32,
+}
+
+/// sample counts that can be asked for with Renderer::set_msaa_samples
+pub const MSAA_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
 /// singleton state object that holds the wgpu device, queue, and surface
 
 #[repr(C)]
@@ -13,12 +46,55 @@ pub struct GlobalUniforms {
     pub time: [f32; 4],
     pub projection: [f32; 16],
     pub view: [f32; 16],
+    pub camera_position: [f32; 4],
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TransformUniforms {
     pub model: [f32; 16],
+    // inverse transpose of the model matrix, for transforming normals
+    pub normal: [f32; 16],
+    // x: receives shadows, y: entity id, z: lod fade as f32 bits, w: lod fade mode
+    pub flags: [u32; 4],
+}
+
+/// transforms the transform buffer holds to begin with, @see Renderer::reserve_transform_slots
+const INITIAL_TRANSFORM_SLOTS: usize = 1024;
+
+/// maximum number of lights that can affect the scene at once
+pub const MAX_LIGHTS: usize = 64;
+
+pub const LIGHT_KIND_DIRECTIONAL: f32 = 0.0;
+pub const LIGHT_KIND_POINT: f32 = 1.0;
+pub const LIGHT_KIND_SPOT: f32 = 2.0;
+
+/// a single light as laid out in the light storage buffer - must match Light in fragment.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniforms {
+    // xyz position, w light kind
+    pub position: [f32; 4],
+    // xyz direction, w range
+    pub direction: [f32; 4],
+    // rgb colour, w intensity
+    pub colour: [f32; 4],
+    // cosines of the inner and outer spot angles
+    pub spot: [f32; 4],
+    // first shadow map layer (negative for no shadows), number of cascades
+    pub shadow: [f32; 4],
+}
+
+/// header at the start of the light storage buffer, followed by the lights themselves
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightsHeader {
+    // rgb colour, w intensity
+    pub ambient: [f32; 4],
+    // intensity, highest mip level, 1.0 if enabled
+    pub environment: [f32; 4],
+    pub count: u32,
+    pub _padding: [u32; 3],
 }
 
 pub struct Renderer<'a> {
@@ -28,7 +104,6 @@ pub struct Renderer<'a> {
     queue: Arc<Mutex<wgpu::Queue>>,
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
-    render_pipeline: wgpu::RenderPipeline,
 
     // from winit
     window: &'a winit::window::Window,
@@ -37,12 +112,77 @@ pub struct Renderer<'a> {
     global_bind_group: wgpu::BindGroup,
     global_bind_group_layout: wgpu::BindGroupLayout,
 
+    light_storage_buffer: wgpu::Buffer,
+    ambient: [f32; 4],
+
+    camera: Camera,
+    // looks at the sprites, which are drawn over the scene
+    camera_2d: OrthographicCamera,
+    // skip meshes whose bounds are outside the camera's view
+    frustum_culling: bool,
+    render_stats: RenderStats,
+    view_mode: ViewMode,
+
+    // exposure, tonemapping and bloom applied when resolving the hdr scene to the surface
+    hdr_settings: HdrSettings,
+    // effects applied to the tonemapped scene, in order
+    post_stack: PostStack,
+
+    // passes that make up a frame, run by MeshRenderer
+    render_graph: RenderGraph,
+
+    // lines and shapes drawn over the scene, from any system
+    debug_draw: DebugDraw,
+    // picks waiting on the entity ids the opaque pass writes
+    picking: Picking,
+    // fonts, and text drawn on the screen this frame
+    text: TextRenderer,
+    // pipeline and layouts shared by every particle emitter
+    particle_gpu: ParticleGpu,
+    // per system and per pass timings
+    profiler: Profiler,
+    // compute kernels and buffers for gameplay systems
+    compute: Compute,
+
+    shadow_settings: ShadowSettings,
+    shadow_maps: ShadowMaps,
+
+    // drawn behind the scene
+    sky: Sky,
+
+    // cubemap used for image based lighting
+    environment: Arc<Texture>,
+    environment_intensity: f32,
+    environment_enabled: bool,
+
     transform_uniform_buffer: wgpu::Buffer,
     transform_bind_group: wgpu::BindGroup,
     transform_bind_group_layout: wgpu::BindGroupLayout,
+    // transforms the buffer has room for, at aligned offsets
+    transform_slots: usize,
+
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    default_material_bind_group: wgpu::BindGroup,
+    // bound in place of textures that are missing, indexed by MaterialTextureSlot
+    default_material_textures: Vec<Arc<Texture>>,
+    // textures loaded from disk, keyed by file path and the options they were loaded with
+    loaded_textures: HashMap<(String, texture::TextureOptions), Arc<Texture>>,
+    // textures created elsewhere, keyed by the name they were inserted with
+    textures: HashMap<String, Arc<Texture>>,
+    // pipelines are created lazily, one per material variant
+    material_pipelines: HashMap<MaterialPipelineKey, wgpu::RenderPipeline>,
+    // source of the material shaders, watched for changes while hot reloading
+    shaders: ShaderLibrary,
 
     depth_texture: wgpu::Texture,
     depth_view: wgpu::TextureView,
+
+    // samples per pixel when drawing the scene, 1 for no multisampling
+    msaa_samples: u32,
+    // counts the adapter can multisample both the hdr and depth formats with
+    supported_msaa_samples: Vec<u32>,
+    // the scene's depth while multisampling, resolved into depth_view by the depth resolve pass
+    msaa_depth: Option<(wgpu::Texture, wgpu::TextureView)>,
 }
 
 impl<'window> Renderer<'window> {
@@ -77,11 +217,28 @@ impl<'window> Renderer<'window> {
             .unwrap();
 
         // get device and queue from adapter
+        // without adapter specific format features only 1x and 4x multisampling can be used, and
+        // without line polygons the wireframe view mode falls back to a shader, and without
+        // timestamp queries the profiler only times the cpu
+        let required_features = adapter.features()
+            & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                | wgpu::Features::POLYGON_MODE_LINE
+                | wgpu::Features::TIMESTAMP_QUERY
+                | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    ..Default::default()
+                },
+                None,
+            )
             .await
             .unwrap();
 
+        let supported_msaa_samples = Self::supported_msaa_samples_for(&adapter, device.features());
+
         // get surface capabilities from adapter
         let surface_capabilities = surface.get_capabilities(&adapter);
 
@@ -115,45 +272,93 @@ impl<'window> Renderer<'window> {
             mapped_at_creation: false,
         });
 
+        // lights are gathered into a storage buffer each frame
+        let light_storage_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Storage Buffer"),
+            size: (mem::size_of::<LightsHeader>() + mem::size_of::<LightUniforms>() * MAX_LIGHTS)
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let global_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Global Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // shadow light matrices and settings
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // shadow map array
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                    // environment cubemap for image based lighting
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
             });
 
-        let global_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Global Bind Group"),
-            layout: &global_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: global_uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        // Modify the transform buffer to be dynamic
-        let transform_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Transform Uniform Buffer"),
-            // Increase size to hold multiple transforms
-            size: (std::mem::size_of::<TransformUniforms>() * 1024) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         let transform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Transform Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: true, // Enable dynamic offsets
@@ -166,51 +371,119 @@ impl<'window> Renderer<'window> {
                 }],
             });
 
-        let transform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Transform Bind Group"),
-            layout: &transform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &transform_uniform_buffer,
-                    offset: 0,
-                    size: Some(
-                        NonZeroU64::new(std::mem::size_of::<TransformUniforms>() as u64).unwrap(),
-                    ),
-                }),
-            }],
-        });
+        let (transform_uniform_buffer, transform_bind_group) = Self::create_transform_buffer(
+            &device,
+            &transform_bind_group_layout,
+            INITIAL_TRANSFORM_SLOTS,
+        );
+
+        let shadow_settings = ShadowSettings::default();
 
         // Create depth texture
-        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Depth Texture"),
-            size: wgpu::Extent3d {
-                width: size.width,
-                height: size.height,
-                depth_or_array_layers: 1,
+        let (depth_texture, depth_view) =
+            Self::create_depth_texture(&device, size, 1, "Depth Texture");
+
+        // uniforms, then a texture and sampler for each material texture slot
+        let mut material_layout_entries = vec![wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
             },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
+            count: None,
+        }];
+
+        for slot in MaterialTextureSlot::ALL {
+            material_layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 1 + slot.index() as u32 * 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            material_layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2 + slot.index() as u32 * 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            });
+        }
+
+        let material_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Material Bind Group Layout"),
+                entries: &material_layout_entries,
+            });
+
+        let shaders = ShaderLibrary::default();
+        let shadow_maps = ShadowMaps::new(
+            &device,
+            &shadow_settings,
+            &transform_bind_group_layout,
+            &material_bind_group_layout,
+            &shaders,
+        );
+
+        // textures bound to empty material slots
+        let default_material_textures: Vec<Arc<Texture>> = MaterialTextureSlot::ALL
+            .iter()
+            .map(|slot| {
+                Arc::new(Texture::solid(
+                    &device,
+                    &queue,
+                    slot.default_texel(),
+                    slot.colour_space(),
+                ))
+            })
+            .collect();
+
+        // material used by meshes without a material component - plain vertex colours
+        let default_material_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Default Material Buffer"),
+            size: mem::size_of::<MaterialUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        queue.write_buffer(
+            &default_material_buffer,
+            0,
+            bytemuck::cast_slice(&[MaterialComponent::new([1.0, 1.0, 1.0, 1.0]).uniforms()]),
+        );
 
-        // create render pipeline
-        let render_pipeline = Self::init_render_pipeline(
+        let default_material_bind_group = Self::create_material_bind_group_with(
+            &device,
+            &material_bind_group_layout,
+            &default_material_buffer,
+            &default_material_textures,
+        );
+
+        // no environment until one is set - lit shaders fall back to the ambient term
+        let environment = Arc::new(Texture::solid_cubemap(&device, &queue, [0, 0, 0, 255]));
+
+        let global_bind_group = Self::create_global_bind_group(
             &device,
-            &config,
             &global_bind_group_layout,
-            &transform_bind_group_layout,
+            &global_uniform_buffer,
+            &light_storage_buffer,
+            &shadow_maps,
+            &environment,
         );
 
+        let particle_gpu = ParticleGpu::new(&device);
+        let profiler = Profiler::new(&device, &queue);
+
         // create shareable device and queue
         let device = Arc::new(Mutex::new(device));
         let queue = Arc::new(Mutex::new(queue));
 
+        let compute = Compute::new(device.clone(), queue.clone());
+
         // assign the configuration to the surface
         Self {
             surface,
@@ -219,37 +492,232 @@ impl<'window> Renderer<'window> {
             config,
             size,
             window,
-            render_pipeline,
             global_uniform_buffer,
             global_bind_group,
             global_bind_group_layout,
+            light_storage_buffer,
+            ambient: [1.0, 1.0, 1.0, 0.15],
+            camera: Camera::new(glam::Vec3::new(0.0, 0.0, 5.0), glam::Vec3::ZERO),
+            camera_2d: OrthographicCamera::default(),
+            frustum_culling: true,
+            render_stats: RenderStats::default(),
+            view_mode: ViewMode::Lit,
+            hdr_settings: HdrSettings::default(),
+            post_stack: PostStack::default(),
+            render_graph: RenderGraph::with_default_passes(),
+            debug_draw: DebugDraw::default(),
+            picking: Picking::default(),
+            text: TextRenderer::default(),
+            particle_gpu,
+            profiler,
+            compute,
+            shadow_settings,
+            shadow_maps,
+            sky: Sky::default(),
+            environment,
+            environment_intensity: 1.0,
+            environment_enabled: false,
             transform_uniform_buffer,
             transform_bind_group,
             transform_bind_group_layout,
+            transform_slots: INITIAL_TRANSFORM_SLOTS,
+            material_bind_group_layout,
+            default_material_bind_group,
+            default_material_textures,
+            loaded_textures: HashMap::new(),
+            textures: HashMap::new(),
+            material_pipelines: HashMap::new(),
+            shaders,
             depth_texture,
             depth_view,
+            msaa_samples: 1,
+            supported_msaa_samples,
+            msaa_depth: None,
         }
     }
 
+    /// Sample counts both the hdr colour format and the depth format can be multisampled with
+    fn supported_msaa_samples_for(adapter: &wgpu::Adapter, features: wgpu::Features) -> Vec<u32> {
+        let colour_flags = adapter.get_texture_format_features(hdr::HDR_FORMAT).flags;
+        let entity_id_flags = adapter
+            .get_texture_format_features(picking::ENTITY_ID_FORMAT)
+            .flags;
+        let depth_flags = adapter
+            .get_texture_format_features(wgpu::TextureFormat::Depth32Float)
+            .flags;
+
+        MSAA_SAMPLE_COUNTS
+            .into_iter()
+            .filter(|&count| {
+                // 4x is guaranteed, anything else needs the adapter's own format features
+                count == 1
+                    || count == 4
+                    || (features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+                        && colour_flags.sample_count_supported(count)
+                        && entity_id_flags.sample_count_supported(count)
+                        && depth_flags.sample_count_supported(count))
+            })
+            .collect()
+    }
+
+    fn create_depth_texture(
+        device: &wgpu::Device,
+        size: winit::dpi::PhysicalSize<u32>,
+        sample_count: u32,
+        label: &str,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_global_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        global_uniform_buffer: &wgpu::Buffer,
+        light_storage_buffer: &wgpu::Buffer,
+        shadow_maps: &ShadowMaps,
+        environment: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Global Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: global_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_storage_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: shadow_maps.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&shadow_maps.array_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&shadow_maps.comparison_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&environment.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(&environment.sampler),
+                },
+            ],
+        })
+    }
+
+    /// Pipeline for a material variant, drawing into the hdr scene - and the entity id target for
+    /// opaque materials while picking is enabled
+    #[allow(clippy::too_many_arguments)]
     fn init_render_pipeline(
         device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+        entity_ids: bool,
         global_bind_group_layout: &wgpu::BindGroupLayout,
         transform_bind_group_layout: &wgpu::BindGroupLayout,
-    ) -> wgpu::RenderPipeline {
-        // load shaders
-        let vertex_shader = Self::load_shader(&device, include_str!("../shaders/vertex.wgsl"));
-        let fragment_shader = Self::load_shader(&device, include_str!("../shaders/fragment.wgsl"));
+        material_bind_group_layout: &wgpu::BindGroupLayout,
+        key: &MaterialPipelineKey,
+        [vertex, fragment]: &[ProcessedShader; 2],
+    ) -> Result<wgpu::RenderPipeline, String> {
+        // load shaders - materials can swap out the fragment stage
+        let vertex_shader = Self::compile_shader(device, &vertex.name, &vertex.source)
+            .map_err(|e| vertex.map_error(&e))?;
+        let fragment_shader = Self::compile_shader(device, &fragment.name, &fragment.source)
+            .map_err(|e| fragment.map_error(&e))?;
 
         // create render pipeline layout
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[global_bind_group_layout, transform_bind_group_layout],
+                bind_group_layouts: &[
+                    global_bind_group_layout,
+                    transform_bind_group_layout,
+                    material_bind_group_layout,
+                ],
                 push_constant_ranges: &[],
             });
 
-        // create render pipeline
+        // blended materials are composited over the scene so they don't occlude with depth
+        let (blend, depth_write_enabled) = match key.blend_mode {
+            BlendMode::Opaque => (
+                wgpu::BlendState {
+                    color: wgpu::BlendComponent::REPLACE,
+                    alpha: wgpu::BlendComponent::REPLACE,
+                },
+                true,
+            ),
+            BlendMode::AlphaBlend => (wgpu::BlendState::ALPHA_BLENDING, false),
+            BlendMode::Additive => (
+                wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::OVER,
+                },
+                false,
+            ),
+        };
+
+        let cull_mode = match key.cull_mode {
+            CullMode::None => None,
+            CullMode::Back => Some(wgpu::Face::Back),
+            CullMode::Front => Some(wgpu::Face::Front),
+        };
+
+        let mut targets = vec![Some(wgpu::ColorTargetState {
+            format: hdr::HDR_FORMAT,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        // the opaque pass attaches the entity ids, custom fragment shaders that don't return
+        // FragmentOutput leave them as nothing drawn
+        if entity_ids && !key.blend_mode.is_transparent() {
+            let writes_ids = fragment
+                .files
+                .iter()
+                .any(|file| file == "common/fragment_output.wgsl");
+
+            targets.push(Some(wgpu::ColorTargetState {
+                format: picking::ENTITY_ID_FORMAT,
+                blend: None,
+                write_mask: if writes_ids {
+                    wgpu::ColorWrites::ALL
+                } else {
+                    wgpu::ColorWrites::empty()
+                },
+            }));
+        }
+
+        // create render pipeline - the stages can compile but still not fit together
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
@@ -262,34 +730,27 @@ impl<'window> Renderer<'window> {
             fragment: Some(wgpu::FragmentState {
                 module: &fragment_shader,
                 entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
+                targets: &targets,
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, // Change this to None to see both sides
+                cull_mode,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
+                depth_write_enabled,
                 depth_compare: wgpu::CompareFunction::Less,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -297,10 +758,78 @@ impl<'window> Renderer<'window> {
             cache: None,
         });
 
-        return render_pipeline;
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(e) => Err(e.to_string()),
+            None => Ok(render_pipeline),
+        }
+    }
+
+    /// Files the vertex and fragment stages of a material pipeline are loaded from - none for
+    /// custom fragment shaders, whose source is given by the material
+    fn material_shader_files(key: &MaterialPipelineKey) -> [Option<&'static str>; 2] {
+        let fragment = match (key.fragment_shader, key.shading) {
+            (Some(_), _) => None,
+            (None, ShadingModel::BlinnPhong) => Some("fragment.wgsl"),
+            (None, ShadingModel::Pbr) => Some("pbr.wgsl"),
+        };
+
+        [Some("vertex.wgsl"), fragment]
+    }
+
+    /// Preprocessed vertex and fragment stages for a material pipeline, from disk while hot
+    /// reloading unless embedded is set
+    fn material_shaders(
+        &self,
+        key: &MaterialPipelineKey,
+        embedded: bool,
+    ) -> Result<[ProcessedShader; 2], String> {
+        let [vertex, fragment] = Self::material_shader_files(key).map(|file| match file {
+            Some(file) if embedded => {
+                let source = ShaderLibrary::embedded(file).unwrap_or_default();
+                self.shaders
+                    .preprocess(file, source, &key.defines, embedded)
+            }
+            Some(file) => {
+                let source = self.shaders.source(file);
+                self.shaders
+                    .preprocess(file, &source, &key.defines, embedded)
+            }
+            None => self.shaders.preprocess(
+                "custom fragment shader",
+                key.fragment_shader.unwrap_or_default(),
+                &key.defines,
+                embedded,
+            ),
+        });
+
+        Ok([vertex?, fragment?])
+    }
+
+    /// Where the built in shaders come from, for preprocessing them outside the material pipelines
+    pub fn shaders(&self) -> &ShaderLibrary {
+        &self.shaders
+    }
+
+    /// Compile wgsl, returning naga's error rather than panicking if it's invalid
+    pub fn compile_shader(
+        device: &wgpu::Device,
+        label: &str,
+        source: &str,
+    ) -> Result<wgpu::ShaderModule, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(e) => Err(e.to_string()),
+            None => Ok(module),
+        }
     }
 
-    fn load_shader(device: &wgpu::Device, path: &str) -> wgpu::ShaderModule {
+    pub fn load_shader(device: &wgpu::Device, path: &str) -> wgpu::ShaderModule {
         return device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(path.into()),
@@ -326,29 +855,95 @@ impl<'window> Renderer<'window> {
         self.surface
             .configure(&self.device.lock().unwrap(), &self.config);
 
-        // Recreate depth texture with new size
-        self.depth_texture = self
-            .device
-            .lock()
-            .unwrap()
-            .create_texture(&wgpu::TextureDescriptor {
-                label: Some("Depth Texture"),
-                size: wgpu::Extent3d {
-                    width: new_size.width,
-                    height: new_size.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                view_formats: &[],
-            });
-        self.depth_view = self
-            .depth_texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        // Recreate depth textures with new size
+        let device = self.device.lock().unwrap();
+
+        (self.depth_texture, self.depth_view) =
+            Self::create_depth_texture(&device, new_size, 1, "Depth Texture");
+
+        self.msaa_depth = (self.msaa_samples > 1).then(|| {
+            Self::create_depth_texture(
+                &device,
+                new_size,
+                self.msaa_samples,
+                "Multisampled Depth Texture",
+            )
+        });
+    }
+
+    /// Draw the scene with a number of samples per pixel, resolving them before bloom and
+    /// tonemapping - counts the adapter can't use are rejected
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<(), String> {
+        if !self.supported_msaa_samples.contains(&samples) {
+            return Err(format!(
+                "{}x msaa is not supported by this adapter, supported counts are {:?}",
+                samples, self.supported_msaa_samples
+            ));
+        }
+
+        if samples == self.msaa_samples {
+            return Ok(());
+        }
+
+        self.msaa_samples = samples;
+
+        // material pipelines are created for one sample count
+        self.rebuild_material_pipelines();
+
+        self.msaa_depth = (samples > 1).then(|| {
+            Self::create_depth_texture(
+                &self.device.lock().unwrap(),
+                self.size,
+                samples,
+                "Multisampled Depth Texture",
+            )
+        });
+
+        if samples > 1 {
+            self.render_graph.declare_texture(
+                render_graph::MSAA_HDR,
+                TextureDesc::new(hdr::HDR_FORMAT).with_sample_count(samples),
+            );
+        } else {
+            self.render_graph.remove_texture(render_graph::MSAA_HDR);
+        }
+
+        self.declare_entity_id_textures();
+
+        Ok(())
+    }
+
+    /// Declare the entity id targets the opaque pass writes while picking is enabled, multisampled
+    /// to match the scene
+    fn declare_entity_id_textures(&mut self) {
+        let graph = &mut self.render_graph;
+
+        if self.picking.enabled {
+            graph.declare_texture(
+                render_graph::ENTITY_IDS,
+                TextureDesc::new(picking::ENTITY_ID_FORMAT),
+            );
+        } else {
+            graph.remove_texture(render_graph::ENTITY_IDS);
+        }
+
+        if self.picking.enabled && self.msaa_samples > 1 {
+            graph.declare_texture(
+                render_graph::MSAA_ENTITY_IDS,
+                TextureDesc::new(picking::ENTITY_ID_FORMAT).with_sample_count(self.msaa_samples),
+            );
+        } else {
+            graph.remove_texture(render_graph::MSAA_ENTITY_IDS);
+        }
+    }
+
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    #[allow(dead_code)]
+    pub fn supported_msaa_samples(&self) -> &[u32] {
+        &self.supported_msaa_samples
     }
 
     // accessors
@@ -365,18 +960,332 @@ impl<'window> Renderer<'window> {
         &self.surface
     }
 
-    pub fn render_pipeline(&self) -> &wgpu::RenderPipeline {
-        &self.render_pipeline
+    /// Create the pipeline for a material variant if it hasn't been created already
+    pub fn prepare_material_pipeline(&mut self, key: &MaterialPipelineKey) {
+        if self.material_pipelines.contains_key(key) {
+            return;
+        }
+
+        if let Some(pipeline) = self.create_material_pipeline(key) {
+            self.material_pipelines.insert(key.clone(), pipeline);
+        }
+    }
+
+    /// Recreate every material pipeline after a change to what they render into or where their
+    /// shaders come from - variants that can't be created at all are dropped, as the old pipeline
+    /// may no longer match the targets
+    fn rebuild_material_pipelines(&mut self) {
+        let keys: Vec<MaterialPipelineKey> = self.material_pipelines.keys().cloned().collect();
+
+        for key in keys {
+            match self.create_material_pipeline(&key) {
+                Some(pipeline) => self.material_pipelines.insert(key, pipeline),
+                None => self.material_pipelines.remove(&key),
+            };
+        }
+    }
+
+    fn create_material_pipeline(&self, key: &MaterialPipelineKey) -> Option<wgpu::RenderPipeline> {
+        let create = |embedded: bool| {
+            Self::init_render_pipeline(
+                &self.device.lock().unwrap(),
+                self.msaa_samples,
+                self.picking.enabled,
+                &self.global_bind_group_layout,
+                &self.transform_bind_group_layout,
+                &self.material_bind_group_layout,
+                key,
+                &self.material_shaders(key, embedded)?,
+            )
+        };
+
+        // shaders being edited on disk might not compile, the built in ones should
+        match create(false) {
+            Ok(pipeline) => Some(pipeline),
+            Err(e) => {
+                log::error(&e);
+
+                match create(true) {
+                    Ok(pipeline) => Some(pipeline),
+                    Err(e) => {
+                        log::error(&e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// While hot reloading, recreate the material pipelines using shader files that changed on
+    /// disk - pipelines that fail to compile keep the last version that did
+    pub fn reload_shaders(&mut self) {
+        let changed = self.shaders.poll();
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let keys: Vec<MaterialPipelineKey> = self.material_pipelines.keys().cloned().collect();
+
+        for key in keys {
+            let shaders = match self.material_shaders(&key, false) {
+                Ok(shaders) => shaders,
+                Err(e) => {
+                    log::error(&e);
+                    continue;
+                }
+            };
+
+            // a change to an include reloads every pipeline using it
+            let affected = shaders
+                .iter()
+                .flat_map(|shader| shader.files.iter())
+                .any(|file| changed.contains(file));
+
+            if !affected {
+                continue;
+            }
+
+            let pipeline = Self::init_render_pipeline(
+                &self.device.lock().unwrap(),
+                self.msaa_samples,
+                self.picking.enabled,
+                &self.global_bind_group_layout,
+                &self.transform_bind_group_layout,
+                &self.material_bind_group_layout,
+                &key,
+                &shaders,
+            );
+
+            match pipeline {
+                Ok(pipeline) => {
+                    self.material_pipelines.insert(key, pipeline);
+                }
+                Err(e) => log::error(&e),
+            }
+        }
+
+        log::info(&format!("reloaded shaders: {}", changed.join(", ")));
+    }
+
+    pub fn shader_hot_reload(&self) -> bool {
+        self.shaders.hot_reload()
+    }
+
+    /// Load the material shaders from the source tree and reload them when they change, or go
+    /// back to the copies built into the binary
+    pub fn set_shader_hot_reload(&mut self, enabled: bool) {
+        if enabled != self.shaders.hot_reload() {
+            self.shaders.set_hot_reload(enabled);
+            self.rebuild_material_pipelines();
+        }
+    }
+
+    /// Get the pipeline for a material variant - must be prepared first
+    pub fn material_pipeline(&self, key: &MaterialPipelineKey) -> Option<&wgpu::RenderPipeline> {
+        self.material_pipelines.get(key)
+    }
+
+    /// Create a bind group for a material's uniform buffer and a texture for each slot
+    pub fn create_material_bind_group(
+        &self,
+        uniform_buffer: &wgpu::Buffer,
+        textures: &[Arc<Texture>],
+    ) -> wgpu::BindGroup {
+        Self::create_material_bind_group_with(
+            &self.device.lock().unwrap(),
+            &self.material_bind_group_layout,
+            uniform_buffer,
+            textures,
+        )
+    }
+
+    fn create_material_bind_group_with(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        textures: &[Arc<Texture>],
+    ) -> wgpu::BindGroup {
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: uniform_buffer.as_entire_binding(),
+        }];
+
+        for (index, texture) in textures.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 1 + index as u32 * 2,
+                resource: wgpu::BindingResource::TextureView(&texture.view),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + index as u32 * 2,
+                resource: wgpu::BindingResource::Sampler(&texture.sampler),
+            });
+        }
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Material Bind Group"),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    /// Load a texture from disk, or return the texture already loaded from the path with the same
+    /// options - the same file loaded with other options is a separate texture
+    pub fn load_texture(
+        &mut self,
+        path: &str,
+        options: texture::TextureOptions,
+    ) -> Result<Arc<Texture>, String> {
+        let key = (path.to_string(), options);
+
+        if let Some(texture) = self.loaded_textures.get(&key) {
+            return Ok(texture.clone());
+        }
+
+        let texture = Arc::new(Texture::from_file(
+            &self.device.lock().unwrap(),
+            &self.queue.lock().unwrap(),
+            path,
+            options,
+        )?);
+
+        self.loaded_textures.insert(key, texture.clone());
+
+        Ok(texture)
+    }
+
+    /// Register a texture created elsewhere (e.g. generated at runtime) under a name
+    #[allow(dead_code)]
+    pub fn insert_texture(&mut self, name: &str, texture: Texture) -> Arc<Texture> {
+        let texture = Arc::new(texture);
+        self.textures.insert(name.to_string(), texture.clone());
+        texture
+    }
+
+    #[allow(dead_code)]
+    pub fn texture(&self, name: &str) -> Option<&Arc<Texture>> {
+        self.textures.get(name)
+    }
+
+    /// Texture bound to a material slot that has no texture of its own
+    pub fn default_material_texture(&self, slot: MaterialTextureSlot) -> &Arc<Texture> {
+        &self.default_material_textures[slot.index()]
+    }
+
+    /// Use a cubemap, given as six faces, for image based lighting in pbr materials
+    #[allow(dead_code)]
+    pub fn set_environment(&mut self, faces: &[&str; 6], intensity: f32) -> Result<(), String> {
+        let environment = Texture::cubemap_from_files(
+            &self.device.lock().unwrap(),
+            &self.queue.lock().unwrap(),
+            faces,
+            texture::TextureOptions::default(),
+        )?;
+
+        self.set_environment_texture(Arc::new(environment), intensity);
+
+        Ok(())
+    }
+
+    /// Use an already loaded cubemap for image based lighting - such as the sky's, so reflections
+    /// match the background
+    pub fn set_environment_texture(&mut self, environment: Arc<Texture>, intensity: f32) {
+        self.environment = environment;
+        self.environment_intensity = intensity;
+        self.environment_enabled = true;
+
+        self.global_bind_group = Self::create_global_bind_group(
+            &self.device.lock().unwrap(),
+            &self.global_bind_group_layout,
+            &self.global_uniform_buffer,
+            &self.light_storage_buffer,
+            &self.shadow_maps,
+            &self.environment,
+        );
+    }
+
+    pub fn sky(&self) -> &Sky {
+        &self.sky
+    }
+
+    /// Change what's drawn behind the scene - takes effect next frame
+    pub fn set_sky(&mut self, sky: Sky) {
+        self.sky = sky;
+    }
+
+    /// Draw a cubemap, given as six faces in +X, -X, +Y, -Y, +Z, -Z order, as the sky
+    pub fn set_sky_cubemap(&mut self, faces: &[&str; 6], intensity: f32) -> Result<(), String> {
+        let texture = Texture::cubemap_from_files(
+            &self.device.lock().unwrap(),
+            &self.queue.lock().unwrap(),
+            faces,
+            texture::TextureOptions::default(),
+        )?;
+
+        self.sky = Sky::Cubemap {
+            texture: Arc::new(texture),
+            intensity,
+        };
+
+        Ok(())
+    }
+
+    /// Draw an equirectangular panorama, such as a .hdr file, as the sky - converted to a cubemap
+    /// with faces of the given size
+    pub fn set_sky_equirect(
+        &mut self,
+        path: &str,
+        face_size: u32,
+        intensity: f32,
+    ) -> Result<(), String> {
+        let texture = Texture::cubemap_from_equirect_file(
+            &self.device.lock().unwrap(),
+            &self.queue.lock().unwrap(),
+            path,
+            face_size,
+            texture::TextureOptions::default(),
+        )?;
+
+        self.sky = Sky::Cubemap {
+            texture: Arc::new(texture),
+            intensity,
+        };
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn set_environment_intensity(&mut self, intensity: f32) {
+        self.environment_intensity = intensity;
+    }
+
+    /// Stop using the environment for lighting, falling back to the ambient term
+    pub fn disable_environment(&mut self) {
+        self.environment_enabled = false;
+    }
+
+    pub fn default_material_bind_group(&self) -> &wgpu::BindGroup {
+        &self.default_material_bind_group
     }
 
     pub fn depth_view(&self) -> &wgpu::TextureView {
         &self.depth_view
     }
 
+    /// The scene's multisampled depth, if msaa is enabled
+    pub fn msaa_depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.msaa_depth.as_ref().map(|(_, view)| view)
+    }
+
     pub fn global_bind_group(&self) -> &wgpu::BindGroup {
         &self.global_bind_group
     }
 
+    /// Layout of the global bind group, for pipelines outside the renderer that draw with it
+    pub fn global_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.global_bind_group_layout
+    }
+
     pub fn update_global_uniforms(&self, uniforms: GlobalUniforms) {
         self.queue.lock().unwrap().write_buffer(
             &self.global_uniform_buffer,
@@ -385,6 +1294,280 @@ impl<'window> Renderer<'window> {
         );
     }
 
+    /// Write the lights for this frame, dropping any beyond MAX_LIGHTS
+    pub fn update_lights(&self, lights: &[LightUniforms]) {
+        let count = lights.len().min(MAX_LIGHTS);
+
+        let header = LightsHeader {
+            ambient: self.ambient,
+            environment: [
+                self.environment_intensity,
+                (self.environment.mip_level_count - 1) as f32,
+                if self.environment_enabled { 1.0 } else { 0.0 },
+                0.0,
+            ],
+            count: count as u32,
+            _padding: [0; 3],
+        };
+
+        let queue = self.queue.lock().unwrap();
+
+        queue.write_buffer(
+            &self.light_storage_buffer,
+            0,
+            bytemuck::cast_slice(&[header]),
+        );
+
+        if count > 0 {
+            queue.write_buffer(
+                &self.light_storage_buffer,
+                mem::size_of::<LightsHeader>() as wgpu::BufferAddress,
+                bytemuck::cast_slice(&lights[..count]),
+            );
+        }
+    }
+
+    /// Set the ambient light applied to every lit surface
+    #[allow(dead_code)]
+    pub fn set_ambient(&mut self, colour: geometry::Colour, intensity: f32) {
+        self.ambient = [colour[0], colour[1], colour[2], intensity];
+    }
+
+    #[allow(dead_code)]
+    pub fn ambient(&self) -> [f32; 4] {
+        self.ambient
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    #[allow(dead_code)]
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    pub fn camera_2d(&self) -> &OrthographicCamera {
+        &self.camera_2d
+    }
+
+    #[allow(dead_code)]
+    pub fn camera_2d_mut(&mut self) -> &mut OrthographicCamera {
+        &mut self.camera_2d
+    }
+
+    pub fn frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
+    #[allow(dead_code)]
+    pub fn set_frustum_culling(&mut self, frustum_culling: bool) {
+        self.frustum_culling = frustum_culling;
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
+    pub fn set_render_stats(&mut self, render_stats: RenderStats) {
+        self.render_stats = render_stats;
+    }
+
+    pub fn hdr_settings(&self) -> &HdrSettings {
+        &self.hdr_settings
+    }
+
+    pub fn set_hdr_settings(&mut self, settings: HdrSettings) {
+        self.hdr_settings = settings;
+    }
+
+    pub fn post_stack(&self) -> &PostStack {
+        &self.post_stack
+    }
+
+    #[allow(dead_code)]
+    pub fn post_stack_mut(&mut self) -> &mut PostStack {
+        &mut self.post_stack
+    }
+
+    pub fn config(&self) -> &wgpu::SurfaceConfiguration {
+        &self.config
+    }
+
+    #[allow(dead_code)]
+    pub fn render_graph(&self) -> &RenderGraph {
+        &self.render_graph
+    }
+
+    pub fn render_graph_mut(&mut self) -> &mut RenderGraph {
+        &mut self.render_graph
+    }
+
+    pub fn view_mode(&self) -> ViewMode {
+        self.view_mode
+    }
+
+    /// Shade the scene normally, or with one of the debug views in place of the mesh passes
+    pub fn set_view_mode(&mut self, view_mode: ViewMode) {
+        self.view_mode = view_mode;
+
+        let debug = view_mode.is_debug();
+
+        self.render_graph.set_pass_enabled("opaque", !debug);
+        self.render_graph.set_pass_enabled("sky", !debug);
+        self.render_graph.set_pass_enabled("particles", !debug);
+        self.render_graph.set_pass_enabled("transparent", !debug);
+        // sprites and tilemaps have no normals or depth to show
+        self.render_graph.set_pass_enabled("sprites", !debug);
+        // nothing writes entity ids without the opaque pass
+        self.render_graph
+            .set_pass_enabled("entity_id_resolve", !debug);
+        self.render_graph.set_pass_enabled("debug_view", debug);
+    }
+
+    /// Write every opaque entity's id alongside the scene, into a target that pick reads back -
+    /// costs an extra colour target in the opaque pass
+    pub fn set_picking_enabled(&mut self, enabled: bool) {
+        if enabled == self.picking.enabled {
+            return;
+        }
+
+        self.picking.enabled = enabled;
+
+        // opaque material pipelines gain or lose their entity id target
+        self.rebuild_material_pipelines();
+        self.declare_entity_id_textures();
+
+        if !enabled {
+            self.picking.clear_requests();
+        }
+    }
+
+    pub fn picking(&self) -> &Picking {
+        &self.picking
+    }
+
+    pub fn picking_mut(&mut self) -> &mut Picking {
+        &mut self.picking
+    }
+
+    /// Start reading back the entity drawn at a pixel in the next frame, in physical pixels from
+    /// the top left of the window - poll the pending pick on later frames rather than waiting on
+    /// it. None if picking isn't enabled, the pixel is outside the window or a debug view is
+    /// replacing the opaque pass
+    pub fn pick(&mut self, x: u32, y: u32) -> Option<PendingPick> {
+        if !self.picking.enabled
+            || self.view_mode.is_debug()
+            || x >= self.size.width
+            || y >= self.size.height
+        {
+            return None;
+        }
+
+        let device = self.device.lock().unwrap();
+
+        Some(self.picking.request(&device, x, y))
+    }
+
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// Toggle the overlay and export traces, @see Profiler
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    #[allow(dead_code)]
+    pub fn compute(&self) -> &Compute {
+        &self.compute
+    }
+
+    /// Load kernels, write buffers and dispatch them, @see Compute
+    pub fn compute_mut(&mut self) -> &mut Compute {
+        &mut self.compute
+    }
+
+    pub fn text(&self) -> &TextRenderer {
+        &self.text
+    }
+
+    /// Load fonts and draw screen text, @see TextRenderer
+    pub fn text_mut(&mut self) -> &mut TextRenderer {
+        &mut self.text
+    }
+
+    pub fn particle_gpu(&self) -> &ParticleGpu {
+        &self.particle_gpu
+    }
+
+    pub fn debug_draw(&self) -> &DebugDraw {
+        &self.debug_draw
+    }
+
+    /// Draw debug lines and shapes, @see DebugDraw
+    pub fn debug_draw_mut(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.size.width as f32 / self.size.height as f32
+    }
+
+    pub fn shadow_settings(&self) -> &ShadowSettings {
+        &self.shadow_settings
+    }
+
+    /// Change shadow settings, recreating the shadow maps if the resolution changed
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        if settings.resolution != self.shadow_settings.resolution {
+            let device = self.device.lock().unwrap();
+
+            self.shadow_maps.resize(&device, settings.resolution);
+            self.global_bind_group = Self::create_global_bind_group(
+                &device,
+                &self.global_bind_group_layout,
+                &self.global_uniform_buffer,
+                &self.light_storage_buffer,
+                &self.shadow_maps,
+                &self.environment,
+            );
+        }
+
+        self.shadow_settings = settings;
+    }
+
+    pub fn shadow_maps(&self) -> &ShadowMaps {
+        &self.shadow_maps
+    }
+
+    /// Write the light matrices used to render and sample this frame's shadow maps
+    pub fn update_shadows(&mut self, view_projections: &[[f32; 16]], cascade_splits: &[f32]) {
+        let mut uniforms = ShadowUniforms {
+            view_projections: [[0.0; 16]; shadows::MAX_SHADOW_MAPS],
+            cascade_splits: [0.0; shadows::MAX_CASCADES],
+            settings: [
+                self.shadow_settings.depth_bias,
+                self.shadow_settings.normal_bias,
+                1.0 / self.shadow_settings.resolution as f32,
+                self.shadow_settings.pcf_radius as f32,
+            ],
+        };
+
+        let count = view_projections.len().min(shadows::MAX_SHADOW_MAPS);
+        uniforms.view_projections[..count].copy_from_slice(&view_projections[..count]);
+
+        let split_count = cascade_splits.len().min(shadows::MAX_CASCADES);
+        uniforms.cascade_splits[..split_count].copy_from_slice(&cascade_splits[..split_count]);
+
+        self.shadow_maps
+            .update(&self.queue.lock().unwrap(), &uniforms, count);
+    }
+
+    pub fn transform_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.transform_bind_group_layout
+    }
+
     pub fn transform_bind_group(&self) -> &wgpu::BindGroup {
         &self.transform_bind_group
     }
@@ -397,11 +1580,70 @@ impl<'window> Renderer<'window> {
         );
     }
 
+    /// A transform buffer with room for a number of transforms at aligned offsets, and a bind
+    /// group for drawing with each of them through a dynamic offset
+    fn create_transform_buffer(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        slots: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Transform Uniform Buffer"),
+            size: Self::get_transform_aligned_size() * slots as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Transform Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: Some(
+                        NonZeroU64::new(std::mem::size_of::<TransformUniforms>() as u64).unwrap(),
+                    ),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Grow the transform buffer to hold at least this many transforms - call before writing the
+    /// frame's transforms, as growing loses the ones already written
+    pub fn reserve_transform_slots(&mut self, slots: usize) {
+        if slots <= self.transform_slots {
+            return;
+        }
+
+        let slots = slots.next_power_of_two();
+        let (buffer, bind_group) = Self::create_transform_buffer(
+            &self.device.lock().unwrap(),
+            &self.transform_bind_group_layout,
+            slots,
+        );
+
+        self.transform_uniform_buffer = buffer;
+        self.transform_bind_group = bind_group;
+        self.transform_slots = slots;
+    }
+
     pub fn update_transform_uniforms_at_offset(
         &self,
         uniforms: TransformUniforms,
         offset: wgpu::BufferAddress,
     ) {
+        if offset + Self::get_transform_aligned_size() > self.transform_uniform_buffer.size() {
+            log::warn(&format!(
+                "transform offset {} is past the end of the transform buffer, skipping it",
+                offset
+            ));
+            return;
+        }
+
         self.queue.lock().unwrap().write_buffer(
             &self.transform_uniform_buffer,
             offset,
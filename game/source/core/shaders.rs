@@ -0,0 +1,543 @@
+use crate::utils::log;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// shaders built into the binary by their path in the shader directory - while hot reloading, other
+/// files in the directory can be included too
+const EMBEDDED: &[(&str, &str)] = &[
+    ("vertex.wgsl", include_str!("../shaders/vertex.wgsl")),
+    ("fragment.wgsl", include_str!("../shaders/fragment.wgsl")),
+    ("pbr.wgsl", include_str!("../shaders/pbr.wgsl")),
+    (
+        "common/vertex_output.wgsl",
+        include_str!("../shaders/common/vertex_output.wgsl"),
+    ),
+    (
+        "common/fragment_output.wgsl",
+        include_str!("../shaders/common/fragment_output.wgsl"),
+    ),
+    (
+        "common/uniforms.wgsl",
+        include_str!("../shaders/common/uniforms.wgsl"),
+    ),
+    (
+        "common/lights.wgsl",
+        include_str!("../shaders/common/lights.wgsl"),
+    ),
+    (
+        "common/material.wgsl",
+        include_str!("../shaders/common/material.wgsl"),
+    ),
+    (
+        "common/lod.wgsl",
+        include_str!("../shaders/common/lod.wgsl"),
+    ),
+];
+
+/// how often the shader files are checked for changes while hot reloading
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A shader with its includes and conditionals expanded, remembering where each line came from
+pub struct ProcessedShader {
+    pub name: String,
+    pub source: String,
+    // every file that went into it, the shader itself first
+    pub files: Vec<String>,
+    // file and line number of each line of the source
+    lines: Vec<(String, usize)>,
+}
+
+impl ProcessedShader {
+    /// Point the locations in a compile error at the files and lines they came from, rather than
+    /// at lines of the expanded source
+    pub fn map_error(&self, error: &str) -> String {
+        error
+            .lines()
+            .map(|line| self.map_location(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // naga gives locations as `┌─ label:line:column`
+    fn map_location(&self, line: &str) -> String {
+        let (prefix, location) = match line.split_once("┌─ ") {
+            Some(split) => split,
+            None => return line.to_string(),
+        };
+
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next();
+        let number = parts.next().and_then(|number| number.parse::<usize>().ok());
+
+        let original = match (column, number, parts.next()) {
+            (Some(column), Some(number), Some(_)) => number
+                .checked_sub(1)
+                .and_then(|index| self.lines.get(index))
+                .map(|(file, number)| (file, number, column)),
+            _ => None,
+        };
+
+        match original {
+            Some((file, number, column)) => format!("{}┌─ {}:{}:{}", prefix, file, number, column),
+            None => line.to_string(),
+        }
+    }
+}
+
+// an #ifdef or #ifndef block being expanded
+struct Conditional {
+    // whether its lines are kept
+    active: bool,
+    // whether the block it's in is kept
+    enclosing_active: bool,
+    seen_else: bool,
+    line: usize,
+}
+
+/// Where shader source comes from - the copies built into the binary, or while hot reloading the
+/// files on disk, which are watched for changes
+pub struct ShaderLibrary {
+    hot_reload: bool,
+    // the source tree's shader directory
+    directory: PathBuf,
+    // when each file was last seen modified
+    modified: HashMap<String, Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+impl Default for ShaderLibrary {
+    fn default() -> Self {
+        let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("source")
+            .join("shaders");
+
+        let mut library = Self {
+            hot_reload: false,
+            directory,
+            modified: HashMap::new(),
+            last_poll: Instant::now(),
+        };
+
+        // on by default in debug builds run from the source tree
+        library.set_hot_reload(cfg!(debug_assertions) && library.directory.is_dir());
+        library
+    }
+}
+
+impl ShaderLibrary {
+    pub fn hot_reload(&self) -> bool {
+        self.hot_reload
+    }
+
+    /// Load shaders from disk and watch them for changes, or go back to the built in ones
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+        self.modified.clear();
+
+        if enabled {
+            for name in self.files_on_disk() {
+                let modified = self.modified_time(&name);
+                self.modified.insert(name, modified);
+            }
+        }
+    }
+
+    /// The copy of a shader built into the binary
+    pub fn embedded(name: &str) -> Option<&'static str> {
+        EMBEDDED
+            .iter()
+            .find(|(embedded, _)| *embedded == name)
+            .map(|(_, source)| *source)
+    }
+
+    /// A shader's source - from disk while hot reloading, unless it can't be read
+    pub fn source(&self, name: &str) -> String {
+        let embedded = Self::embedded(name).unwrap_or_default();
+
+        if !self.hot_reload {
+            return embedded.to_string();
+        }
+
+        match fs::read_to_string(self.directory.join(name)) {
+            Ok(source) => source,
+            Err(e) => {
+                log::warn(&format!(
+                    "couldn't read shader {}, using the built in copy: {}",
+                    name, e
+                ));
+                embedded.to_string()
+            }
+        }
+    }
+
+    /// Expand a shader's `#include "file"`, `#define`, `#ifdef`, `#ifndef`, `#else` and `#endif`
+    /// directives, starting with the given defines. includes are paths in the shader directory,
+    /// expanded once per shader, and come from disk while hot reloading unless embedded is set
+    pub fn preprocess(
+        &self,
+        name: &str,
+        source: &str,
+        defines: &[&str],
+        embedded: bool,
+    ) -> Result<ProcessedShader, String> {
+        let mut shader = ProcessedShader {
+            name: name.to_string(),
+            source: String::new(),
+            files: vec![name.to_string()],
+            lines: Vec::new(),
+        };
+
+        let mut defines = defines.iter().map(|define| define.to_string()).collect();
+
+        self.expand(name, source, &mut defines, embedded, &mut shader)?;
+
+        Ok(shader)
+    }
+
+    fn expand(
+        &self,
+        name: &str,
+        source: &str,
+        defines: &mut HashSet<String>,
+        embedded: bool,
+        shader: &mut ProcessedShader,
+    ) -> Result<(), String> {
+        let mut conditionals: Vec<Conditional> = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let error = |message: &str| format!("{}:{}: {}", name, number, message);
+
+            let active = conditionals.last().is_none_or(|block| block.active);
+            let trimmed = line.trim();
+
+            if !trimmed.starts_with('#') {
+                if active {
+                    shader.source.push_str(line);
+                    shader.source.push('\n');
+                    shader.lines.push((name.to_string(), number));
+                }
+
+                continue;
+            }
+
+            let (directive, argument) = match trimmed.split_once(char::is_whitespace) {
+                Some((directive, argument)) => (directive, argument.trim()),
+                None => (trimmed, ""),
+            };
+
+            match directive {
+                "#ifdef" | "#ifndef" => {
+                    if argument.is_empty() {
+                        return Err(error(&format!("{} needs a name", directive)));
+                    }
+
+                    let defined = defines.contains(argument);
+
+                    conditionals.push(Conditional {
+                        active: active && defined == (directive == "#ifdef"),
+                        enclosing_active: active,
+                        seen_else: false,
+                        line: number,
+                    });
+                }
+                "#else" => match conditionals.last_mut() {
+                    Some(block) if !block.seen_else => {
+                        block.active = block.enclosing_active && !block.active;
+                        block.seen_else = true;
+                    }
+                    Some(_) => return Err(error("#else after #else")),
+                    None => return Err(error("#else without #ifdef or #ifndef")),
+                },
+                "#endif" => {
+                    if conditionals.pop().is_none() {
+                        return Err(error("#endif without #ifdef or #ifndef"));
+                    }
+                }
+                // includes and defines in skipped blocks don't happen
+                "#define" | "#include" if !active => {}
+                "#define" => {
+                    // defines are feature toggles, without values
+                    if argument.is_empty() || argument.contains(char::is_whitespace) {
+                        return Err(error("#define needs a single name"));
+                    }
+
+                    defines.insert(argument.to_string());
+                }
+                "#include" => {
+                    let path = match argument
+                        .strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                    {
+                        Some(path) => path,
+                        None => return Err(error("#include needs a quoted path")),
+                    };
+
+                    // shared declarations can be included from several files
+                    if shader.files.iter().any(|file| file == path) {
+                        continue;
+                    }
+
+                    let included = match self.load(path, embedded) {
+                        Some(included) => included,
+                        None => return Err(error(&format!("couldn't find {} to include", path))),
+                    };
+
+                    shader.files.push(path.to_string());
+                    self.expand(path, &included, defines, embedded, shader)?;
+                }
+                _ => return Err(error(&format!("unknown directive {}", directive))),
+            }
+        }
+
+        // blocks can't span files
+        match conditionals.last() {
+            Some(block) => Err(format!(
+                "{}:{}: #ifdef or #ifndef without #endif",
+                name, block.line
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // a shader to include - built in ones can always be, while hot reloading so can any other file
+    // in the shader directory
+    fn load(&self, name: &str, embedded: bool) -> Option<String> {
+        if embedded || !self.hot_reload {
+            return Self::embedded(name).map(|source| source.to_string());
+        }
+
+        if Self::embedded(name).is_some() {
+            return Some(self.source(name));
+        }
+
+        fs::read_to_string(self.directory.join(name)).ok()
+    }
+
+    /// Names of the shaders whose files have changed, been added or been removed since they were
+    /// last polled - none if not hot reloading, or if they were polled too recently
+    pub fn poll(&mut self) -> Vec<String> {
+        if !self.hot_reload || self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+
+        self.last_poll = Instant::now();
+
+        let mut names = self.files_on_disk();
+
+        // removed files change too
+        for name in self.modified.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        let mut changed = Vec::new();
+
+        for name in names {
+            let modified = self.modified_time(&name);
+
+            if self.modified.get(&name) != Some(&modified) {
+                self.modified.insert(name.clone(), modified);
+                changed.push(name);
+            }
+        }
+
+        changed
+    }
+
+    // paths of the .wgsl files in the shader directory, relative to it with / separators
+    fn files_on_disk(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut directories = vec![self.directory.clone()];
+
+        while let Some(directory) = directories.pop() {
+            let entries = match fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    directories.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|extension| extension == "wgsl")
+                {
+                    if let Ok(relative) = path.strip_prefix(&self.directory) {
+                        let parts: Vec<_> = relative
+                            .components()
+                            .map(|part| part.as_os_str().to_string_lossy())
+                            .collect();
+
+                        files.push(parts.join("/"));
+                    }
+                }
+            }
+        }
+
+        files
+    }
+
+    fn modified_time(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.directory.join(name))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A library hot reloading from a directory of its own holding the given files
+    fn library(test: &str, files: &[(&str, &str)]) -> ShaderLibrary {
+        let directory = std::env::temp_dir().join(format!("bideobame_shaders_{}", test));
+        let _ = fs::remove_dir_all(&directory);
+
+        for (name, source) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+
+        ShaderLibrary {
+            hot_reload: true,
+            directory,
+            modified: HashMap::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    fn expand(library: &ShaderLibrary, source: &str, defines: &[&str]) -> Result<String, String> {
+        library
+            .preprocess("main.wgsl", source, defines, false)
+            .map(|shader| shader.source)
+    }
+
+    #[test]
+    fn nested_conditionals_follow_their_enclosing_block() {
+        let library = library("nested", &[]);
+        let source = "\
+#ifdef A
+#ifdef B
+both
+#else
+a
+#define C
+#endif
+#else
+#ifdef B
+b
+#else
+neither
+#endif
+#endif
+#ifdef C
+c
+#endif
+#ifndef A
+not a
+#endif
+";
+
+        assert_eq!(expand(&library, source, &["A", "B"]).unwrap(), "both\n");
+        assert_eq!(expand(&library, source, &["A"]).unwrap(), "a\nc\n");
+        assert_eq!(expand(&library, source, &["B"]).unwrap(), "b\nnot a\n");
+        assert_eq!(expand(&library, source, &[]).unwrap(), "neither\nnot a\n");
+    }
+
+    #[test]
+    fn unbalanced_conditionals_are_errors() {
+        let library = library("unbalanced", &[]);
+
+        let errors = [
+            (
+                "#ifdef A\n",
+                "main.wgsl:1: #ifdef or #ifndef without #endif",
+            ),
+            ("#endif\n", "main.wgsl:1: #endif without #ifdef or #ifndef"),
+            (
+                "#ifdef A\n#else\n#else\n#endif\n",
+                "main.wgsl:3: #else after #else",
+            ),
+            ("#ifdef\n#endif\n", "main.wgsl:1: #ifdef needs a name"),
+            ("#define A 1\n", "main.wgsl:1: #define needs a single name"),
+        ];
+
+        for (source, error) in errors {
+            assert_eq!(expand(&library, source, &[]), Err(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn missing_includes_are_errors() {
+        let library = library("missing", &[]);
+
+        assert_eq!(
+            expand(&library, "one\n#include \"missing.wgsl\"\n", &[]),
+            Err("main.wgsl:2: couldn't find missing.wgsl to include".to_string())
+        );
+    }
+
+    #[test]
+    fn include_cycles_include_each_file_once() {
+        let library = library(
+            "cycle",
+            &[
+                ("a.wgsl", "#include \"b.wgsl\"\na\n"),
+                ("b.wgsl", "#include \"a.wgsl\"\nb\n"),
+            ],
+        );
+
+        let shader = library
+            .preprocess("main.wgsl", "#include \"a.wgsl\"\nmain\n", &[], false)
+            .unwrap();
+
+        assert_eq!(shader.source, "b\na\nmain\n");
+        assert_eq!(shader.files, ["main.wgsl", "a.wgsl", "b.wgsl"]);
+    }
+
+    #[test]
+    fn files_on_disk_are_only_included_while_hot_reloading() {
+        let mut library = library("disk", &[("common/shared.wgsl", "shared\n")]);
+        let source = "#include \"common/shared.wgsl\"\n";
+
+        assert_eq!(expand(&library, source, &[]).unwrap(), "shared\n");
+        assert!(library.preprocess("main.wgsl", source, &[], true).is_err());
+
+        library.hot_reload = false;
+
+        assert!(expand(&library, source, &[]).is_err());
+    }
+
+    #[test]
+    fn errors_are_mapped_to_the_file_and_line_they_came_from() {
+        let library = library(
+            "lines",
+            &[("shared.wgsl", "#ifdef A\nskipped\n#endif\nshared\n")],
+        );
+
+        let shader = library
+            .preprocess(
+                "main.wgsl",
+                "one\n#include \"shared.wgsl\"\nthree\n",
+                &[],
+                false,
+            )
+            .unwrap();
+
+        let error = "error: unknown identifier\n  ┌─ wgsl:2:5\n  │\n2 │ shared\n";
+
+        assert_eq!(
+            shader.map_error(error),
+            "error: unknown identifier\n  ┌─ shared.wgsl:4:5\n  │\n2 │ shared"
+        );
+        assert_eq!(shader.map_error("  ┌─ wgsl:3:1"), "  ┌─ main.wgsl:3:1");
+
+        // lines past the end are left alone
+        assert_eq!(shader.map_error("  ┌─ wgsl:9:1"), "  ┌─ wgsl:9:1");
+    }
+}
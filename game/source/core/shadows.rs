@@ -0,0 +1,419 @@
+use crate::core::camera::Camera;
+use crate::core::geometry;
+use crate::core::shaders::ShaderLibrary;
+use glam::{Mat4, Vec3, Vec4};
+
+/// number of layers in the shadow map array - each cascade or spot light uses one
+pub const MAX_SHADOW_MAPS: usize = 8;
+
+/// maximum number of cascades a directional light can be split into
+pub const MAX_CASCADES: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowSettings {
+    // width and height of each shadow map layer
+    pub resolution: u32,
+    // subtracted from the receiver's depth before comparing, to avoid acne
+    pub depth_bias: f32,
+    // receivers are pushed along their normal by this many world units before lookup
+    pub normal_bias: f32,
+    // radius in texels of the pcf kernel - 0 gives hard shadows
+    pub pcf_radius: u32,
+    pub cascade_count: u32,
+    // directional light shadows stop at this distance from the camera
+    pub cascade_distance: f32,
+    // blend between uniform (0.0) and logarithmic (1.0) cascade splits
+    pub cascade_split_lambda: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            depth_bias: 0.002,
+            normal_bias: 0.02,
+            pcf_radius: 1,
+            cascade_count: 3,
+            cascade_distance: 30.0,
+            cascade_split_lambda: 0.75,
+        }
+    }
+}
+
+/// shadow data for the lit shader - must match Shadows in fragment.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowUniforms {
+    pub view_projections: [[f32; 16]; MAX_SHADOW_MAPS],
+    // view distance at which each cascade ends
+    pub cascade_splits: [f32; MAX_CASCADES],
+    // depth bias, normal bias, texel size, pcf radius
+    pub settings: [f32; 4],
+}
+
+/// Distances along the view direction at which each cascade ends
+pub fn cascade_splits(near: f32, far: f32, count: u32, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let p = i as f32 / count as f32;
+            let logarithmic = near * (far / near).powf(p);
+            let uniform = near + (far - near) * p;
+
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// Orthographic view projections covering each cascade of the camera frustum
+pub fn directional_cascades(
+    camera: &Camera,
+    aspect_ratio: f32,
+    direction: Vec3,
+    settings: &ShadowSettings,
+) -> (Vec<Mat4>, Vec<f32>) {
+    let cascade_count = settings.cascade_count.clamp(1, MAX_CASCADES as u32);
+    let far = settings.cascade_distance.min(camera.far);
+    let splits = cascade_splits(
+        camera.near,
+        far,
+        cascade_count,
+        settings.cascade_split_lambda,
+    );
+
+    let view = camera.view_matrix();
+    let up = up_vector(direction);
+
+    let mut matrices = Vec::with_capacity(splits.len());
+    let mut near = camera.near;
+
+    for &split in splits.iter() {
+        let inverse =
+            (camera.projection_matrix_between(aspect_ratio, near, split) * view).inverse();
+
+        // corners of this slice of the frustum in world space
+        let mut corners = Vec::with_capacity(8);
+
+        for x in [-1.0, 1.0] {
+            for y in [-1.0, 1.0] {
+                for z in [0.0, 1.0] {
+                    let corner = inverse * Vec4::new(x, y, z, 1.0);
+                    corners.push(corner.truncate() / corner.w);
+                }
+            }
+        }
+
+        let centre = corners.iter().fold(Vec3::ZERO, |sum, c| sum + *c) / corners.len() as f32;
+
+        // a bounding sphere keeps the projection size stable as the camera rotates
+        let radius = corners
+            .iter()
+            .map(|c| c.distance(centre))
+            .fold(0.0_f32, f32::max)
+            .ceil();
+
+        // snap the centre to whole texels so shadows don't shimmer as the camera moves
+        let texel_size = (radius * 2.0) / settings.resolution as f32;
+        let light_rotation = Mat4::look_to_rh(Vec3::ZERO, direction, up);
+        let mut light_space_centre = light_rotation.transform_point3(centre);
+        light_space_centre.x = (light_space_centre.x / texel_size).floor() * texel_size;
+        light_space_centre.y = (light_space_centre.y / texel_size).floor() * texel_size;
+        let centre = light_rotation
+            .inverse()
+            .transform_point3(light_space_centre);
+
+        // pull the eye well back so casters outside the slice still land in the map
+        let depth_range = radius * 6.0;
+        let eye = centre - direction * (depth_range - radius);
+
+        let light_view = Mat4::look_to_rh(eye, direction, up);
+        let projection = Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.0, depth_range);
+
+        matrices.push(projection * light_view);
+        near = split;
+    }
+
+    (matrices, splits)
+}
+
+/// Perspective view projection covering a spot light's cone
+pub fn spot_view_projection(position: Vec3, direction: Vec3, outer_angle: f32, range: f32) -> Mat4 {
+    let fov = (outer_angle * 2.0).clamp(0.01, 179.0_f32.to_radians());
+    let projection = Mat4::perspective_rh(fov, 1.0, 0.05, range.max(0.1));
+    let view = Mat4::look_to_rh(position, direction, up_vector(direction));
+
+    projection * view
+}
+
+/// an up vector that isn't parallel to the given direction
+fn up_vector(direction: Vec3) -> Vec3 {
+    if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    }
+}
+
+/// gpu resources for rendering and sampling shadow maps
+pub struct ShadowMaps {
+    pub texture: wgpu::Texture,
+    // whole array, for sampling in the lit shader
+    pub array_view: wgpu::TextureView,
+    // one view per layer, for rendering into
+    pub layer_views: Vec<wgpu::TextureView>,
+    pub comparison_sampler: wgpu::Sampler,
+    pub uniform_buffer: wgpu::Buffer,
+    // light view projections for the depth pass, one per layer at aligned offsets
+    pub pass_uniform_buffer: wgpu::Buffer,
+    pub pass_bind_group: wgpu::BindGroup,
+    pub pipeline: wgpu::RenderPipeline,
+    // alpha tested, for materials with an alpha cutoff - binds the material as group 2
+    pub cutout_pipeline: wgpu::RenderPipeline,
+    // number of layers in use this frame
+    pub active_count: usize,
+}
+
+impl ShadowMaps {
+    pub fn new(
+        device: &wgpu::Device,
+        settings: &ShadowSettings,
+        transform_bind_group_layout: &wgpu::BindGroupLayout,
+        material_bind_group_layout: &wgpu::BindGroupLayout,
+        shaders: &ShaderLibrary,
+    ) -> Self {
+        let (texture, array_view, layer_views) = Self::create_texture(device, settings.resolution);
+
+        let comparison_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Comparison Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            size: std::mem::size_of::<ShadowUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let pass_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Pass Uniform Buffer"),
+            size: Self::pass_aligned_size() * MAX_SHADOW_MAPS as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let pass_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Shadow Pass Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<[f32; 16]>() as u64
+                        ),
+                    },
+                    count: None,
+                }],
+            });
+
+        let pass_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Pass Bind Group"),
+            layout: &pass_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &pass_uniform_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<[f32; 16]>() as u64),
+                }),
+            }],
+        });
+
+        let source = match shaders.preprocess(
+            "shadow.wgsl",
+            include_str!("../shaders/shadow.wgsl"),
+            &[],
+            true,
+        ) {
+            Ok(shader) => shader.source,
+            Err(e) => panic!("built in shadow shader failed to preprocess: {}", e),
+        };
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&pass_bind_group_layout, transform_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // depth only - no fragment stage
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[geometry::Vertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let cutout_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Shadow Cutout Pipeline Layout"),
+                bind_group_layouts: &[
+                    &pass_bind_group_layout,
+                    transform_bind_group_layout,
+                    material_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        // discards where the material is cut out, writing no colour
+        let cutout_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Cutout Pipeline"),
+            layout: Some(&cutout_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_cutout"),
+                buffers: &[geometry::Vertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_cutout"),
+                targets: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            texture,
+            array_view,
+            layer_views,
+            comparison_sampler,
+            uniform_buffer,
+            pass_uniform_buffer,
+            pass_bind_group,
+            pipeline,
+            cutout_pipeline,
+            active_count: 0,
+        }
+    }
+
+    /// Recreate the shadow map texture at a new resolution
+    pub fn resize(&mut self, device: &wgpu::Device, resolution: u32) {
+        let (texture, array_view, layer_views) = Self::create_texture(device, resolution);
+
+        // free the old maps now rather than whenever the last view of them is dropped
+        self.texture.destroy();
+
+        self.texture = texture;
+        self.array_view = array_view;
+        self.layer_views = layer_views;
+    }
+
+    /// Upload the light matrices for this frame
+    pub fn update(&mut self, queue: &wgpu::Queue, uniforms: &ShadowUniforms, active_count: usize) {
+        self.active_count = active_count.min(MAX_SHADOW_MAPS);
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[*uniforms]));
+
+        for layer in 0..self.active_count {
+            queue.write_buffer(
+                &self.pass_uniform_buffer,
+                Self::pass_aligned_size() * layer as wgpu::BufferAddress,
+                bytemuck::cast_slice(&uniforms.view_projections[layer]),
+            );
+        }
+    }
+
+    pub fn pass_aligned_size() -> wgpu::BufferAddress {
+        let align = 256; // minimum uniform buffer offset alignment
+        let unaligned = std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress;
+        unaligned.div_ceil(align) * align
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        resolution: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView, Vec<wgpu::TextureView>) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map Texture"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: MAX_SHADOW_MAPS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Shadow Map Array View"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let layer_views = (0..MAX_SHADOW_MAPS as u32)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Map Layer View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        (texture, array_view, layer_views)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::core::texture::Texture;
+use std::sync::Arc;
+
+/// a procedural sky, blending from the horizon up to the zenith and down to the ground
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientSky {
+    // linear rgb
+    pub zenith: [f32; 3],
+    pub horizon: [f32; 3],
+    pub ground: [f32; 3],
+    // higher keeps the horizon colour closer to the horizon
+    pub exponent: f32,
+    // direction towards the sun, for a disc drawn over the gradient
+    pub sun_direction: [f32; 3],
+    pub sun_colour: [f32; 3],
+    // angular radius of the disc in radians, 0.0 for none
+    pub sun_size: f32,
+}
+
+impl Default for GradientSky {
+    fn default() -> Self {
+        Self {
+            zenith: [0.15, 0.35, 0.75],
+            horizon: [0.7, 0.8, 0.9],
+            ground: [0.25, 0.22, 0.2],
+            exponent: 0.6,
+            sun_direction: [0.4, 0.6, -0.7],
+            sun_colour: [20.0, 18.0, 15.0],
+            sun_size: 0.02,
+        }
+    }
+}
+
+/// what the sky pass draws behind the scene
+#[derive(Clone)]
+pub enum Sky {
+    // a single linear rgb colour
+    Colour([f32; 3]),
+    Gradient(GradientSky),
+    // scaled by an intensity, so hdr cubemaps can be brightened or dimmed
+    Cubemap {
+        texture: Arc<Texture>,
+        intensity: f32,
+    },
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Sky::Colour([0.1, 0.2, 0.3])
+    }
+}
+
+impl Sky {
+    /// matches the branches in sky.wgsl
+    pub fn mode(&self) -> u32 {
+        match self {
+            Sky::Colour(_) => 0,
+            Sky::Gradient(_) => 1,
+            Sky::Cubemap { .. } => 2,
+        }
+    }
+}
@@ -1,6 +1,45 @@
+use crate::core::game::EntityId;
+
+/// the mouse as last reported by the window
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MouseState {
+    // pixels from the top left of the window
+    pub position: [f32; 2],
+    pub left_down: bool,
+    // the left button went down or up since the last update
+    pub left_pressed: bool,
+    pub left_released: bool,
+    // the cursor is over a ui node, so clicks shouldn't reach the world
+    pub over_ui: bool,
+    // the entity under the cursor at the last click on the world, once its pick has been read
+    // back - a frame or two after the click
+    pub picked: Option<EntityId>,
+}
+
+impl MouseState {
+    pub fn set_left(&mut self, down: bool) {
+        if down && !self.left_down {
+            self.left_pressed = true;
+        }
+
+        if !down && self.left_down {
+            self.left_released = true;
+        }
+
+        self.left_down = down;
+    }
+
+    /// Clear presses and releases once the update systems have seen them
+    pub fn end_frame(&mut self) {
+        self.left_pressed = false;
+        self.left_released = false;
+    }
+}
+
 pub struct GameState {
     pub total_time: f32, // Total time since game start in seconds
     pub delta_time: f32, // Time since last frame in seconds
+    pub mouse: MouseState,
 }
 
 impl GameState {
@@ -8,6 +47,7 @@ impl GameState {
         Self {
             total_time: 0.0,
             delta_time: 0.0,
+            mouse: MouseState::default(),
         }
     }
 
@@ -0,0 +1,556 @@
+use crate::utils::log;
+use std::collections::HashMap;
+
+/// side of each font's glyph atlas
+pub const ATLAS_SIZE: u32 = 1024;
+
+/// format of the glyph atlas - coverage for bitmap fonts, distance for sdf fonts
+pub const ATLAS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+/// pixel size sdf glyphs are rasterised at, before being scaled to whatever size is drawn
+const SDF_SIZE: f32 = 48.0;
+
+/// pixels either side of a sdf glyph's edge the distance field covers
+const SDF_SPREAD: usize = 6;
+
+/// index of a font loaded with TextRenderer::load_font
+pub type FontId = usize;
+
+/// how a font's glyphs are stored in its atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontRendering {
+    // coverage rasterised at each size drawn - sharpest at small sizes
+    Bitmap,
+    // a signed distance field rasterised once, which stays sharp when scaled or billboarded
+    Sdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Centre,
+    Right,
+}
+
+impl TextAlign {
+    /// how far across the text's width its anchor sits
+    pub fn factor(&self) -> f32 {
+        match self {
+            TextAlign::Left => 0.0,
+            TextAlign::Centre => 0.5,
+            TextAlign::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font: FontId,
+    // in pixels
+    pub size: f32,
+    pub colour: [f32; 4],
+    // which end of each line the anchor is at
+    pub align: TextAlign,
+    // lines longer than this, in pixels, wrap between words
+    pub max_width: Option<f32>,
+}
+
+impl TextStyle {
+    pub fn new(font: FontId, size: f32) -> Self {
+        Self {
+            font,
+            size,
+            colour: [1.0, 1.0, 1.0, 1.0],
+            align: TextAlign::Left,
+            max_width: None,
+        }
+    }
+
+    pub fn with_colour(mut self, colour: [f32; 4]) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+}
+
+/// a glyph's quad relative to the text's anchor, in pixels with y down, and its place in the atlas
+#[derive(Debug, Clone, Copy)]
+pub struct LaidOutGlyph {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+/// where a glyph was packed into the atlas, and how to place it relative to the pen
+#[derive(Debug, Clone, Copy)]
+struct AtlasGlyph {
+    // in atlas pixels
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    // offset of the bitmap's top left from the pen on the baseline, at the size it was rasterised
+    left: f32,
+    top: f32,
+}
+
+/// a font and the atlas its glyphs are packed into as they're first used
+pub struct Font {
+    font: fontdue::Font,
+    pub rendering: FontRendering,
+    // glyphs by character and rasterised pixel size - always SDF_SIZE for sdf fonts
+    glyphs: HashMap<(char, u32), Option<AtlasGlyph>>,
+    pixels: Vec<u8>,
+    // shelf packing - the current row's top, its tallest glyph, and the next free x
+    shelf_y: u32,
+    shelf_height: u32,
+    shelf_x: u32,
+    // the atlas has changed since it was last uploaded
+    pub dirty: bool,
+    // the atlas was cleared to make room since it was last uploaded, @see Font::glyph
+    cleared: bool,
+    // created on the first TextRenderer::upload
+    pub texture: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: &[u8], rendering: FontRendering) -> Result<Self, String> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            font,
+            rendering,
+            glyphs: HashMap::new(),
+            pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE) as usize],
+            shelf_y: 0,
+            shelf_height: 0,
+            shelf_x: 0,
+            dirty: true,
+            cleared: false,
+            texture: None,
+        })
+    }
+
+    /// Lay out text at a pixel size, relative to an anchor at the top of the first line
+    ///
+    /// lines break at newlines, and between words when longer than the style's max width. each line
+    /// is placed against the anchor by the style's alignment.
+    pub fn layout(&mut self, text: &str, style: &TextStyle) -> Vec<LaidOutGlyph> {
+        let size = style.size;
+        let (ascent, line_height) = self.line_metrics(size);
+
+        let mut glyphs = Vec::new();
+
+        for (line_index, line) in self.wrap(text, style).iter().enumerate() {
+            let line_width = self.measure(line, size);
+            let mut pen_x = -line_width * style.align.factor();
+            let baseline = ascent + line_height * line_index as f32;
+            let mut previous = None;
+
+            for character in line.chars() {
+                if let Some(previous) = previous {
+                    pen_x += self
+                        .font
+                        .horizontal_kern(previous, character, size)
+                        .unwrap_or(0.0);
+                }
+
+                previous = Some(character);
+
+                let advance = self.font.metrics(character, size).advance_width;
+
+                if let Some(glyph) = self.glyph(character, size) {
+                    // sdf glyphs are rasterised at one size and scaled
+                    let scale = match self.rendering {
+                        FontRendering::Bitmap => 1.0,
+                        FontRendering::Sdf => size / SDF_SIZE,
+                    };
+
+                    let mut min = [pen_x + glyph.left * scale, baseline - glyph.top * scale];
+
+                    // bitmaps are sharpest on whole pixels
+                    if self.rendering == FontRendering::Bitmap {
+                        min = [min[0].round(), min[1].round()];
+                    }
+
+                    glyphs.push(LaidOutGlyph {
+                        min,
+                        max: [
+                            min[0] + glyph.width as f32 * scale,
+                            min[1] + glyph.height as f32 * scale,
+                        ],
+                        uv_min: [
+                            glyph.x as f32 / ATLAS_SIZE as f32,
+                            glyph.y as f32 / ATLAS_SIZE as f32,
+                        ],
+                        uv_max: [
+                            (glyph.x + glyph.width) as f32 / ATLAS_SIZE as f32,
+                            (glyph.y + glyph.height) as f32 / ATLAS_SIZE as f32,
+                        ],
+                    });
+                }
+
+                pen_x += advance;
+            }
+        }
+
+        glyphs
+    }
+
+    /// Width of the widest line and height of every line of text, in pixels, as it'd be laid out
+    pub fn size(&self, text: &str, style: &TextStyle) -> [f32; 2] {
+        let (_, line_height) = self.line_metrics(style.size);
+        let lines = self.wrap(text, style);
+
+        let width = lines
+            .iter()
+            .map(|line| self.measure(line, style.size))
+            .fold(0.0, f32::max);
+
+        [width, line_height * lines.len() as f32]
+    }
+
+    /// The ascent and the distance between baselines at a pixel size
+    fn line_metrics(&self, size: f32) -> (f32, f32) {
+        match self.font.horizontal_line_metrics(size) {
+            Some(metrics) => (metrics.ascent, metrics.new_line_size),
+            None => (size, size * 1.2),
+        }
+    }
+
+    /// Width of a single line of text, in pixels
+    pub fn measure(&self, line: &str, size: f32) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+
+        for character in line.chars() {
+            if let Some(previous) = previous {
+                width += self
+                    .font
+                    .horizontal_kern(previous, character, size)
+                    .unwrap_or(0.0);
+            }
+
+            width += self.font.metrics(character, size).advance_width;
+            previous = Some(character);
+        }
+
+        width
+    }
+
+    /// Split text into lines at newlines, and greedily between words past the max width
+    fn wrap(&self, text: &str, style: &TextStyle) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for paragraph in text.split('\n') {
+            let max_width = match style.max_width {
+                Some(max_width) => max_width,
+                None => {
+                    lines.push(paragraph.to_string());
+                    continue;
+                }
+            };
+
+            let mut line = String::new();
+
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+
+                // a single word longer than the line is left to overflow
+                if !line.is_empty() && self.measure(&candidate, style.size) > max_width {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                } else {
+                    line = candidate;
+                }
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    /// The glyph's place in the atlas, rasterising and packing it on first use - None for glyphs
+    /// with no pixels, or when the atlas is full
+    ///
+    /// a full atlas is cleared and packed again with the glyphs still in use, so text laid out
+    /// earlier in the frame can show the wrong glyphs for that frame. if it fills up again before
+    /// it's uploaded, the text drawn at once needs more than the atlas holds and glyphs are dropped.
+    fn glyph(&mut self, character: char, size: f32) -> Option<AtlasGlyph> {
+        let pixel_size = match self.rendering {
+            FontRendering::Bitmap => size.round().max(1.0) as u32,
+            FontRendering::Sdf => SDF_SIZE as u32,
+        };
+
+        if let Some(glyph) = self.glyphs.get(&(character, pixel_size)) {
+            return *glyph;
+        }
+
+        let (metrics, coverage) = self.font.rasterize(character, pixel_size as f32);
+
+        let glyph = if metrics.width == 0 || metrics.height == 0 {
+            None
+        } else {
+            let (bitmap, width, height, padding) = match self.rendering {
+                FontRendering::Bitmap => (coverage, metrics.width, metrics.height, 0),
+                FontRendering::Sdf => {
+                    let (field, width, height) =
+                        signed_distance_field(&coverage, metrics.width, metrics.height);
+                    (field, width, height, SDF_SPREAD)
+                }
+            };
+
+            let mut packed = self.pack(&bitmap, width as u32, height as u32);
+
+            // glyphs too big for an empty atlas are left out rather than clearing it for nothing
+            let fits = width < ATLAS_SIZE as usize && height < ATLAS_SIZE as usize;
+
+            if packed.is_none() && fits {
+                if self.cleared {
+                    log::warn(&format!(
+                        "glyph atlas is full, dropping {:?} at {}px",
+                        character, pixel_size
+                    ));
+
+                    // not cached, so it's tried again once the atlas has room
+                    return None;
+                }
+
+                log::warn("glyph atlas is full, clearing it to make room");
+
+                self.clear_atlas();
+                packed = self.pack(&bitmap, width as u32, height as u32);
+            }
+
+            packed.map(|(x, y)| AtlasGlyph {
+                x,
+                y,
+                width: width as u32,
+                height: height as u32,
+                left: metrics.xmin as f32 - padding as f32,
+                top: (metrics.ymin + metrics.height as i32) as f32 + padding as f32,
+            })
+        };
+
+        self.glyphs.insert((character, pixel_size), glyph);
+
+        glyph
+    }
+
+    /// Forget every packed glyph, so those in use are rasterised again as they're laid out
+    fn clear_atlas(&mut self) {
+        self.glyphs.clear();
+        self.pixels.fill(0);
+        self.shelf_y = 0;
+        self.shelf_height = 0;
+        self.shelf_x = 0;
+        self.dirty = true;
+        self.cleared = true;
+    }
+
+    /// Copy a bitmap into the next free space on the atlas's shelves
+    fn pack(&mut self, bitmap: &[u8], width: u32, height: u32) -> Option<(u32, u32)> {
+        // a pixel between glyphs so filtering doesn't bleed between them
+        let padded_width = width + 1;
+        let padded_height = height + 1;
+
+        if self.shelf_x + padded_width > ATLAS_SIZE {
+            self.shelf_y += self.shelf_height;
+            self.shelf_x = 0;
+            self.shelf_height = 0;
+        }
+
+        if self.shelf_y + padded_height > ATLAS_SIZE || padded_width > ATLAS_SIZE {
+            return None;
+        }
+
+        let (x, y) = (self.shelf_x, self.shelf_y);
+
+        for row in 0..height {
+            let source = (row * width) as usize;
+            let target = ((y + row) * ATLAS_SIZE + x) as usize;
+
+            self.pixels[target..target + width as usize]
+                .copy_from_slice(&bitmap[source..source + width as usize]);
+        }
+
+        self.shelf_x += padded_width;
+        self.shelf_height = self.shelf_height.max(padded_height);
+        self.dirty = true;
+
+        Some((x, y))
+    }
+}
+
+/// Distance to the glyph's edge, padded by SDF_SPREAD - 0.5 on the edge, rising inside
+fn signed_distance_field(coverage: &[u8], width: usize, height: usize) -> (Vec<u8>, usize, usize) {
+    let spread = SDF_SPREAD as i32;
+    let field_width = width + SDF_SPREAD * 2;
+    let field_height = height + SDF_SPREAD * 2;
+
+    let inside = |x: i32, y: i32| {
+        let (x, y) = (x - spread, y - spread);
+
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && coverage[y as usize * width + x as usize] >= 128
+    };
+
+    let mut field = vec![0; field_width * field_height];
+
+    for y in 0..field_height as i32 {
+        for x in 0..field_width as i32 {
+            let is_inside = inside(x, y);
+            let mut nearest = (spread * spread) as f32;
+
+            // the closest pixel on the other side of the edge, within the spread
+            for offset_y in -spread..=spread {
+                for offset_x in -spread..=spread {
+                    if inside(x + offset_x, y + offset_y) != is_inside {
+                        nearest = nearest.min((offset_x * offset_x + offset_y * offset_y) as f32);
+                    }
+                }
+            }
+
+            let distance = nearest.sqrt() / SDF_SPREAD as f32;
+            let signed = if is_inside { distance } else { -distance };
+
+            field[y as usize * field_width + x as usize] =
+                ((0.5 + signed * 0.5).clamp(0.0, 1.0) * 255.0) as u8;
+        }
+    }
+
+    (field, field_width, field_height)
+}
+
+/// text queued for this frame at a pixel position on the screen
+pub struct ScreenText {
+    pub text: String,
+    pub position: [f32; 2],
+    pub style: TextStyle,
+}
+
+/// Loaded fonts, and screen text drawn immediately - queue text every frame to keep it on screen,
+/// like DebugDraw. text in the world comes from TextLabelComponents.
+#[derive(Default)]
+pub struct TextRenderer {
+    fonts: Vec<Font>,
+    screen_text: Vec<ScreenText>,
+}
+
+impl TextRenderer {
+    /// Load a TTF or OTF font
+    pub fn load_font(&mut self, path: &str, rendering: FontRendering) -> Result<FontId, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("failed to read font {}: {}", path, e))?;
+        let font = Font::from_bytes(&bytes, rendering)
+            .map_err(|e| format!("failed to load font {}: {}", path, e))?;
+
+        self.fonts.push(font);
+
+        Ok(self.fonts.len() - 1)
+    }
+
+    /// Draw text this frame, anchored at a pixel position from the top left of the window
+    pub fn draw(&mut self, text: &str, position: [f32; 2], style: &TextStyle) {
+        self.screen_text.push(ScreenText {
+            text: text.to_string(),
+            position,
+            style: style.clone(),
+        });
+    }
+
+    /// Width and height of a block of text in pixels, including wrapped lines
+    pub fn size(&self, text: &str, style: &TextStyle) -> [f32; 2] {
+        match self.fonts.get(style.font) {
+            Some(font) => font.size(text, style),
+            None => [0.0, 0.0],
+        }
+    }
+
+    /// Create atlas textures for new fonts, and copy up atlases that have had glyphs added - called
+    /// by passes after laying out text
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        for font in self.fonts.iter_mut() {
+            if font.texture.is_none() {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Glyph Atlas"),
+                    size: wgpu::Extent3d {
+                        width: ATLAS_SIZE,
+                        height: ATLAS_SIZE,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: ATLAS_FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+                font.texture = Some((texture, view));
+                font.dirty = true;
+            }
+
+            if font.dirty {
+                if let Some((texture, _)) = &font.texture {
+                    queue.write_texture(
+                        wgpu::ImageCopyTexture {
+                            texture,
+                            mip_level: 0,
+                            origin: wgpu::Origin3d::ZERO,
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        &font.pixels,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(ATLAS_SIZE),
+                            rows_per_image: Some(ATLAS_SIZE),
+                        },
+                        wgpu::Extent3d {
+                            width: ATLAS_SIZE,
+                            height: ATLAS_SIZE,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                }
+
+                font.dirty = false;
+            }
+
+            font.cleared = false;
+        }
+    }
+
+    pub fn font_mut(&mut self, font: FontId) -> Option<&mut Font> {
+        self.fonts.get_mut(font)
+    }
+
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// Hand over this frame's screen text
+    pub fn take_screen_text(&mut self) -> Vec<ScreenText> {
+        std::mem::take(&mut self.screen_text)
+    }
+}
@@ -0,0 +1,685 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use wgpu;
+
+/// mipmap pipelines by target format, built the first time a texture of that format needs mips
+/// and reused for every texture after - there's only ever the one device
+static MIPMAP_GENERATOR: OnceLock<Mutex<MipmapGenerator>> = OnceLock::new();
+
+struct MipmapGenerator {
+    shader: wgpu::ShaderModule,
+    sampler: wgpu::Sampler,
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+}
+
+impl MipmapGenerator {
+    fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mipmap Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/mipmap.wgsl").into()),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Mipmap Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            shader,
+            sampler,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Build the pipeline for rendering into textures of this format, if it isn't built already
+    fn prepare(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        let shader = &self.shader;
+
+        self.pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Mipmap Pipeline"),
+                layout: None,
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(format.into())],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        });
+    }
+}
+
+/// whether texel data is colour (stored as sRGB) or data such as normals (stored linearly)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColourSpace {
+    Srgb,
+    Linear,
+}
+
+/// sampler settings for a texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerOptions {
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    pub address_mode: wgpu::AddressMode,
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            address_mode: wgpu::AddressMode::Repeat,
+        }
+    }
+}
+
+impl SamplerOptions {
+    /// nearest filtering with clamped edges - useful for pixel art
+    pub fn pixelated() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// options used when creating a texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    pub colour_space: ColourSpace,
+    pub generate_mipmaps: bool,
+    pub sampler: SamplerOptions,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            colour_space: ColourSpace::Srgb,
+            generate_mipmaps: true,
+            sampler: SamplerOptions::default(),
+        }
+    }
+}
+
+/// a texture on the gpu, along with a view and sampler ready for binding
+pub struct Texture {
+    // owns what the view refers to, nothing reads it once created
+    #[allow(dead_code)]
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+}
+
+impl Texture {
+    /// Load a PNG or JPEG file from disk into a texture
+    pub fn from_file(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &str,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("failed to load texture {}: {}", path, e))?
+            .to_rgba8();
+
+        Ok(Self::from_rgba8(
+            device,
+            queue,
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            options,
+            path,
+        ))
+    }
+
+    /// Create a texture from tightly packed rgba8 texel data
+    pub fn from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        options: TextureOptions,
+        label: &str,
+    ) -> Self {
+        let format = match options.colour_space {
+            ColourSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColourSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        };
+
+        let mip_level_count = if options.generate_mipmaps {
+            Self::mip_level_count(width, height)
+        } else {
+            1
+        };
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            // mip levels are generated by rendering into them
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        if mip_level_count > 1 {
+            Self::generate_mipmaps(device, queue, &texture, format, mip_level_count, 0);
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = Self::create_sampler(device, &options.sampler);
+
+        Self {
+            texture,
+            view,
+            sampler,
+            width,
+            height,
+            mip_level_count,
+        }
+    }
+
+    /// Load six square images into a cubemap, in +X, -X, +Y, -Y, +Z, -Z order
+    pub fn cubemap_from_files(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        paths: &[&str; 6],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let mut size = 0;
+        let mut faces = Vec::with_capacity(6);
+
+        for path in paths {
+            let image = image::open(path)
+                .map_err(|e| format!("failed to load cubemap face {}: {}", path, e))?
+                .to_rgba8();
+
+            if image.width() != image.height() || (size != 0 && image.width() != size) {
+                return Err(format!(
+                    "cubemap face {} must be square and match the other faces",
+                    path
+                ));
+            }
+
+            size = image.width();
+            faces.push(image.into_raw());
+        }
+
+        Self::cubemap_from_rgba8(device, queue, size, &faces, options, paths[0])
+    }
+
+    /// Create a cubemap from six faces of tightly packed rgba8 texel data, each size by size texels
+    pub fn cubemap_from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        faces: &[Vec<u8>],
+        options: TextureOptions,
+        label: &str,
+    ) -> Result<Self, String> {
+        if faces.len() != 6 {
+            return Err(format!(
+                "cubemap {} needs 6 faces, got {}",
+                label,
+                faces.len()
+            ));
+        }
+
+        if size == 0 {
+            return Err(format!("cubemap {} has empty faces", label));
+        }
+
+        let face_bytes = (size * size * 4) as usize;
+
+        if let Some(layer) = faces.iter().position(|face| face.len() != face_bytes) {
+            return Err(format!(
+                "cubemap {} face {} is {} bytes, expected {} for {}x{} texels",
+                label,
+                layer,
+                faces[layer].len(),
+                face_bytes,
+                size,
+                size
+            ));
+        }
+
+        let format = match options.colour_space {
+            ColourSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColourSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        };
+
+        let mip_level_count = if options.generate_mipmaps {
+            Self::mip_level_count(size, size)
+        } else {
+            1
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        for (layer, data) in faces.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * size),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            if mip_level_count > 1 {
+                Self::generate_mipmaps(
+                    device,
+                    queue,
+                    &texture,
+                    format,
+                    mip_level_count,
+                    layer as u32,
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cubemap View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        let sampler = Self::create_sampler(device, &options.sampler);
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            width: size,
+            height: size,
+            mip_level_count,
+        })
+    }
+
+    /// Load an equirectangular image, such as a Radiance HDR panorama, and resample it into a
+    /// Rgba16Float cubemap with faces of the given size
+    pub fn cubemap_from_equirect_file(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &str,
+        face_size: u32,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("failed to load equirectangular image {}: {}", path, e))?
+            .to_rgba32f();
+
+        let (width, height) = image.dimensions();
+        let format = wgpu::TextureFormat::Rgba16Float;
+
+        let mip_level_count = if options.generate_mipmaps {
+            Self::mip_level_count(face_size, face_size)
+        } else {
+            1
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(path),
+            size: wgpu::Extent3d {
+                width: face_size,
+                height: face_size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        // bilinear sample of the panorama, wrapping around horizontally
+        let sample = |u: f32, v: f32| -> [f32; 4] {
+            let x = u * width as f32 - 0.5;
+            let y = (v * height as f32 - 0.5).clamp(0.0, height as f32 - 1.0);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+
+            let texel = |x: f32, y: f32| {
+                let x = (x as i64).rem_euclid(width as i64) as u32;
+                let y = (y as u32).min(height - 1);
+                image.get_pixel(x, y).0
+            };
+
+            let corners = [
+                (texel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+                (texel(x0 + 1.0, y0), fx * (1.0 - fy)),
+                (texel(x0, y0 + 1.0), (1.0 - fx) * fy),
+                (texel(x0 + 1.0, y0 + 1.0), fx * fy),
+            ];
+
+            let mut colour = [0.0; 4];
+
+            for (texel, weight) in corners {
+                for channel in 0..4 {
+                    colour[channel] += texel[channel] * weight;
+                }
+            }
+
+            colour
+        };
+
+        for layer in 0..6u32 {
+            let mut data: Vec<u16> = Vec::with_capacity((face_size * face_size * 4) as usize);
+
+            for y in 0..face_size {
+                for x in 0..face_size {
+                    // -1 to 1 across the face, with v going down
+                    let u = (x as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+                    let v = (y as f32 + 0.5) / face_size as f32 * 2.0 - 1.0;
+
+                    // faces in +X, -X, +Y, -Y, +Z, -Z order, as wgpu samples them
+                    let direction = match layer {
+                        0 => glam::Vec3::new(1.0, -v, -u),
+                        1 => glam::Vec3::new(-1.0, -v, u),
+                        2 => glam::Vec3::new(u, 1.0, v),
+                        3 => glam::Vec3::new(u, -1.0, -v),
+                        4 => glam::Vec3::new(u, -v, 1.0),
+                        _ => glam::Vec3::new(-u, -v, -1.0),
+                    }
+                    .normalize();
+
+                    let longitude = direction.x.atan2(-direction.z);
+                    let latitude = direction.y.clamp(-1.0, 1.0).acos();
+
+                    let colour = sample(
+                        longitude / (2.0 * std::f32::consts::PI) + 0.5,
+                        latitude / std::f32::consts::PI,
+                    );
+
+                    data.extend(colour.map(f32_to_f16));
+                }
+            }
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytemuck::cast_slice(&data),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(8 * face_size),
+                    rows_per_image: Some(face_size),
+                },
+                wgpu::Extent3d {
+                    width: face_size,
+                    height: face_size,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            if mip_level_count > 1 {
+                Self::generate_mipmaps(device, queue, &texture, format, mip_level_count, layer);
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cubemap View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        let sampler = Self::create_sampler(device, &options.sampler);
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            width: face_size,
+            height: face_size,
+            mip_level_count,
+        })
+    }
+
+    /// Create a 1x1 cubemap of a single colour - used when no environment is set
+    pub fn solid_cubemap(device: &wgpu::Device, queue: &wgpu::Queue, colour: [u8; 4]) -> Self {
+        // six faces of one texel each are always valid
+        Self::cubemap_from_rgba8(
+            device,
+            queue,
+            1,
+            &vec![colour.to_vec(); 6],
+            TextureOptions {
+                colour_space: ColourSpace::Srgb,
+                generate_mipmaps: false,
+                sampler: SamplerOptions::default(),
+            },
+            "Solid Cubemap",
+        )
+        .unwrap()
+    }
+
+    /// Create a 1x1 texture of a single colour - used in place of missing textures
+    pub fn solid(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        colour: [u8; 4],
+        colour_space: ColourSpace,
+    ) -> Self {
+        Self::from_rgba8(
+            device,
+            queue,
+            1,
+            1,
+            &colour,
+            TextureOptions {
+                colour_space,
+                generate_mipmaps: false,
+                sampler: SamplerOptions::default(),
+            },
+            "Solid Texture",
+        )
+    }
+
+    pub fn create_sampler(device: &wgpu::Device, options: &SamplerOptions) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Texture Sampler"),
+            address_mode_u: options.address_mode,
+            address_mode_v: options.address_mode,
+            address_mode_w: options.address_mode,
+            mag_filter: options.mag_filter,
+            min_filter: options.min_filter,
+            mipmap_filter: options.mipmap_filter,
+            ..Default::default()
+        })
+    }
+
+    /// number of mip levels in a full chain down to 1x1
+    pub fn mip_level_count(width: u32, height: u32) -> u32 {
+        32 - width.max(height).max(1).leading_zeros()
+    }
+
+    /// Fill in mip levels 1.. of one array layer by repeatedly downsampling the previous level
+    fn generate_mipmaps(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+        layer: u32,
+    ) {
+        let mut generator = MIPMAP_GENERATOR
+            .get_or_init(|| Mutex::new(MipmapGenerator::new(device)))
+            .lock()
+            .unwrap();
+
+        generator.prepare(device, format);
+
+        let sampler = &generator.sampler;
+        let pipeline = &generator.pipelines[&format];
+
+        let views: Vec<wgpu::TextureView> = (0..mip_level_count)
+            .map(|mip| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Mip View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Mipmap Encoder"),
+        });
+
+        for target_mip in 1..mip_level_count as usize {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Mipmap Bind Group"),
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[target_mip - 1]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Mipmap Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &views[target_mip],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Convert to a half float, rounding towards zero - values too large for a half become infinity
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+
+    if value.is_nan() {
+        sign | 0x7e00
+    } else if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // subnormal, or too small and flushed to zero
+        if exponent < -10 {
+            sign
+        } else {
+            sign | (((mantissa | 0x80_0000) >> (14 - exponent)) as u16)
+        }
+    } else {
+        sign | ((exponent as u16) << 10) | ((mantissa >> 13) as u16)
+    }
+}
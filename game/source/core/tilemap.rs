@@ -0,0 +1,75 @@
+use crate::core::game::EntityId;
+use std::mem;
+
+/// tiles along each side of a chunk - chunks are meshed, uploaded and culled as a whole
+pub const CHUNK_SIZE: u32 = 16;
+
+/// a 2d quad corner, shared by sprites and tilemap chunks - must match VertexInput in sprite.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpriteVertex {
+    // world units on the xy plane
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub colour: [f32; 4],
+}
+
+impl SpriteVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x2,
+            2 => Float32x4
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<SpriteVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// a square of tiles meshed into its own vertex buffer by the TilemapBufferer
+pub struct TilemapChunk {
+    // needs meshing again, after a tile in it changed
+    pub dirty: bool,
+    // min and max corners in world units, for culling against the 2d camera
+    pub bounds: [[f32; 2]; 2],
+    pub vertex_buffer: Option<wgpu::Buffer>,
+    pub vertex_count: u32,
+    // in vertices
+    pub capacity: usize,
+}
+
+impl TilemapChunk {
+    pub fn new() -> Self {
+        Self {
+            dirty: true,
+            bounds: [[0.0; 2]; 2],
+            vertex_buffer: None,
+            vertex_count: 0,
+            capacity: 0,
+        }
+    }
+}
+
+/// which tiles block colliders
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileCollision {
+    None,
+    // every tile that isn't empty
+    All,
+    // only these tileset indices
+    Tiles(Vec<u32>),
+}
+
+/// a collider overlapping a solid tile, found by the CollisionSystem
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileContact {
+    pub tilemap: EntityId,
+    pub tile: [u32; 2],
+    // direction the collider was pushed out, along x or y
+    pub normal: [f32; 2],
+    pub depth: f32,
+}
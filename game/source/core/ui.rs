@@ -0,0 +1,595 @@
+use crate::core::game::{self, ComponentEnum, ComponentType, EntityId};
+use std::collections::HashMap;
+
+/// a width or height
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiVal {
+    // sized to fit the node's content or children
+    Auto,
+    Px(f32),
+    // of the parent's size inside its padding
+    Percent(f32),
+}
+
+impl UiVal {
+    fn resolve(&self, parent: f32) -> Option<f32> {
+        match self {
+            UiVal::Auto => None,
+            UiVal::Px(px) => Some(*px),
+            UiVal::Percent(percent) => Some(parent * percent / 100.0),
+        }
+    }
+}
+
+/// space around each side of a node, in pixels
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct UiEdges {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl UiEdges {
+    pub fn all(value: f32) -> Self {
+        Self {
+            left: value,
+            right: value,
+            top: value,
+            bottom: value,
+        }
+    }
+
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Self {
+        Self {
+            left: horizontal,
+            right: horizontal,
+            top: vertical,
+            bottom: vertical,
+        }
+    }
+
+    // axis 0 is horizontal, 1 is vertical
+    fn start(&self, axis: usize) -> f32 {
+        if axis == 0 {
+            self.left
+        } else {
+            self.top
+        }
+    }
+
+    fn end(&self, axis: usize) -> f32 {
+        if axis == 0 {
+            self.right
+        } else {
+            self.bottom
+        }
+    }
+
+    fn total(&self, axis: usize) -> f32 {
+        self.start(axis) + self.end(axis)
+    }
+}
+
+/// the axis children are laid out along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiDirection {
+    Row,
+    Column,
+}
+
+impl UiDirection {
+    fn axis(&self) -> usize {
+        match self {
+            UiDirection::Row => 0,
+            UiDirection::Column => 1,
+        }
+    }
+}
+
+/// where children sit along the direction when they don't fill it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiJustify {
+    Start,
+    Centre,
+    #[allow(dead_code)]
+    End,
+    SpaceBetween,
+}
+
+/// where children sit across the direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiAlign {
+    Start,
+    Centre,
+    #[allow(dead_code)]
+    End,
+    // auto sized children fill the parent
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiPosition {
+    // laid out in order among its siblings
+    Flow,
+    // out of the flow, pinned to a point on the parent - [0, 0] is the top left and [1, 1] the
+    // bottom right, and the same point on the node sits there, moved by an offset in pixels
+    Anchored { anchor: [f32; 2], offset: [f32; 2] },
+}
+
+/// flexbox style layout for a node and its children
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiStyle {
+    pub width: UiVal,
+    pub height: UiVal,
+    pub padding: UiEdges,
+    pub margin: UiEdges,
+    pub direction: UiDirection,
+    pub justify: UiJustify,
+    pub align: UiAlign,
+    // between children, in pixels
+    pub gap: f32,
+    // share of the parent's leftover space along its direction
+    pub grow: f32,
+    pub position: UiPosition,
+}
+
+impl Default for UiStyle {
+    fn default() -> Self {
+        Self {
+            width: UiVal::Auto,
+            height: UiVal::Auto,
+            padding: UiEdges::default(),
+            margin: UiEdges::default(),
+            direction: UiDirection::Column,
+            justify: UiJustify::Start,
+            align: UiAlign::Start,
+            gap: 0.0,
+            grow: 0.0,
+            position: UiPosition::Flow,
+        }
+    }
+}
+
+impl UiStyle {
+    pub fn with_size(mut self, width: UiVal, height: UiVal) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: UiEdges) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: UiEdges) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: UiDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_justify(mut self, justify: UiJustify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn with_align(mut self, align: UiAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn with_grow(mut self, grow: f32) -> Self {
+        self.grow = grow;
+        self
+    }
+
+    pub fn anchored(mut self, anchor: [f32; 2], offset: [f32; 2]) -> Self {
+        self.position = UiPosition::Anchored { anchor, offset };
+        self
+    }
+
+    fn size(&self, axis: usize) -> UiVal {
+        if axis == 0 {
+            self.width
+        } else {
+            self.height
+        }
+    }
+}
+
+/// a laid out node, in pixels from the top left of the window
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct UiRect {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl UiRect {
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        (0..2).all(|axis| {
+            point[axis] >= self.position[axis]
+                && point[axis] < self.position[axis] + self.size[axis]
+        })
+    }
+
+    /// The rect inside some edges
+    fn inset(&self, edges: &UiEdges) -> UiRect {
+        UiRect {
+            position: [self.position[0] + edges.left, self.position[1] + edges.top],
+            size: [
+                (self.size[0] - edges.total(0)).max(0.0),
+                (self.size[1] - edges.total(1)).max(0.0),
+            ],
+        }
+    }
+}
+
+/// mouse state of a button node
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UiInteraction {
+    #[default]
+    None,
+    Hovered,
+    Pressed,
+}
+
+/// a node as seen by the layout - its style, the size of what it draws, and its children
+pub struct LayoutNode {
+    pub style: UiStyle,
+    // text or image size, used when the node is auto sized and has no children
+    pub content_size: [f32; 2],
+    // indices into the node list, in order
+    pub children: Vec<usize>,
+    pub rect: UiRect,
+}
+
+/// Lay out trees of nodes in a window of the given size, setting each node's rect
+pub fn layout(nodes: &mut [LayoutNode], roots: &[usize], screen: [f32; 2]) {
+    let mut measured = vec![[0.0; 2]; nodes.len()];
+
+    for &root in roots {
+        measure(nodes, root, &mut measured);
+    }
+
+    let screen_rect = UiRect {
+        position: [0.0, 0.0],
+        size: screen,
+    };
+
+    for &root in roots {
+        let style = &nodes[root].style;
+        let size = [0, 1].map(|axis| {
+            style
+                .size(axis)
+                .resolve(screen[axis])
+                .unwrap_or(measured[root][axis])
+        });
+
+        let rect = match style.position {
+            UiPosition::Flow => UiRect {
+                position: [style.margin.left, style.margin.top],
+                size,
+            },
+            UiPosition::Anchored { anchor, offset } => anchored(&screen_rect, size, anchor, offset),
+        };
+
+        arrange(nodes, root, rect, &measured);
+    }
+}
+
+/// The size each node wants from its content and children alone, percentages aside
+fn measure(nodes: &[LayoutNode], index: usize, measured: &mut Vec<[f32; 2]>) -> [f32; 2] {
+    let node = &nodes[index];
+    let main = node.style.direction.axis();
+    let cross = 1 - main;
+
+    let mut content = [0.0; 2];
+    let mut flow_children = 0;
+
+    for &child in node.children.iter() {
+        let child_size = measure(nodes, child, measured);
+        let child_style = &nodes[child].style;
+
+        // anchored children don't take up space
+        if child_style.position != UiPosition::Flow {
+            continue;
+        }
+
+        content[main] += child_size[main] + child_style.margin.total(main);
+        content[cross] = f32::max(
+            content[cross],
+            child_size[cross] + child_style.margin.total(cross),
+        );
+        flow_children += 1;
+    }
+
+    if flow_children > 0 {
+        content[main] += node.style.gap * (flow_children - 1) as f32;
+    } else {
+        content = node.content_size;
+    }
+
+    let size = [0, 1].map(|axis| match node.style.size(axis) {
+        UiVal::Px(px) => px,
+        _ => content[axis] + node.style.padding.total(axis),
+    });
+
+    measured[index] = size;
+
+    size
+}
+
+/// Place a node at its rect, then its children inside its padding
+fn arrange(nodes: &mut [LayoutNode], index: usize, rect: UiRect, measured: &[[f32; 2]]) {
+    nodes[index].rect = rect;
+
+    let style = nodes[index].style.clone();
+    let children = nodes[index].children.clone();
+    let inner = rect.inset(&style.padding);
+    let main = style.direction.axis();
+    let cross = 1 - main;
+
+    let flow: Vec<usize> = children
+        .iter()
+        .copied()
+        .filter(|&child| nodes[child].style.position == UiPosition::Flow)
+        .collect();
+
+    // sizes along the direction, before leftover space is shared out
+    let mut main_sizes: Vec<f32> = flow
+        .iter()
+        .map(|&child| {
+            nodes[child]
+                .style
+                .size(main)
+                .resolve(inner.size[main])
+                .unwrap_or(measured[child][main])
+        })
+        .collect();
+
+    let used: f32 = flow
+        .iter()
+        .zip(main_sizes.iter())
+        .map(|(&child, size)| size + nodes[child].style.margin.total(main))
+        .sum::<f32>()
+        + style.gap * flow.len().saturating_sub(1) as f32;
+
+    let free = inner.size[main] - used;
+    let total_grow: f32 = flow.iter().map(|&child| nodes[child].style.grow).sum();
+
+    let mut cursor = 0.0;
+    let mut gap = style.gap;
+
+    if free > 0.0 && total_grow > 0.0 {
+        for (size, &child) in main_sizes.iter_mut().zip(flow.iter()) {
+            *size += free * nodes[child].style.grow / total_grow;
+        }
+    } else if free > 0.0 {
+        match style.justify {
+            UiJustify::Start => (),
+            UiJustify::Centre => cursor = free * 0.5,
+            UiJustify::End => cursor = free,
+            UiJustify::SpaceBetween if flow.len() > 1 => {
+                gap += free / (flow.len() - 1) as f32;
+            }
+            UiJustify::SpaceBetween => (),
+        }
+    }
+
+    for (&child, &main_size) in flow.iter().zip(main_sizes.iter()) {
+        let child_style = &nodes[child].style;
+        let margin = child_style.margin;
+
+        let cross_size = match child_style.size(cross).resolve(inner.size[cross]) {
+            Some(size) => size,
+            None if style.align == UiAlign::Stretch => {
+                (inner.size[cross] - margin.total(cross)).max(0.0)
+            }
+            None => measured[child][cross],
+        };
+
+        let cross_offset = match style.align {
+            UiAlign::Start | UiAlign::Stretch => margin.start(cross),
+            UiAlign::Centre => {
+                (inner.size[cross] - cross_size - margin.total(cross)) * 0.5 + margin.start(cross)
+            }
+            UiAlign::End => inner.size[cross] - cross_size - margin.end(cross),
+        };
+
+        cursor += margin.start(main);
+
+        let mut child_rect = UiRect::default();
+        child_rect.position[main] = inner.position[main] + cursor;
+        child_rect.position[cross] = inner.position[cross] + cross_offset;
+        child_rect.size[main] = main_size;
+        child_rect.size[cross] = cross_size;
+
+        cursor += main_size + margin.end(main) + gap;
+
+        arrange(nodes, child, child_rect, measured);
+    }
+
+    for &child in children.iter() {
+        if let UiPosition::Anchored { anchor, offset } = nodes[child].style.position {
+            let child_style = &nodes[child].style;
+            let size = [0, 1].map(|axis| {
+                child_style
+                    .size(axis)
+                    .resolve(inner.size[axis])
+                    .unwrap_or(measured[child][axis])
+            });
+
+            arrange(
+                nodes,
+                child,
+                anchored(&inner, size, anchor, offset),
+                measured,
+            );
+        }
+    }
+}
+
+fn anchored(parent: &UiRect, size: [f32; 2], anchor: [f32; 2], offset: [f32; 2]) -> UiRect {
+    UiRect {
+        position: [0, 1].map(|axis| {
+            parent.position[axis] + (parent.size[axis] - size[axis]) * anchor[axis] + offset[axis]
+        }),
+        size,
+    }
+}
+
+/// Visible ui nodes in the order they're drawn - parents before their children, and siblings in the
+/// order they were created, so later nodes are on top
+pub fn draw_order(world: &game::World) -> Vec<EntityId> {
+    let mut children: HashMap<Option<EntityId>, Vec<EntityId>> = HashMap::new();
+
+    for entity_id in world.get_entities_with_components(&[ComponentType::UiNode]) {
+        if let Some(ComponentEnum::UiNode(node)) =
+            world.get_entity_component_by_type(entity_id, ComponentType::UiNode)
+        {
+            if node.visible {
+                children.entry(node.parent).or_default().push(entity_id);
+            }
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort();
+    }
+
+    let mut order = Vec::new();
+    let mut stack: Vec<EntityId> = children.get(&None).cloned().unwrap_or_default();
+    stack.reverse();
+
+    while let Some(entity_id) = stack.pop() {
+        order.push(entity_id);
+
+        if let Some(siblings) = children.get(&Some(entity_id)) {
+            stack.extend(siblings.iter().rev());
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 100x20 row with padding 5 holding two 10x10 leaves, laid out at the top left of the window
+    fn row(style: UiStyle) -> Vec<LayoutNode> {
+        let leaf = |content_size| LayoutNode {
+            style: UiStyle::default(),
+            content_size,
+            children: Vec::new(),
+            rect: UiRect::default(),
+        };
+
+        vec![
+            LayoutNode {
+                style: style
+                    .with_size(UiVal::Px(100.0), UiVal::Px(20.0))
+                    .with_padding(UiEdges::all(5.0))
+                    .with_direction(UiDirection::Row),
+                content_size: [0.0, 0.0],
+                children: vec![1, 2],
+                rect: UiRect::default(),
+            },
+            leaf([10.0, 10.0]),
+            leaf([10.0, 6.0]),
+        ]
+    }
+
+    fn positions(nodes: &[LayoutNode]) -> Vec<[f32; 2]> {
+        nodes[1..].iter().map(|node| node.rect.position).collect()
+    }
+
+    #[test]
+    fn justify_places_children_along_the_row() {
+        let cases = [
+            (UiJustify::Start, [5.0, 15.0]),
+            (UiJustify::Centre, [40.0, 50.0]),
+            (UiJustify::End, [75.0, 85.0]),
+            (UiJustify::SpaceBetween, [5.0, 85.0]),
+        ];
+
+        for (justify, expected) in cases {
+            let mut nodes = row(UiStyle::default().with_justify(justify));
+            layout(&mut nodes, &[0], [800.0, 600.0]);
+
+            let x: Vec<f32> = positions(&nodes)
+                .iter()
+                .map(|position| position[0])
+                .collect();
+            assert_eq!(x, expected, "{:?}", justify);
+        }
+    }
+
+    #[test]
+    fn align_places_children_across_the_row() {
+        let cases = [
+            (UiAlign::Start, [5.0, 5.0], 6.0),
+            (UiAlign::Centre, [5.0, 7.0], 6.0),
+            (UiAlign::End, [5.0, 9.0], 6.0),
+            (UiAlign::Stretch, [5.0, 5.0], 10.0),
+        ];
+
+        for (align, expected, second_height) in cases {
+            let mut nodes = row(UiStyle::default().with_align(align));
+            layout(&mut nodes, &[0], [800.0, 600.0]);
+
+            let y: Vec<f32> = positions(&nodes)
+                .iter()
+                .map(|position| position[1])
+                .collect();
+            assert_eq!(y, expected, "{:?}", align);
+            assert_eq!(nodes[2].rect.size[1], second_height, "{:?}", align);
+        }
+    }
+
+    #[test]
+    fn grow_shares_the_leftover_space() {
+        let mut nodes = row(UiStyle::default().with_gap(10.0));
+        nodes[1].style.grow = 1.0;
+        nodes[2].style.grow = 3.0;
+        layout(&mut nodes, &[0], [800.0, 600.0]);
+
+        // 90 wide inside the padding, less 20 for the children and 10 for the gap
+        assert_eq!(nodes[1].rect.size[0], 10.0 + 15.0);
+        assert_eq!(nodes[2].rect.size[0], 10.0 + 45.0);
+        assert_eq!(nodes[2].rect.position[0], 5.0 + 25.0 + 10.0);
+    }
+
+    #[test]
+    fn auto_size_fits_children_and_percent_uses_the_parent() {
+        let mut nodes = row(UiStyle::default().with_gap(4.0));
+        nodes[0].style.width = UiVal::Auto;
+        nodes[1].style.height = UiVal::Percent(50.0);
+        layout(&mut nodes, &[0], [800.0, 600.0]);
+
+        // two 10 wide children, the gap and the padding
+        assert_eq!(nodes[0].rect.size[0], 10.0 + 4.0 + 10.0 + 10.0);
+        assert_eq!(nodes[1].rect.size[1], 5.0);
+    }
+
+    #[test]
+    fn anchored_children_are_out_of_the_flow() {
+        let mut nodes = row(UiStyle::default());
+        nodes[1].style = UiStyle::default().anchored([1.0, 1.0], [-2.0, 0.0]);
+        layout(&mut nodes, &[0], [800.0, 600.0]);
+
+        // pinned to the bottom right inside the padding, leaving the other child at the start
+        assert_eq!(nodes[1].rect.position, [95.0 - 10.0 - 2.0, 15.0 - 10.0]);
+        assert_eq!(nodes[2].rect.position, [5.0, 5.0]);
+    }
+}
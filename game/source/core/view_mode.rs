@@ -0,0 +1,21 @@
+/// how the scene is shaded - anything but Lit swaps the mesh passes for the debug view pass, and
+/// skips bloom, tonemapping and post processing so the values are shown as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ViewMode {
+    #[default]
+    Lit,
+    // triangle edges, with PolygonMode::Line where the device supports it
+    Wireframe,
+    // world space normals, mapped from -1..1 to 0..1
+    Normals,
+    // linear view depth, white at the near plane
+    Depth,
+    // a flat colour hashed from each mesh's entity id
+    EntityId,
+}
+
+impl ViewMode {
+    pub fn is_debug(&self) -> bool {
+        *self != ViewMode::Lit
+    }
+}
@@ -1,5 +1,6 @@
 mod components;
 mod core;
+mod passes;
 mod systems;
 mod utils;
 
@@ -0,0 +1,277 @@
+use crate::core::hdr::{self, BloomUniforms};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{game, renderer};
+use std::mem;
+
+struct BloomResources {
+    prefilter_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    // one per step - every downsample, then every upsample
+    uniform_buffers: Vec<wgpu::Buffer>,
+    // one per step over the graph's textures, and the graph generation they were created in
+    bind_groups: Option<(u64, Vec<wgpu::BindGroup>)>,
+}
+
+/// Blurs the bright parts of the hdr scene by thresholding it, downsampling it through the bloom
+/// chain, then upsampling and adding each level back onto the one above
+pub struct BloomPass {
+    resources: Option<BloomResources>,
+}
+
+impl BloomPass {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    fn create_resources(device: &wgpu::Device) -> BloomResources {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/bloom.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bloom Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = |label: &str, entry_point: &str, blend: Option<wgpu::BlendState>| {
+            render_graph::fullscreen_pipeline(
+                device,
+                label,
+                &shader,
+                entry_point,
+                &bind_group_layout,
+                hdr::HDR_FORMAT,
+                blend,
+            )
+        };
+
+        let prefilter_pipeline = pipeline("Bloom Prefilter Pipeline", "fs_prefilter", None);
+        let downsample_pipeline = pipeline("Bloom Downsample Pipeline", "fs_downsample", None);
+        let upsample_pipeline = pipeline(
+            "Bloom Upsample Pipeline",
+            "fs_upsample",
+            Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::REPLACE,
+            }),
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Bloom Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffers = (0..hdr::BLOOM_MIP_COUNT * 2 - 1)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Bloom Uniform Buffer"),
+                    size: mem::size_of::<BloomUniforms>() as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        BloomResources {
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffers,
+            bind_groups: None,
+        }
+    }
+}
+
+impl GraphPass for BloomPass {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::HDR]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        render_graph::BLOOM_TEXTURES.to_vec()
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        if self.resources.is_none() {
+            self.resources = Some(Self::create_resources(&renderer.device().lock().unwrap()));
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &mut self.resources {
+            Some(resources) => resources,
+            None => return,
+        };
+
+        let settings = renderer.hdr_settings();
+        let bloom_target = context.view(render_graph::BLOOM_TEXTURES[0]);
+
+        // leave the chain black so the tonemapper adds nothing
+        if !settings.bloom || renderer.view_mode().is_debug() {
+            render_graph::begin_fullscreen_pass(
+                context.encoder,
+                "Bloom Clear Pass",
+                bloom_target,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            );
+            return;
+        }
+
+        let device = renderer.device().lock().unwrap();
+        let queue = renderer.queue().lock().unwrap();
+
+        // threshold and downsample the scene, then each level into the next
+        let mut steps = Vec::with_capacity(hdr::BLOOM_MIP_COUNT * 2 - 1);
+
+        for level in 0..hdr::BLOOM_MIP_COUNT {
+            let source = if level == 0 {
+                render_graph::HDR
+            } else {
+                render_graph::BLOOM_TEXTURES[level - 1]
+            };
+
+            steps.push((source, render_graph::BLOOM_TEXTURES[level], false));
+        }
+
+        // blur each level back up and add it onto the one above
+        for level in (0..hdr::BLOOM_MIP_COUNT - 1).rev() {
+            steps.push((
+                render_graph::BLOOM_TEXTURES[level + 1],
+                render_graph::BLOOM_TEXTURES[level],
+                true,
+            ));
+        }
+
+        let generation = context.generation();
+
+        if resources.bind_groups.as_ref().map(|(created, _)| *created) != Some(generation) {
+            let bind_groups = steps
+                .iter()
+                .zip(resources.uniform_buffers.iter())
+                .map(|(&(source, _, _), uniform_buffer)| {
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Bloom Bind Group"),
+                        layout: &resources.bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(context.view(source)),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Sampler(&resources.sampler),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: uniform_buffer.as_entire_binding(),
+                            },
+                        ],
+                    })
+                })
+                .collect();
+
+            resources.bind_groups = Some((generation, bind_groups));
+        }
+
+        let bind_groups = match &resources.bind_groups {
+            Some((_, bind_groups)) => bind_groups,
+            None => return,
+        };
+
+        for (step, (source, target, additive)) in steps.into_iter().enumerate() {
+            let (width, height) = context.size(source);
+
+            queue.write_buffer(
+                &resources.uniform_buffers[step],
+                0,
+                bytemuck::cast_slice(&[BloomUniforms {
+                    settings: [
+                        settings.bloom_threshold,
+                        settings.bloom_knee,
+                        settings.bloom_radius,
+                        0.0,
+                    ],
+                    texel_size: [1.0 / width as f32, 1.0 / height as f32, 0.0, 0.0],
+                }]),
+            );
+
+            let pipeline = if additive {
+                &resources.upsample_pipeline
+            } else if step == 0 {
+                &resources.prefilter_pipeline
+            } else {
+                &resources.downsample_pipeline
+            };
+
+            // upsampling adds onto what the downsample left in the target
+            let load = if additive {
+                wgpu::LoadOp::Load
+            } else {
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK)
+            };
+
+            let mut render_pass = render_graph::begin_fullscreen_pass(
+                context.encoder,
+                "Bloom Pass",
+                context.view(target),
+                load,
+            );
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_groups[step], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::core::debug_draw::DebugVertex;
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{game, hdr, renderer};
+use std::mem;
+
+struct DebugDrawResources {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    // in vertices
+    capacity: usize,
+    // sample count the pipeline was created for
+    sample_count: u32,
+}
+
+/// Draws the renderer's DebugDraw lines into the scene, depth tested against it but not writing
+/// depth, then drops the ones that have expired
+pub struct DebugDrawPass {
+    resources: Option<DebugDrawResources>,
+    vertex_count: u32,
+}
+
+impl DebugDrawPass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            vertex_count: 0,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        global_bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/debug.wgsl"));
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Draw Pipeline Layout"),
+            bind_group_layouts: &[global_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Draw Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[DebugVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: hdr::HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Draw Vertex Buffer"),
+            size: (capacity * mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+impl GraphPass for DebugDrawPass {
+    fn name(&self) -> &'static str {
+        "debug_draw"
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        render_graph::SCENE_OUTPUTS.to_vec()
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let vertices = if renderer.debug_draw().enabled {
+            renderer.debug_draw().vertices()
+        } else {
+            Vec::new()
+        };
+
+        renderer.debug_draw_mut().end_frame();
+
+        self.vertex_count = vertices.len() as u32;
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        let device = renderer.device().lock().unwrap();
+        let sample_count = renderer.msaa_samples();
+
+        let resources = match self.resources.take() {
+            Some(resources) if resources.sample_count == sample_count => resources,
+            existing => {
+                let capacity = existing.map_or(1024, |resources| resources.capacity);
+
+                DebugDrawResources {
+                    pipeline: Self::create_pipeline(
+                        &device,
+                        renderer.global_bind_group_layout(),
+                        sample_count,
+                    ),
+                    vertex_buffer: Self::create_vertex_buffer(&device, capacity),
+                    capacity,
+                    sample_count,
+                }
+            }
+        };
+
+        let resources = self.resources.insert(resources);
+
+        if vertices.len() > resources.capacity {
+            resources.capacity = vertices.len().next_power_of_two();
+            resources.vertex_buffer = Self::create_vertex_buffer(&device, resources.capacity);
+        }
+
+        renderer.queue().lock().unwrap().write_buffer(
+            &resources.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&vertices),
+        );
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &self.resources {
+            Some(resources) if self.vertex_count > 0 => resources,
+            _ => return,
+        };
+
+        let mut render_pass = render_graph::begin_scene_pass(
+            context,
+            renderer,
+            "Debug Draw Pass",
+            wgpu::Color::BLACK,
+            false,
+        );
+
+        render_pass.set_pipeline(&resources.pipeline);
+        render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::core::game::{self, ComponentEnum, ComponentType};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::shaders::ShaderLibrary;
+use crate::core::view_mode::ViewMode;
+use crate::core::{geometry, hdr, renderer};
+use std::collections::HashMap;
+
+struct DebugViewResources {
+    vertex_shader: wgpu::ShaderModule,
+    shader: wgpu::ShaderModule,
+    // the mesh's vertex and index buffers, for the barycentric wireframe
+    mesh_bind_group_layout: wgpu::BindGroupLayout,
+    // keyed by view mode and sample count
+    pipelines: HashMap<(ViewMode, u32), wgpu::RenderPipeline>,
+}
+
+/// Draws every visible mesh with the renderer's debug view mode in place of the opaque and
+/// transparent passes, @see Renderer::set_view_mode
+pub struct DebugViewPass {
+    resources: Option<DebugViewResources>,
+    // drawing lines with PolygonMode::Line, rather than the barycentric fallback
+    line_mode: bool,
+}
+
+impl DebugViewPass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            line_mode: false,
+        }
+    }
+
+    fn create_resources(
+        device: &wgpu::Device,
+        renderer: &renderer::Renderer,
+    ) -> DebugViewResources {
+        // the material vertex stage, with its includes expanded
+        let vertex_source = ShaderLibrary::embedded("vertex.wgsl").unwrap_or_default();
+        let vertex_shader =
+            match renderer
+                .shaders()
+                .preprocess("vertex.wgsl", vertex_source, &[], true)
+            {
+                Ok(vertex) => renderer::Renderer::load_shader(device, &vertex.source),
+                Err(e) => panic!("built in vertex shader failed to preprocess: {}", e),
+            };
+        let shader =
+            renderer::Renderer::load_shader(device, include_str!("../shaders/debug_view.wgsl"));
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let mesh_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Debug View Mesh Bind Group Layout"),
+                entries: &[storage_entry(0), storage_entry(1)],
+            });
+
+        DebugViewResources {
+            vertex_shader,
+            shader,
+            mesh_bind_group_layout,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        renderer: &renderer::Renderer,
+        view_mode: ViewMode,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let resources = self.resources.as_ref().unwrap();
+        let barycentric = view_mode == ViewMode::Wireframe && !self.line_mode;
+
+        let mut bind_group_layouts = vec![
+            renderer.global_bind_group_layout(),
+            renderer.transform_bind_group_layout(),
+        ];
+
+        if barycentric {
+            bind_group_layouts.push(&resources.mesh_bind_group_layout);
+        }
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug View Pipeline Layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let vertex_buffers = [geometry::Vertex::desc()];
+
+        let (vertex_module, vertex_entry_point, buffers): (_, _, &[wgpu::VertexBufferLayout]) =
+            if barycentric {
+                (&resources.shader, "vs_wireframe", &[])
+            } else {
+                (&resources.vertex_shader, "vs_main", &vertex_buffers)
+            };
+
+        let fragment_entry_point = match view_mode {
+            ViewMode::Wireframe if barycentric => "fs_wireframe_barycentric",
+            ViewMode::Wireframe => "fs_wireframe",
+            ViewMode::Depth => "fs_depth",
+            ViewMode::EntityId => "fs_entity_id",
+            ViewMode::Normals | ViewMode::Lit => "fs_normals",
+        };
+
+        // wireframes show the back of the mesh too
+        let (cull_mode, polygon_mode) = match view_mode {
+            ViewMode::Wireframe if self.line_mode => (None, wgpu::PolygonMode::Line),
+            ViewMode::Wireframe => (None, wgpu::PolygonMode::Fill),
+            _ => (Some(wgpu::Face::Back), wgpu::PolygonMode::Fill),
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug View Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: vertex_module,
+                entry_point: Some(vertex_entry_point),
+                buffers,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &resources.shader,
+                entry_point: Some(fragment_entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: hdr::HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode,
+                polygon_mode,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+impl GraphPass for DebugViewPass {
+    fn name(&self) -> &'static str {
+        "debug_view"
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        render_graph::SCENE_OUTPUTS.to_vec()
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let device = renderer.device().lock().unwrap();
+
+        if self.resources.is_none() {
+            self.resources = Some(Self::create_resources(&device, renderer));
+            self.line_mode = device
+                .features()
+                .contains(wgpu::Features::POLYGON_MODE_LINE);
+        }
+
+        let key = (renderer.view_mode(), renderer.msaa_samples());
+
+        if !self
+            .resources
+            .as_ref()
+            .unwrap()
+            .pipelines
+            .contains_key(&key)
+        {
+            let pipeline = self.create_pipeline(&device, renderer, key.0, key.1);
+
+            self.resources
+                .as_mut()
+                .unwrap()
+                .pipelines
+                .insert(key, pipeline);
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let view_mode = renderer.view_mode();
+
+        let (resources, pipeline) = match &self.resources {
+            Some(resources) => match resources
+                .pipelines
+                .get(&(view_mode, renderer.msaa_samples()))
+            {
+                Some(pipeline) => (resources, pipeline),
+                None => return,
+            },
+            None => return,
+        };
+
+        let barycentric = view_mode == ViewMode::Wireframe && !self.line_mode;
+        let device = renderer.device().lock().unwrap();
+        let draws = context.draws;
+
+        // storage bindings are made before the pass borrows the encoder
+        let mesh_bind_groups: HashMap<(game::EntityId, usize), wgpu::BindGroup> = if barycentric {
+            draws
+                .opaque
+                .iter()
+                .chain(draws.transparent.iter())
+                .filter_map(|draw| {
+                    match world.get_entity_component_by_type(draw.entity_id, ComponentType::Mesh) {
+                        Some(ComponentEnum::Mesh(mesh)) => {
+                            let (vertex_buffer, index_buffer, _) = mesh.buffers(draw.lod)?;
+
+                            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                                label: Some("Debug View Mesh Bind Group"),
+                                layout: &resources.mesh_bind_group_layout,
+                                entries: &[
+                                    wgpu::BindGroupEntry {
+                                        binding: 0,
+                                        resource: vertex_buffer.as_entire_binding(),
+                                    },
+                                    wgpu::BindGroupEntry {
+                                        binding: 1,
+                                        resource: index_buffer.as_entire_binding(),
+                                    },
+                                ],
+                            });
+
+                            Some(((draw.entity_id, draw.lod), bind_group))
+                        }
+                        _ => None,
+                    }
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut render_pass = render_graph::begin_scene_pass(
+            context,
+            renderer,
+            "Debug View Pass",
+            wgpu::Color::BLACK,
+            false,
+        );
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
+
+        for draw in draws.opaque.iter().chain(draws.transparent.iter()) {
+            let mesh = world.get_entity_component_by_type(draw.entity_id, ComponentType::Mesh);
+
+            if let Some(ComponentEnum::Mesh(mesh)) = mesh {
+                if let Some((vertex_buffer, index_buffer, num_indices)) = mesh.buffers(draw.lod) {
+                    render_pass.set_bind_group(
+                        1,
+                        renderer.transform_bind_group(),
+                        &[draw.transform_offset],
+                    );
+
+                    if barycentric {
+                        if let Some(mesh_bind_group) =
+                            mesh_bind_groups.get(&(draw.entity_id, draw.lod))
+                        {
+                            render_pass.set_bind_group(2, mesh_bind_group, &[]);
+                            render_pass.draw(0..num_indices, 0..1);
+                        }
+                    } else {
+                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                        render_pass
+                            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                        render_pass.draw_indexed(0..num_indices, 0, 0..1);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{game, renderer};
+
+struct DepthResolveResources {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+/// Resolves the multisampled scene depth into the main depth buffer, so later passes can sample
+/// it - does nothing when msaa is disabled, as the scene was drawn into the main depth buffer
+pub struct DepthResolvePass {
+    resources: Option<DepthResolveResources>,
+}
+
+impl DepthResolvePass {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    fn create_resources(device: &wgpu::Device) -> DepthResolveResources {
+        let shader =
+            renderer::Renderer::load_shader(device, include_str!("../shaders/depth_resolve.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Resolve Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: true,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Resolve Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // writes every pixel's depth, with no colour targets
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth Resolve Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        DepthResolveResources {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+}
+
+impl GraphPass for DepthResolvePass {
+    fn name(&self) -> &'static str {
+        "depth_resolve"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::MSAA_DEPTH]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::DEPTH]
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        if self.resources.is_none() && renderer.msaa_samples() > 1 {
+            self.resources = Some(Self::create_resources(&renderer.device().lock().unwrap()));
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &self.resources {
+            Some(resources) if renderer.msaa_samples() > 1 => resources,
+            _ => return,
+        };
+
+        let bind_group =
+            renderer
+                .device()
+                .lock()
+                .unwrap()
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Depth Resolve Bind Group"),
+                    layout: &resources.bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(
+                            context.view(render_graph::MSAA_DEPTH),
+                        ),
+                    }],
+                });
+
+        let mut render_pass = context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Depth Resolve Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: context.view(render_graph::DEPTH),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+        render_pass.set_pipeline(&resources.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
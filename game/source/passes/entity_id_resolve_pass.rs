@@ -0,0 +1,136 @@
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{game, picking, renderer};
+
+struct EntityIdResolveResources {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // over the graph's textures, and the graph generation it was created in
+    bind_group: Option<(u64, wgpu::BindGroup)>,
+}
+
+/// Resolves the multisampled entity ids written by the opaque pass into the target picks are read
+/// back from - does nothing without picking or msaa, as the opaque pass writes that target itself
+pub struct EntityIdResolvePass {
+    resources: Option<EntityIdResolveResources>,
+}
+
+impl EntityIdResolvePass {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    fn create_resources(device: &wgpu::Device) -> EntityIdResolveResources {
+        let shader = renderer::Renderer::load_shader(
+            device,
+            include_str!("../shaders/entity_id_resolve.wgsl"),
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Entity Id Resolve Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Uint,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: true,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline = render_graph::fullscreen_pipeline(
+            device,
+            "Entity Id Resolve Pipeline",
+            &shader,
+            "fs_main",
+            &bind_group_layout,
+            picking::ENTITY_ID_FORMAT,
+            None,
+        );
+
+        EntityIdResolveResources {
+            pipeline,
+            bind_group_layout,
+            bind_group: None,
+        }
+    }
+}
+
+impl GraphPass for EntityIdResolvePass {
+    fn name(&self) -> &'static str {
+        "entity_id_resolve"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::MSAA_ENTITY_IDS]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::ENTITY_IDS]
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        if self.resources.is_none() && renderer.picking().enabled && renderer.msaa_samples() > 1 {
+            self.resources = Some(Self::create_resources(&renderer.device().lock().unwrap()));
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &mut self.resources {
+            Some(resources) if renderer.picking().enabled && renderer.msaa_samples() > 1 => {
+                resources
+            }
+            _ => return,
+        };
+
+        let generation = context.generation();
+
+        if resources.bind_group.as_ref().map(|(created, _)| *created) != Some(generation) {
+            let bind_group =
+                renderer
+                    .device()
+                    .lock()
+                    .unwrap()
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Entity Id Resolve Bind Group"),
+                        layout: &resources.bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(
+                                context.view(render_graph::MSAA_ENTITY_IDS),
+                            ),
+                        }],
+                    });
+
+            resources.bind_group = Some((generation, bind_group));
+        }
+
+        let bind_group = match &resources.bind_group {
+            Some((_, bind_group)) => bind_group,
+            None => return,
+        };
+
+        let target = context.view(render_graph::ENTITY_IDS);
+        let mut render_pass = render_graph::begin_fullscreen_pass(
+            context.encoder,
+            "Entity Id Resolve Pass",
+            target,
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        );
+
+        render_pass.set_pipeline(&resources.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
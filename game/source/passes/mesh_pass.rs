@@ -0,0 +1,132 @@
+use crate::components::material_component::MaterialPipelineKey;
+use crate::core::render_graph::{self, GraphPass, MeshDraw, PassContext};
+use crate::core::{game, renderer};
+
+/// Draws meshes with their materials - either the opaque and cutout meshes, writing depth,
+/// or the transparent ones over them, depth tested but not written
+pub struct MeshPass {
+    pub transparent: bool,
+}
+
+impl MeshPass {
+    pub fn opaque() -> Self {
+        Self { transparent: false }
+    }
+
+    pub fn transparent() -> Self {
+        Self { transparent: true }
+    }
+}
+
+impl GraphPass for MeshPass {
+    fn name(&self) -> &'static str {
+        if self.transparent {
+            "transparent"
+        } else {
+            "opaque"
+        }
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::SHADOW_MAPS]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        let mut outputs = render_graph::SCENE_OUTPUTS.to_vec();
+
+        if !self.transparent {
+            outputs.extend([render_graph::ENTITY_IDS, render_graph::MSAA_ENTITY_IDS]);
+        }
+
+        outputs
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let draw_lists = context.draws;
+        let draws = if self.transparent {
+            &draw_lists.transparent
+        } else {
+            &draw_lists.opaque
+        };
+
+        // nothing to composite, and the opaque pass has already cleared the targets
+        if self.transparent && draws.is_empty() {
+            return;
+        }
+
+        let label = if self.transparent {
+            "Transparent Pass"
+        } else {
+            "Opaque Pass"
+        };
+
+        // opaque meshes also write their entity ids for picking, @see Renderer::set_picking_enabled
+        let entity_ids = !self.transparent && renderer.picking().enabled;
+
+        // the sky pass fills in the background
+        let mut render_pass = render_graph::begin_scene_pass(
+            context,
+            renderer,
+            label,
+            wgpu::Color::BLACK,
+            entity_ids,
+        );
+
+        draw_meshes(&mut render_pass, world, renderer, draws);
+    }
+}
+
+/// Draw meshes with their materials, in the given order
+pub fn draw_meshes<'a>(
+    render_pass: &mut wgpu::RenderPass<'a>,
+    world: &'a game::World,
+    renderer: &'a renderer::Renderer,
+    draws: &[MeshDraw],
+) {
+    render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
+
+    for draw in draws {
+        let mesh = world.get_entity_component_by_type(draw.entity_id, game::ComponentType::Mesh);
+
+        if let Some(game::ComponentEnum::Mesh(mesh)) = mesh {
+            // fall back to the default material for meshes without one
+            let (pipeline_key, material_bind_group) = match world
+                .get_entity_component_by_type(draw.entity_id, game::ComponentType::Material)
+            {
+                Some(game::ComponentEnum::Material(material)) => {
+                    (material.pipeline_key(), material.bind_group.as_ref())
+                }
+                _ => (
+                    MaterialPipelineKey::default(),
+                    Some(renderer.default_material_bind_group()),
+                ),
+            };
+
+            if let (
+                Some((vertex_buffer, index_buffer, num_indices)),
+                Some(material_bind_group),
+                Some(pipeline),
+            ) = (
+                mesh.buffers(draw.lod),
+                material_bind_group,
+                renderer.material_pipeline(&pipeline_key),
+            ) {
+                render_pass.set_bind_group(
+                    1,
+                    renderer.transform_bind_group(),
+                    &[draw.transform_offset],
+                );
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(2, material_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..num_indices, 0, 0..1);
+            }
+        }
+    }
+}
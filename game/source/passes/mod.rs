@@ -0,0 +1,14 @@
+pub mod bloom_pass;
+pub mod debug_draw_pass;
+pub mod debug_view_pass;
+pub mod depth_resolve_pass;
+pub mod entity_id_resolve_pass;
+pub mod mesh_pass;
+pub mod particle_pass;
+pub mod post_process_pass;
+pub mod shadow_pass;
+pub mod sky_pass;
+pub mod sprite_pass;
+pub mod text_pass;
+pub mod tonemap_pass;
+pub mod ui_pass;
@@ -0,0 +1,193 @@
+use crate::components::particle_emitter_component::ParticleBlend;
+use crate::core::game::{self, ComponentEnum, ComponentType};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{hdr, renderer};
+use std::collections::HashMap;
+
+/// Draws every emitter's particles into the scene as camera facing quads, depth tested against it
+/// but not writing depth - after the transparent meshes, so particles sit over glass
+pub struct ParticlePass {
+    shader: Option<wgpu::ShaderModule>,
+    // keyed by blend and sample count
+    pipelines: HashMap<(ParticleBlend, u32), wgpu::RenderPipeline>,
+}
+
+impl ParticlePass {
+    pub fn new() -> Self {
+        Self {
+            shader: None,
+            pipelines: HashMap::new(),
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        renderer: &renderer::Renderer,
+        blend: ParticleBlend,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[
+                renderer.global_bind_group_layout(),
+                &renderer.particle_gpu().render_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let blend = match blend {
+            ParticleBlend::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            ParticleBlend::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: hdr::HDR_FORMAT,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+impl GraphPass for ParticlePass {
+    fn name(&self) -> &'static str {
+        "particles"
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        render_graph::SCENE_OUTPUTS.to_vec()
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let device = renderer.device().clone();
+        let device = device.lock().unwrap();
+        let sample_count = renderer.msaa_samples();
+
+        for (_, component) in world.get_components_by_type(ComponentType::ParticleEmitter) {
+            if let ComponentEnum::ParticleEmitter(emitter) = component {
+                let key = (emitter.blend, sample_count);
+
+                if self.pipelines.contains_key(&key) {
+                    continue;
+                }
+
+                let shader = self.shader.get_or_insert_with(|| {
+                    renderer::Renderer::load_shader(
+                        &device,
+                        include_str!("../shaders/particle.wgsl"),
+                    )
+                });
+
+                let pipeline =
+                    Self::create_pipeline(&device, shader, renderer, emitter.blend, sample_count);
+                self.pipelines.insert(key, pipeline);
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let camera_position = renderer.camera().position;
+
+        let mut emitters: Vec<_> = world
+            .get_components_by_type(ComponentType::ParticleEmitter)
+            .into_iter()
+            .filter_map(|(entity_id, component)| match component {
+                ComponentEnum::ParticleEmitter(emitter) => emitter
+                    .buffers
+                    .as_ref()
+                    .filter(|buffers| buffers.instance_count > 0)
+                    .map(|buffers| {
+                        let distance = match world
+                            .get_entity_component_by_type(entity_id, ComponentType::Transform)
+                        {
+                            Some(ComponentEnum::Transform(transform)) => {
+                                camera_position.distance_squared(transform.position.into())
+                            }
+                            _ => 0.0,
+                        };
+
+                        (emitter.blend, buffers, distance)
+                    }),
+                _ => None,
+            })
+            .collect();
+
+        if emitters.is_empty() {
+            return;
+        }
+
+        // emitters back to front, so blended ones cover those behind them - the ParticleBufferer
+        // sorts the particles within cpu emitters, gpu emitters' particles draw in ring order
+        emitters.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        let sample_count = renderer.msaa_samples();
+
+        let mut render_pass = render_graph::begin_scene_pass(
+            context,
+            renderer,
+            "Particle Pass",
+            wgpu::Color::BLACK,
+            false,
+        );
+
+        render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
+
+        for (blend, buffers, _) in emitters {
+            if let Some(pipeline) = self.pipelines.get(&(blend, sample_count)) {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(1, &buffers.render_bind_group, &[]);
+                render_pass.draw(0..6, 0..buffers.instance_count);
+            }
+        }
+    }
+}
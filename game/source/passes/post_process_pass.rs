@@ -0,0 +1,372 @@
+use crate::core::post::{self, PostUniforms};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::texture::{self, Texture};
+use crate::core::{game, renderer};
+use crate::utils::log;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::Arc;
+
+/// side of the identity lookup table used when colour grading has no lut
+const IDENTITY_LUT_SIZE: u32 = 16;
+
+/// an effect from the stack, ready to draw
+struct PostStep {
+    entry_point: &'static str,
+    uniform_buffer_index: usize,
+    lut: Arc<Texture>,
+}
+
+/// an enabled effect gathered from the stack, before its pipeline and uniforms are set up
+struct PendingEffect {
+    entry_point: &'static str,
+    params: [[f32; 4]; 2],
+    lut: Option<Arc<Texture>>,
+}
+
+struct PostResources {
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    identity_lut: Arc<Texture>,
+    // keyed by entry point and target format
+    pipelines: HashMap<(&'static str, wgpu::TextureFormat), wgpu::RenderPipeline>,
+    uniform_buffers: Vec<wgpu::Buffer>,
+}
+
+/// Runs the renderer's post processing stack over the tonemapped scene, ping-ponging between two
+/// textures, with the last effect writing to the surface
+pub struct PostProcessPass {
+    resources: Option<PostResources>,
+    steps: Vec<PostStep>,
+    surface_format: Option<wgpu::TextureFormat>,
+    // luts that couldn't be loaded, so the error is only reported once
+    failed_luts: HashSet<String>,
+}
+
+impl PostProcessPass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            steps: Vec::new(),
+            surface_format: None,
+            failed_luts: HashSet::new(),
+        }
+    }
+
+    fn create_resources(device: &wgpu::Device, queue: &wgpu::Queue) -> PostResources {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/post.wgsl"));
+
+        let texture_entry = |binding, sample_type| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Bind Group Layout"),
+            entries: &[
+                texture_entry(0, wgpu::TextureSampleType::Float { filterable: true }),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                texture_entry(2, wgpu::TextureSampleType::Depth),
+                texture_entry(3, wgpu::TextureSampleType::Float { filterable: true }),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // each slice holds red across and green down, for one value of blue
+        let size = IDENTITY_LUT_SIZE;
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+
+        for y in 0..size {
+            for x in 0..size * size {
+                let value = |step: u32| (step * 255 / (size - 1)) as u8;
+
+                data.extend_from_slice(&[value(x % size), value(y), value(x / size), 255]);
+            }
+        }
+
+        let identity_lut = Arc::new(Texture::from_rgba8(
+            device,
+            queue,
+            size * size,
+            size,
+            &data,
+            Self::lut_options(),
+            "Identity Lut",
+        ));
+
+        PostResources {
+            shader,
+            bind_group_layout,
+            sampler,
+            identity_lut,
+            pipelines: HashMap::new(),
+            uniform_buffers: Vec::new(),
+        }
+    }
+
+    /// Luts hold raw srgb values, and are sampled at exact texel positions
+    fn lut_options() -> texture::TextureOptions {
+        texture::TextureOptions {
+            colour_space: texture::ColourSpace::Linear,
+            generate_mipmaps: false,
+            sampler: texture::SamplerOptions {
+                address_mode: wgpu::AddressMode::ClampToEdge,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl GraphPass for PostProcessPass {
+    fn name(&self) -> &'static str {
+        "post_process"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::LDR, render_graph::DEPTH]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![
+            render_graph::POST_TEXTURES[0],
+            render_graph::POST_TEXTURES[1],
+            render_graph::SURFACE,
+        ]
+    }
+
+    fn prepare(
+        &mut self,
+        context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        if self.resources.is_none() {
+            self.resources = Some(Self::create_resources(
+                &renderer.device().lock().unwrap(),
+                &renderer.queue().lock().unwrap(),
+            ));
+        }
+
+        let surface_format = context.format(render_graph::SURFACE);
+        self.surface_format = Some(surface_format);
+
+        // gather the enabled effects as entry points and parameters, loading any luts they need
+        let mut effects: Vec<PendingEffect> = Vec::new();
+
+        for index in 0..renderer.post_stack().entries().len() {
+            let entry = &renderer.post_stack().entries()[index];
+
+            // debug views are shown as they were drawn
+            if !entry.enabled || renderer.view_mode().is_debug() {
+                continue;
+            }
+
+            let entry_point = entry.effect.entry_point();
+            let params = entry.effect.params();
+
+            let lut_path = match &entry.effect {
+                post::PostEffect::ColourGrading { lut, .. } => lut.clone(),
+                _ => None,
+            };
+
+            let lut = match lut_path {
+                Some(path) if !self.failed_luts.contains(&path) => {
+                    match renderer.load_texture(&path, Self::lut_options()) {
+                        Ok(lut) => Some(lut),
+                        Err(e) => {
+                            log::error(&e);
+                            self.failed_luts.insert(path);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            effects.push(PendingEffect {
+                entry_point,
+                params,
+                lut,
+            });
+        }
+
+        // a stack with nothing enabled still has to copy the scene to the surface
+        if effects.is_empty() {
+            effects.push(PendingEffect {
+                entry_point: "fs_copy",
+                params: [[0.0; 4]; 2],
+                lut: None,
+            });
+        }
+
+        let size = renderer.size();
+        let camera = renderer.camera();
+        let depth = [camera.near, camera.far, 0.0, 0.0];
+
+        let resources = self.resources.as_mut().unwrap();
+        let device = renderer.device().lock().unwrap();
+        let queue = renderer.queue().lock().unwrap();
+
+        self.steps.clear();
+
+        let last = effects.len() - 1;
+
+        for (index, effect) in effects.into_iter().enumerate() {
+            let PendingEffect {
+                entry_point,
+                params,
+                lut,
+            } = effect;
+
+            let format = if index == last {
+                surface_format
+            } else {
+                post::LDR_FORMAT
+            };
+
+            if !resources.pipelines.contains_key(&(entry_point, format)) {
+                let pipeline = render_graph::fullscreen_pipeline(
+                    &device,
+                    "Post Pipeline",
+                    &resources.shader,
+                    entry_point,
+                    &resources.bind_group_layout,
+                    format,
+                    None,
+                );
+
+                resources.pipelines.insert((entry_point, format), pipeline);
+            }
+
+            if resources.uniform_buffers.len() <= index {
+                resources
+                    .uniform_buffers
+                    .push(device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Post Uniform Buffer"),
+                        size: mem::size_of::<PostUniforms>() as wgpu::BufferAddress,
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }));
+            }
+
+            queue.write_buffer(
+                &resources.uniform_buffers[index],
+                0,
+                bytemuck::cast_slice(&[PostUniforms {
+                    params,
+                    output_target: [
+                        1.0 / size.width as f32,
+                        1.0 / size.height as f32,
+                        if format.is_srgb() { 0.0 } else { 1.0 },
+                        0.0,
+                    ],
+                    depth,
+                }]),
+            );
+
+            self.steps.push(PostStep {
+                entry_point,
+                uniform_buffer_index: index,
+                lut: lut.unwrap_or_else(|| resources.identity_lut.clone()),
+            });
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let (resources, surface_format) = match (&self.resources, self.surface_format) {
+            (Some(resources), Some(surface_format)) => (resources, surface_format),
+            _ => return,
+        };
+
+        let device = renderer.device().lock().unwrap();
+        let mut source = render_graph::LDR;
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let last = index == self.steps.len() - 1;
+
+            let (target, format) = if last {
+                (render_graph::SURFACE, surface_format)
+            } else {
+                (render_graph::POST_TEXTURES[index % 2], post::LDR_FORMAT)
+            };
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Bind Group"),
+                layout: &resources.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(context.view(source)),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&resources.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(
+                            context.view(render_graph::DEPTH),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&step.lut.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: resources.uniform_buffers[step.uniform_buffer_index]
+                            .as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut render_pass = render_graph::begin_fullscreen_pass(
+                context.encoder,
+                "Post Process Pass",
+                context.view(target),
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            );
+
+            render_pass.set_pipeline(&resources.pipelines[&(step.entry_point, format)]);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+
+            source = target;
+        }
+    }
+}
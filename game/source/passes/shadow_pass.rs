@@ -0,0 +1,95 @@
+use crate::core::render_graph::{self, GraphPass, PassContext};
+use crate::core::{game, renderer, shadows};
+
+/// Renders depth from each shadow casting light's point of view into the shadow map layers
+pub struct ShadowPass {}
+
+impl GraphPass for ShadowPass {
+    fn name(&self) -> &'static str {
+        "shadow"
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::SHADOW_MAPS]
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let shadow_maps = renderer.shadow_maps();
+
+        for layer in 0..shadow_maps.active_count {
+            let mut shadow_pass = context
+                .encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Shadow Pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &shadow_maps.layer_views[layer],
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+
+            shadow_pass.set_bind_group(
+                0,
+                &shadow_maps.pass_bind_group,
+                &[
+                    (shadows::ShadowMaps::pass_aligned_size() * layer as wgpu::BufferAddress)
+                        as u32,
+                ],
+            );
+
+            for draw in context.draws.shadow_casters.iter() {
+                if let Some(game::ComponentEnum::Mesh(mesh)) =
+                    world.get_entity_component_by_type(draw.entity_id, game::ComponentType::Mesh)
+                {
+                    if !mesh.casts_shadows {
+                        continue;
+                    }
+
+                    // cut out materials are alpha tested, everything else only needs depth
+                    let cutout = match world
+                        .get_entity_component_by_type(draw.entity_id, game::ComponentType::Material)
+                    {
+                        Some(game::ComponentEnum::Material(material))
+                            if material.alpha_cutoff.is_some() =>
+                        {
+                            material.bind_group.as_ref()
+                        }
+                        _ => None,
+                    };
+
+                    match cutout {
+                        Some(bind_group) => {
+                            shadow_pass.set_pipeline(&shadow_maps.cutout_pipeline);
+                            shadow_pass.set_bind_group(2, bind_group, &[]);
+                        }
+                        None => shadow_pass.set_pipeline(&shadow_maps.pipeline),
+                    }
+
+                    if let Some((vertex_buffer, index_buffer, num_indices)) = mesh.buffers(draw.lod)
+                    {
+                        shadow_pass.set_bind_group(
+                            1,
+                            renderer.transform_bind_group(),
+                            &[draw.transform_offset],
+                        );
+                        shadow_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                        shadow_pass
+                            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                        shadow_pass.draw_indexed(0..num_indices, 0, 0..1);
+                    }
+                }
+            }
+        }
+    }
+}
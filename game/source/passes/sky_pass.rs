@@ -0,0 +1,309 @@
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::sky::Sky;
+use crate::core::texture::Texture;
+use crate::core::{game, hdr, renderer};
+use std::mem;
+use std::sync::Arc;
+
+/// uniform layout for the sky shader - must match the Sky struct in sky.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniforms {
+    inverse_view_projection: [[f32; 4]; 4],
+    // mode, intensity, gradient exponent
+    settings: [f32; 4],
+    zenith: [f32; 4],
+    horizon: [f32; 4],
+    ground: [f32; 4],
+    // xyz direction towards the sun, w cosine of its angular radius
+    sun_direction: [f32; 4],
+    sun_colour: [f32; 4],
+}
+
+struct SkyResources {
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    // bound when the sky isn't a cubemap
+    fallback: Arc<Texture>,
+    // the cubemap the bind group was created with
+    bound: Arc<Texture>,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    // sample count the pipeline was created for
+    sample_count: u32,
+}
+
+/// Draws the renderer's sky behind the opaque meshes, wherever they left the depth at the far plane
+pub struct SkyPass {
+    resources: Option<SkyResources>,
+}
+
+impl SkyPass {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    fn create_resources(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sample_count: u32,
+    ) -> SkyResources {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/sky.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sky Uniform Buffer"),
+            size: mem::size_of::<SkyUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let fallback = Arc::new(Texture::solid_cubemap(device, queue, [0, 0, 0, 255]));
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &uniform_buffer, &fallback);
+        let pipeline = Self::create_pipeline(device, &shader, &bind_group_layout, sample_count);
+
+        SkyResources {
+            shader,
+            bind_group_layout,
+            uniform_buffer,
+            bound: fallback.clone(),
+            fallback,
+            bind_group,
+            pipeline,
+            sample_count,
+        }
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        cubemap: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&cubemap.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&cubemap.sampler),
+                },
+            ],
+        })
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sky Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sky Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: hdr::HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn uniforms(sky: &Sky, inverse_view_projection: glam::Mat4) -> SkyUniforms {
+        let rgb = |colour: [f32; 3]| [colour[0], colour[1], colour[2], 1.0];
+
+        let mut uniforms = SkyUniforms {
+            inverse_view_projection: inverse_view_projection.to_cols_array_2d(),
+            settings: [sky.mode() as f32, 1.0, 1.0, 0.0],
+            zenith: [0.0; 4],
+            horizon: [0.0; 4],
+            ground: [0.0; 4],
+            sun_direction: [0.0, 1.0, 0.0, 2.0],
+            sun_colour: [0.0; 4],
+        };
+
+        match sky {
+            Sky::Colour(colour) => uniforms.zenith = rgb(*colour),
+            Sky::Gradient(gradient) => {
+                uniforms.settings[2] = gradient.exponent;
+                uniforms.zenith = rgb(gradient.zenith);
+                uniforms.horizon = rgb(gradient.horizon);
+                uniforms.ground = rgb(gradient.ground);
+                uniforms.sun_colour = rgb(gradient.sun_colour);
+
+                let [x, y, z] = gradient.sun_direction;
+                let edge = if gradient.sun_size > 0.0 {
+                    gradient.sun_size.cos()
+                } else {
+                    2.0
+                };
+
+                uniforms.sun_direction = [x, y, z, edge];
+            }
+            Sky::Cubemap { intensity, .. } => uniforms.settings[1] = *intensity,
+        }
+
+        uniforms
+    }
+}
+
+impl GraphPass for SkyPass {
+    fn name(&self) -> &'static str {
+        "sky"
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        render_graph::SCENE_OUTPUTS.to_vec()
+    }
+
+    fn prepare(
+        &mut self,
+        _context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let device = renderer.device().lock().unwrap();
+        let queue = renderer.queue().lock().unwrap();
+        let sample_count = renderer.msaa_samples();
+
+        let resources = self
+            .resources
+            .get_or_insert_with(|| Self::create_resources(&device, &queue, sample_count));
+
+        if resources.sample_count != sample_count {
+            resources.pipeline = Self::create_pipeline(
+                &device,
+                &resources.shader,
+                &resources.bind_group_layout,
+                sample_count,
+            );
+            resources.sample_count = sample_count;
+        }
+
+        let sky = renderer.sky();
+
+        let cubemap = match sky {
+            Sky::Cubemap { texture, .. } => texture,
+            _ => &resources.fallback,
+        };
+
+        if !Arc::ptr_eq(cubemap, &resources.bound) {
+            resources.bind_group = Self::create_bind_group(
+                &device,
+                &resources.bind_group_layout,
+                &resources.uniform_buffer,
+                cubemap,
+            );
+            resources.bound = cubemap.clone();
+        }
+
+        // the sky is infinitely far away, so the camera moving doesn't move it
+        let camera = renderer.camera();
+        let mut view = camera.view_matrix();
+        view.w_axis = glam::Vec4::W;
+
+        let view_projection = camera.projection_matrix(renderer.aspect_ratio()) * view;
+
+        queue.write_buffer(
+            &resources.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[Self::uniforms(sky, view_projection.inverse())]),
+        );
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &self.resources {
+            Some(resources) => resources,
+            None => return,
+        };
+
+        let mut render_pass = render_graph::begin_scene_pass(
+            context,
+            renderer,
+            "Sky Pass",
+            wgpu::Color::BLACK,
+            false,
+        );
+
+        render_pass.set_pipeline(&resources.pipeline);
+        render_pass.set_bind_group(0, &resources.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
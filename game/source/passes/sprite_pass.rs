@@ -0,0 +1,547 @@
+use crate::components::{sprite_component, tilemap_component, transform_component};
+use crate::core::game::{self, ComponentEnum, ComponentType, EntityId};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::renderer;
+use crate::core::texture::Texture;
+use crate::core::tilemap::SpriteVertex;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+
+/// uniform layout for the sprite shader - must match the Sprites struct in sprite.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteUniforms {
+    view_projection: [[f32; 4]; 4],
+    // x 1.0 if the output needs gamma encoding
+    settings: [f32; 4],
+}
+
+/// something to draw, sorted with the others by z order
+enum SpriteItem<'a> {
+    Sprite(
+        &'a sprite_component::SpriteComponent,
+        &'a transform_component::TransformComponent,
+    ),
+    Tilemap(&'a tilemap_component::TilemapComponent),
+}
+
+enum SpriteBatch {
+    // a run of sprites sharing a texture, drawn with one call
+    Sprites {
+        texture: String,
+        vertices: std::ops::Range<u32>,
+    },
+    // a tilemap's chunks within the camera's view, each in its own vertex buffer
+    Tilemap {
+        entity_id: EntityId,
+        texture: String,
+        chunks: Vec<usize>,
+    },
+}
+
+struct SpriteResources {
+    shader: wgpu::ShaderModule,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    // by target format
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    // by texture path, along with the texture they were created for
+    bind_groups: HashMap<String, (Arc<Texture>, wgpu::BindGroup)>,
+    vertex_buffer: wgpu::Buffer,
+    // in vertices
+    capacity: usize,
+}
+
+/// Draws the sprites and tilemaps through the renderer's 2d camera, over the finished scene and
+/// under the text and ui. they're sorted by z order - tilemaps first, then sprites by texture, so
+/// each texture is bound once per layer.
+pub struct SpritePass {
+    resources: Option<SpriteResources>,
+    batches: Vec<SpriteBatch>,
+    surface_format: Option<wgpu::TextureFormat>,
+}
+
+impl SpritePass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            batches: Vec::new(),
+            surface_format: None,
+        }
+    }
+
+    fn create_resources(device: &wgpu::Device) -> SpriteResources {
+        let shader =
+            renderer::Renderer::load_shader(device, include_str!("../shaders/sprite.wgsl"));
+
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Sprite Uniform Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Sprite Texture Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Uniform Buffer"),
+            size: mem::size_of::<SpriteUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Uniform Bind Group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let capacity = 6 * 256;
+
+        SpriteResources {
+            shader,
+            uniform_bind_group_layout,
+            texture_bind_group_layout,
+            uniform_buffer,
+            uniform_bind_group,
+            pipelines: HashMap::new(),
+            bind_groups: HashMap::new(),
+            vertex_buffer: Self::create_vertex_buffer(device, capacity),
+            capacity,
+        }
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Vertex Buffer"),
+            size: (capacity * mem::size_of::<SpriteVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        resources: &SpriteResources,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[
+                &resources.uniform_bind_group_layout,
+                &resources.texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &resources.shader,
+                entry_point: Some("vs_main"),
+                buffers: &[SpriteVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &resources.shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        resources: &SpriteResources,
+        texture: &Texture,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Texture Bind Group"),
+            layout: &resources.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        })
+    }
+
+    /// Create a bind group for a texture, unless there's one for the same texture already
+    fn bind_texture(
+        device: &wgpu::Device,
+        resources: &mut SpriteResources,
+        path: &str,
+        texture: &Arc<Texture>,
+    ) {
+        let bound = match resources.bind_groups.get(path) {
+            Some((bound, _)) => Arc::ptr_eq(bound, texture),
+            None => false,
+        };
+
+        if !bound {
+            let bind_group = Self::create_bind_group(device, resources, texture);
+            resources
+                .bind_groups
+                .insert(path.to_string(), (texture.clone(), bind_group));
+        }
+    }
+
+    /// Quads for a sprite, continuing the last batch if it uses the same texture
+    fn push_sprite(
+        &mut self,
+        vertices: &mut Vec<SpriteVertex>,
+        sprite: &sprite_component::SpriteComponent,
+        transform: &transform_component::TransformComponent,
+        texture: &Texture,
+    ) {
+        let [x, y, width, height] = sprite
+            .region
+            .unwrap_or([0, 0, texture.width, texture.height]);
+
+        let texture_size = [texture.width as f32, texture.height as f32];
+        let mut uv_min = [x as f32 / texture_size[0], y as f32 / texture_size[1]];
+        let mut uv_max = [
+            (x + width) as f32 / texture_size[0],
+            (y + height) as f32 / texture_size[1],
+        ];
+
+        if sprite.flip_x {
+            mem::swap(&mut uv_min[0], &mut uv_max[0]);
+        }
+
+        if sprite.flip_y {
+            mem::swap(&mut uv_min[1], &mut uv_max[1]);
+        }
+
+        let pixels_per_unit = sprite.pixels_per_unit.max(f32::EPSILON);
+        let size = glam::Vec2::new(
+            width as f32 / pixels_per_unit * transform.scale[0],
+            height as f32 / pixels_per_unit * transform.scale[1],
+        );
+        let rotation = glam::Mat2::from_angle(transform.rotation[2]);
+        let position = glam::Vec2::new(transform.position[0], transform.position[1]);
+
+        let start = vertices.len() as u32;
+
+        // texture rows run downwards, world y runs up
+        for (corner_x, corner_y) in [(0, 0), (1, 0), (0, 1), (0, 1), (1, 0), (1, 1)] {
+            let local = glam::Vec2::new(
+                (corner_x as f32 - sprite.anchor[0]) * size.x,
+                (corner_y as f32 - sprite.anchor[1]) * size.y,
+            );
+
+            vertices.push(SpriteVertex {
+                position: (position + rotation * local).to_array(),
+                uv: [
+                    if corner_x == 0 { uv_min[0] } else { uv_max[0] },
+                    if corner_y == 0 { uv_max[1] } else { uv_min[1] },
+                ],
+                colour: sprite.tint,
+            });
+        }
+
+        let end = vertices.len() as u32;
+
+        match self.batches.last_mut() {
+            Some(SpriteBatch::Sprites { texture, vertices }) if *texture == sprite.texture => {
+                vertices.end = end
+            }
+            _ => self.batches.push(SpriteBatch::Sprites {
+                texture: sprite.texture.clone(),
+                vertices: start..end,
+            }),
+        }
+    }
+}
+
+impl GraphPass for SpritePass {
+    fn name(&self) -> &'static str {
+        "sprites"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::SURFACE]
+    }
+
+    fn prepare(
+        &mut self,
+        context: &PrepareContext,
+        world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        self.batches.clear();
+
+        let mut items = Vec::new();
+
+        for (entity_id, component) in world.get_components_by_type(ComponentType::Sprite) {
+            if let ComponentEnum::Sprite(sprite) = component {
+                let transform =
+                    match world.get_entity_component_by_type(entity_id, ComponentType::Transform) {
+                        Some(ComponentEnum::Transform(transform)) => transform,
+                        _ => continue,
+                    };
+
+                if sprite.loaded_texture.is_some() {
+                    items.push((
+                        (sprite.z_order, 1, &sprite.texture, entity_id),
+                        SpriteItem::Sprite(sprite, transform),
+                    ));
+                }
+            }
+        }
+
+        for (entity_id, component) in world.get_components_by_type(ComponentType::Tilemap) {
+            if let ComponentEnum::Tilemap(tilemap) = component {
+                if tilemap.loaded_texture.is_some() {
+                    items.push((
+                        (tilemap.z_order, 0, &tilemap.tileset, entity_id),
+                        SpriteItem::Tilemap(tilemap),
+                    ));
+                }
+            }
+        }
+
+        if items.is_empty() {
+            return;
+        }
+
+        // later items are drawn over earlier ones
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let size = renderer.size();
+        let view_projection = renderer
+            .camera_2d()
+            .view_projection_matrix(size.width as f32, size.height as f32);
+
+        // the world space rectangle the camera sees, for culling tilemap chunks
+        let inverse = view_projection.inverse();
+        let mut view_min = glam::Vec2::splat(f32::MAX);
+        let mut view_max = glam::Vec2::splat(f32::MIN);
+
+        for corner in [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]] {
+            let point = inverse
+                .project_point3(glam::Vec3::new(corner[0], corner[1], 0.0))
+                .truncate();
+            view_min = view_min.min(point);
+            view_max = view_max.max(point);
+        }
+
+        let device = renderer.device().lock().unwrap();
+        let queue = renderer.queue().lock().unwrap();
+
+        let mut resources = match self.resources.take() {
+            Some(resources) => resources,
+            None => Self::create_resources(&device),
+        };
+
+        let mut vertices = Vec::new();
+
+        for ((_, _, path, entity_id), item) in items {
+            match item {
+                SpriteItem::Sprite(sprite, transform) => {
+                    let texture = match &sprite.loaded_texture {
+                        Some(texture) => texture,
+                        None => continue,
+                    };
+
+                    Self::bind_texture(&device, &mut resources, path, texture);
+                    self.push_sprite(&mut vertices, sprite, transform, texture);
+                }
+                SpriteItem::Tilemap(tilemap) => {
+                    let texture = match &tilemap.loaded_texture {
+                        Some(texture) => texture,
+                        None => continue,
+                    };
+
+                    let chunks: Vec<usize> = tilemap
+                        .chunks
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, chunk)| {
+                            let [min, max] = chunk.bounds;
+
+                            chunk.vertex_count > 0
+                                && !chunk.dirty
+                                && max[0] >= view_min.x
+                                && min[0] <= view_max.x
+                                && max[1] >= view_min.y
+                                && min[1] <= view_max.y
+                        })
+                        .map(|(index, _)| index)
+                        .collect();
+
+                    if chunks.is_empty() {
+                        continue;
+                    }
+
+                    Self::bind_texture(&device, &mut resources, path, texture);
+                    self.batches.push(SpriteBatch::Tilemap {
+                        entity_id,
+                        texture: path.clone(),
+                        chunks,
+                    });
+                }
+            }
+        }
+
+        let surface_format = context.format(render_graph::SURFACE);
+        self.surface_format = Some(surface_format);
+
+        if !resources.pipelines.contains_key(&surface_format) {
+            let pipeline = Self::create_pipeline(&device, &resources, surface_format);
+            resources.pipelines.insert(surface_format, pipeline);
+        }
+
+        if vertices.len() > resources.capacity {
+            resources.capacity = vertices.len().next_power_of_two();
+            resources.vertex_buffer = Self::create_vertex_buffer(&device, resources.capacity);
+        }
+
+        queue.write_buffer(&resources.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+
+        queue.write_buffer(
+            &resources.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[SpriteUniforms {
+                view_projection: view_projection.to_cols_array_2d(),
+                settings: [
+                    if surface_format.is_srgb() { 0.0 } else { 1.0 },
+                    0.0,
+                    0.0,
+                    0.0,
+                ],
+            }]),
+        );
+
+        self.resources = Some(resources);
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        world: &game::World,
+        _renderer: &renderer::Renderer,
+    ) {
+        let (resources, surface_format) = match (&self.resources, self.surface_format) {
+            (Some(resources), Some(surface_format)) if !self.batches.is_empty() => {
+                (resources, surface_format)
+            }
+            _ => return,
+        };
+
+        let mut render_pass = context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sprite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: context.view(render_graph::SURFACE),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: context.colour_load(render_graph::SURFACE, wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+        render_pass.set_pipeline(&resources.pipelines[&surface_format]);
+        render_pass.set_bind_group(0, &resources.uniform_bind_group, &[]);
+
+        for batch in self.batches.iter() {
+            match batch {
+                SpriteBatch::Sprites { texture, vertices } => {
+                    if let Some((_, bind_group)) = resources.bind_groups.get(texture) {
+                        render_pass.set_bind_group(1, bind_group, &[]);
+                        render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+                        render_pass.draw(vertices.clone(), 0..1);
+                    }
+                }
+                SpriteBatch::Tilemap {
+                    entity_id,
+                    texture,
+                    chunks,
+                } => {
+                    let tilemap = match world
+                        .get_entity_component_by_type(*entity_id, ComponentType::Tilemap)
+                    {
+                        Some(ComponentEnum::Tilemap(tilemap)) => tilemap,
+                        _ => continue,
+                    };
+
+                    let bind_group = match resources.bind_groups.get(texture) {
+                        Some((_, bind_group)) => bind_group,
+                        None => continue,
+                    };
+
+                    render_pass.set_bind_group(1, bind_group, &[]);
+
+                    for &index in chunks {
+                        let chunk = &tilemap.chunks[index];
+
+                        if let Some(vertex_buffer) = &chunk.vertex_buffer {
+                            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                            render_pass.draw(0..chunk.vertex_count, 0..1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::core::game::{self, ComponentEnum, ComponentType};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::renderer;
+use crate::core::text::{FontRendering, LaidOutGlyph};
+use std::collections::HashMap;
+use std::mem;
+
+/// a glyph corner as laid out in the text vertex buffer, @see text.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextVertex {
+    anchor: [f32; 3],
+    offset: [f32; 2],
+    uv: [f32; 2],
+    colour: [f32; 4],
+}
+
+impl TextVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x2,
+            2 => Float32x2,
+            3 => Float32x4
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// uniform layout for the text shader - must match the Text struct in text.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextUniforms {
+    // surface width, height, 1.0 if the output needs gamma encoding
+    screen: [f32; 4],
+}
+
+/// vertices drawn with one font's atlas, from one of the vertex lists
+struct TextBatch {
+    font: usize,
+    world: bool,
+    vertices: std::ops::Range<u32>,
+}
+
+struct TextResources {
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    // keyed by whether it draws labels, the font rendering, and the target format
+    pipelines: HashMap<(bool, FontRendering, wgpu::TextureFormat), wgpu::RenderPipeline>,
+    // by font
+    bind_groups: HashMap<usize, wgpu::BindGroup>,
+    vertex_buffer: wgpu::Buffer,
+    // in vertices
+    capacity: usize,
+}
+
+/// Draws TextLabelComponents in the world, depth tested against the scene, then the renderer's
+/// screen text over everything - after post processing so text stays crisp
+pub struct TextPass {
+    resources: Option<TextResources>,
+    batches: Vec<TextBatch>,
+    surface_format: Option<wgpu::TextureFormat>,
+}
+
+impl TextPass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            batches: Vec::new(),
+            surface_format: None,
+        }
+    }
+
+    fn create_resources(device: &wgpu::Device) -> TextResources {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/text.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Text Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Uniform Buffer"),
+            size: mem::size_of::<TextUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let capacity = 6 * 256;
+
+        TextResources {
+            shader,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            pipelines: HashMap::new(),
+            bind_groups: HashMap::new(),
+            vertex_buffer: Self::create_vertex_buffer(device, capacity),
+            capacity,
+        }
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Vertex Buffer"),
+            size: (capacity * mem::size_of::<TextVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        resources: &TextResources,
+        global_bind_group_layout: &wgpu::BindGroupLayout,
+        world: bool,
+        rendering: FontRendering,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[global_bind_group_layout, &resources.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // labels are hidden behind the scene, screen text is drawn over it
+        let depth_compare = if world {
+            wgpu::CompareFunction::LessEqual
+        } else {
+            wgpu::CompareFunction::Always
+        };
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &resources.shader,
+                entry_point: Some(if world { "vs_world" } else { "vs_screen" }),
+                buffers: &[TextVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &resources.shader,
+                entry_point: Some(match rendering {
+                    FontRendering::Bitmap => "fs_bitmap",
+                    FontRendering::Sdf => "fs_sdf",
+                }),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Two triangles for each glyph
+    fn push_quads(
+        vertices: &mut Vec<TextVertex>,
+        glyphs: &[LaidOutGlyph],
+        colour: [f32; 4],
+        corner: impl Fn([f32; 2]) -> ([f32; 3], [f32; 2]),
+    ) {
+        for glyph in glyphs {
+            for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 0), (0, 1), (1, 1)] {
+                let position = [
+                    if x == 0 { glyph.min[0] } else { glyph.max[0] },
+                    if y == 0 { glyph.min[1] } else { glyph.max[1] },
+                ];
+                let (anchor, offset) = corner(position);
+
+                vertices.push(TextVertex {
+                    anchor,
+                    offset,
+                    uv: [
+                        if x == 0 {
+                            glyph.uv_min[0]
+                        } else {
+                            glyph.uv_max[0]
+                        },
+                        if y == 0 {
+                            glyph.uv_min[1]
+                        } else {
+                            glyph.uv_max[1]
+                        },
+                    ],
+                    colour,
+                });
+            }
+        }
+    }
+}
+
+impl GraphPass for TextPass {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::DEPTH]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::SURFACE]
+    }
+
+    fn prepare(
+        &mut self,
+        context: &PrepareContext,
+        world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let screen_text = renderer.text_mut().take_screen_text();
+
+        // lay out every label and screen text, grouped by font
+        let mut world_vertices: HashMap<usize, Vec<TextVertex>> = HashMap::new();
+        let mut screen_vertices: HashMap<usize, Vec<TextVertex>> = HashMap::new();
+
+        for entity_id in world
+            .get_entities_with_components(&[ComponentType::TextLabel, ComponentType::Transform])
+        {
+            let label = world.get_entity_component_by_type(entity_id, ComponentType::TextLabel);
+            let transform = world.get_entity_component_by_type(entity_id, ComponentType::Transform);
+
+            if let (
+                Some(ComponentEnum::TextLabel(label)),
+                Some(ComponentEnum::Transform(transform)),
+            ) = (label, transform)
+            {
+                let font = match renderer.text_mut().font_mut(label.style.font) {
+                    Some(font) => font,
+                    None => continue,
+                };
+
+                let glyphs = font.layout(&label.text, &label.style);
+
+                // the bottom of the text sits on the anchor
+                let bottom = glyphs.iter().map(|glyph| glyph.max[1]).fold(0.0, f32::max);
+
+                let anchor = [
+                    transform.position[0] + label.offset[0],
+                    transform.position[1] + label.offset[1],
+                    transform.position[2] + label.offset[2],
+                ];
+                let scale = label.scale;
+
+                Self::push_quads(
+                    world_vertices.entry(label.style.font).or_default(),
+                    &glyphs,
+                    label.style.colour,
+                    |[x, y]| (anchor, [x * scale, (bottom - y) * scale]),
+                );
+            }
+        }
+
+        for screen_text in screen_text.iter() {
+            let font = match renderer.text_mut().font_mut(screen_text.style.font) {
+                Some(font) => font,
+                None => continue,
+            };
+
+            let glyphs = font.layout(&screen_text.text, &screen_text.style);
+            let [anchor_x, anchor_y] = screen_text.position;
+
+            Self::push_quads(
+                screen_vertices.entry(screen_text.style.font).or_default(),
+                &glyphs,
+                screen_text.style.colour,
+                |[x, y]| ([anchor_x + x, anchor_y + y, 0.0], [0.0, 0.0]),
+            );
+        }
+
+        self.batches.clear();
+
+        if world_vertices.is_empty() && screen_vertices.is_empty() {
+            return;
+        }
+
+        let device = renderer.device().clone();
+        let device = device.lock().unwrap();
+        let queue = renderer.queue().clone();
+        let queue = queue.lock().unwrap();
+
+        let resources = self
+            .resources
+            .get_or_insert_with(|| Self::create_resources(&device));
+
+        let surface_format = context.format(render_graph::SURFACE);
+        self.surface_format = Some(surface_format);
+
+        renderer.text_mut().upload(&device, &queue);
+
+        // each font's atlas is created once, so its bind group can be kept
+        for (index, font) in renderer.text().fonts().iter().enumerate() {
+            if let Some((_, view)) = &font.texture {
+                if !resources.bind_groups.contains_key(&index) {
+                    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Text Bind Group"),
+                        layout: &resources.bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(view),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Sampler(&resources.sampler),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: resources.uniform_buffer.as_entire_binding(),
+                            },
+                        ],
+                    });
+
+                    resources.bind_groups.insert(index, bind_group);
+                }
+            }
+        }
+
+        // labels first, so screen text is drawn over them
+        let mut vertices = Vec::new();
+
+        for (world, font_vertices) in [(true, world_vertices), (false, screen_vertices)] {
+            let mut fonts: Vec<(usize, Vec<TextVertex>)> = font_vertices.into_iter().collect();
+            fonts.sort_by_key(|(font, _)| *font);
+
+            for (font, font_vertices) in fonts {
+                let start = vertices.len() as u32;
+                vertices.extend(font_vertices);
+
+                self.batches.push(TextBatch {
+                    font,
+                    world,
+                    vertices: start..vertices.len() as u32,
+                });
+
+                let rendering = renderer.text().fonts()[font].rendering;
+                let key = (world, rendering, surface_format);
+
+                if !resources.pipelines.contains_key(&key) {
+                    let pipeline = Self::create_pipeline(
+                        &device,
+                        resources,
+                        renderer.global_bind_group_layout(),
+                        world,
+                        rendering,
+                        surface_format,
+                    );
+
+                    resources.pipelines.insert(key, pipeline);
+                }
+            }
+        }
+
+        if vertices.len() > resources.capacity {
+            resources.capacity = vertices.len().next_power_of_two();
+            resources.vertex_buffer = Self::create_vertex_buffer(&device, resources.capacity);
+        }
+
+        queue.write_buffer(&resources.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+
+        let size = renderer.size();
+
+        queue.write_buffer(
+            &resources.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[TextUniforms {
+                screen: [
+                    size.width as f32,
+                    size.height as f32,
+                    if surface_format.is_srgb() { 0.0 } else { 1.0 },
+                    0.0,
+                ],
+            }]),
+        );
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let (resources, surface_format) = match (&self.resources, self.surface_format) {
+            (Some(resources), Some(surface_format)) if !self.batches.is_empty() => {
+                (resources, surface_format)
+            }
+            _ => return,
+        };
+
+        let mut render_pass = context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Text Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: context.view(render_graph::SURFACE),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: context.colour_load(render_graph::SURFACE, wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: context.view(render_graph::DEPTH),
+                    depth_ops: Some(wgpu::Operations {
+                        load: context.depth_load(render_graph::DEPTH),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+        render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+
+        let fonts = renderer.text().fonts();
+
+        for batch in self.batches.iter() {
+            let font = &fonts[batch.font];
+
+            if let Some(bind_group) = resources.bind_groups.get(&batch.font) {
+                let key = (batch.world, font.rendering, surface_format);
+
+                render_pass.set_pipeline(&resources.pipelines[&key]);
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.draw(batch.vertices.clone(), 0..1);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::core::hdr::{TonemapUniforms, Tonemapper};
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::{game, renderer};
+use std::mem;
+
+struct TonemapResources {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    // format the pipeline was created for
+    format: wgpu::TextureFormat,
+    // over the graph's textures, and the graph generation it was created in
+    bind_group: Option<(u64, wgpu::BindGroup)>,
+}
+
+/// Applies exposure and the tonemapping curve to the hdr scene and bloom, writing the result to
+/// a displayable target
+pub struct TonemapPass {
+    // target the result is written to, the surface unless post processing follows
+    pub target: &'static str,
+    resources: Option<TonemapResources>,
+}
+
+impl TonemapPass {
+    pub fn new(target: &'static str) -> Self {
+        Self {
+            target,
+            resources: None,
+        }
+    }
+
+    fn create_resources(device: &wgpu::Device, format: wgpu::TextureFormat) -> TonemapResources {
+        let shader =
+            renderer::Renderer::load_shader(device, include_str!("../shaders/tonemap.wgsl"));
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Tonemap Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = render_graph::fullscreen_pipeline(
+            device,
+            "Tonemap Pipeline",
+            &shader,
+            "fs_main",
+            &bind_group_layout,
+            format,
+            None,
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Tonemap Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tonemap Uniform Buffer"),
+            size: mem::size_of::<TonemapUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        TonemapResources {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            format,
+            bind_group: None,
+        }
+    }
+}
+
+impl GraphPass for TonemapPass {
+    fn name(&self) -> &'static str {
+        "tonemap"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![render_graph::HDR, render_graph::BLOOM_TEXTURES[0]]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![self.target]
+    }
+
+    fn prepare(
+        &mut self,
+        context: &PrepareContext,
+        _world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let format = context.format(self.target);
+
+        if self.resources.as_ref().map(|resources| resources.format) != Some(format) {
+            self.resources = Some(Self::create_resources(
+                &renderer.device().lock().unwrap(),
+                format,
+            ));
+        }
+
+        let settings = renderer.hdr_settings();
+
+        // debug views are shown as they were drawn
+        let debug_view = renderer.view_mode().is_debug();
+
+        if let Some(resources) = &self.resources {
+            renderer.queue().lock().unwrap().write_buffer(
+                &resources.uniform_buffer,
+                0,
+                bytemuck::cast_slice(&[TonemapUniforms {
+                    settings: [
+                        if debug_view { 1.0 } else { settings.exposure },
+                        if settings.bloom && !debug_view {
+                            settings.bloom_intensity
+                        } else {
+                            0.0
+                        },
+                        if format.is_srgb() { 0.0 } else { 1.0 },
+                        0.0,
+                    ],
+                    tonemapper: if debug_view {
+                        Tonemapper::None.index()
+                    } else {
+                        settings.tonemapper.index()
+                    },
+                    _padding: [0; 3],
+                }]),
+            );
+        }
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        renderer: &renderer::Renderer,
+    ) {
+        let resources = match &mut self.resources {
+            Some(resources) => resources,
+            None => return,
+        };
+
+        let generation = context.generation();
+
+        if resources.bind_group.as_ref().map(|(created, _)| *created) != Some(generation) {
+            let bind_group =
+                renderer
+                    .device()
+                    .lock()
+                    .unwrap()
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Tonemap Bind Group"),
+                        layout: &resources.bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(
+                                    context.view(render_graph::HDR),
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::TextureView(
+                                    context.view(render_graph::BLOOM_TEXTURES[0]),
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: wgpu::BindingResource::Sampler(&resources.sampler),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 3,
+                                resource: resources.uniform_buffer.as_entire_binding(),
+                            },
+                        ],
+                    });
+
+            resources.bind_group = Some((generation, bind_group));
+        }
+
+        let bind_group = match &resources.bind_group {
+            Some((_, bind_group)) => bind_group,
+            None => return,
+        };
+
+        let mut render_pass = render_graph::begin_fullscreen_pass(
+            context.encoder,
+            "Tonemap Pass",
+            context.view(self.target),
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        );
+
+        render_pass.set_pipeline(&resources.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
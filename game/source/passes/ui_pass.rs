@@ -0,0 +1,638 @@
+use crate::components::ui_node_component::UiContent;
+use crate::core::game::{self, ComponentEnum, ComponentType};
+use crate::core::profiler::ProfileTrack;
+use crate::core::render_graph::{self, GraphPass, PassContext, PrepareContext};
+use crate::core::renderer;
+use crate::core::text::{FontRendering, TextStyle};
+use crate::core::texture::{ColourSpace, Texture};
+use crate::core::ui;
+use std::collections::HashMap;
+use std::mem;
+
+// how the fragment shader treats the sampled texel, @see ui.wgsl
+const MODE_COLOUR: u32 = 0;
+const MODE_BITMAP: u32 = 1;
+const MODE_SDF: u32 = 2;
+
+// profiler overlay layout, in pixels
+const OVERLAY_POSITION: [f32; 2] = [8.0, 8.0];
+const OVERLAY_PADDING: f32 = 6.0;
+const OVERLAY_ROW_HEIGHT: f32 = 16.0;
+const OVERLAY_FONT_SIZE: f32 = 13.0;
+const OVERLAY_LABEL_WIDTH: f32 = 240.0;
+const OVERLAY_BAR_WIDTH: f32 = 160.0;
+// a full bar is a 60hz frame
+const OVERLAY_BUDGET_MS: f64 = 1000.0 / 60.0;
+
+/// a quad corner as laid out in the ui vertex buffer, @see ui.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UiVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    colour: [f32; 4],
+    mode: u32,
+}
+
+impl UiVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x2,
+            1 => Float32x2,
+            2 => Float32x4,
+            3 => Uint32
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<UiVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// uniform layout for the ui shader - must match the Ui struct in ui.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UiUniforms {
+    // surface width, height, 1.0 if the output needs gamma encoding
+    screen: [f32; 4],
+}
+
+/// the texture a run of quads samples
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum UiBinding {
+    // panels and buttons
+    White,
+    Image(String),
+    Font(usize),
+}
+
+struct UiBatch {
+    binding: UiBinding,
+    vertices: std::ops::Range<u32>,
+}
+
+struct UiResources {
+    shader: wgpu::ShaderModule,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    white: Texture,
+    atlas_sampler: wgpu::Sampler,
+    // by target format
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    bind_groups: HashMap<UiBinding, wgpu::BindGroup>,
+    vertex_buffer: wgpu::Buffer,
+    // in vertices
+    capacity: usize,
+}
+
+/// Draws the ui nodes over everything else, in the order from ui::draw_order - text included, so
+/// nodes on top cover the text below them, then the profiler overlay if it's shown
+pub struct UiPass {
+    resources: Option<UiResources>,
+    batches: Vec<UiBatch>,
+    surface_format: Option<wgpu::TextureFormat>,
+}
+
+impl UiPass {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            batches: Vec::new(),
+            surface_format: None,
+        }
+    }
+
+    fn create_resources(device: &wgpu::Device, queue: &wgpu::Queue) -> UiResources {
+        let shader = renderer::Renderer::load_shader(device, include_str!("../shaders/ui.wgsl"));
+
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Ui Uniform Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Ui Texture Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ui Uniform Buffer"),
+            size: mem::size_of::<UiUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Ui Uniform Bind Group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let atlas_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Ui Atlas Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let capacity = 6 * 256;
+
+        UiResources {
+            shader,
+            uniform_bind_group_layout,
+            texture_bind_group_layout,
+            uniform_buffer,
+            uniform_bind_group,
+            white: Texture::solid(device, queue, [255, 255, 255, 255], ColourSpace::Srgb),
+            atlas_sampler,
+            pipelines: HashMap::new(),
+            bind_groups: HashMap::new(),
+            vertex_buffer: Self::create_vertex_buffer(device, capacity),
+            capacity,
+        }
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ui Vertex Buffer"),
+            size: (capacity * mem::size_of::<UiVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        resources: &UiResources,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ui Pipeline Layout"),
+            bind_group_layouts: &[
+                &resources.uniform_bind_group_layout,
+                &resources.texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ui Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &resources.shader,
+                entry_point: Some("vs_main"),
+                buffers: &[UiVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &resources.shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        resources: &UiResources,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Ui Texture Bind Group"),
+            layout: &resources.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Glyphs for text anchored at the top of its first line, skipped if the font isn't loaded
+    fn push_text(
+        &mut self,
+        vertices: &mut Vec<UiVertex>,
+        renderer: &mut renderer::Renderer,
+        text: &str,
+        mut anchor: [f32; 2],
+        style: &TextStyle,
+    ) {
+        let font = match renderer.text_mut().font_mut(style.font) {
+            Some(font) => font,
+            None => return,
+        };
+
+        let glyphs = font.layout(text, style);
+        let mode = match font.rendering {
+            FontRendering::Bitmap => MODE_BITMAP,
+            FontRendering::Sdf => MODE_SDF,
+        };
+
+        if font.rendering == FontRendering::Bitmap {
+            anchor = [anchor[0].round(), anchor[1].round()];
+        }
+
+        let binding = UiBinding::Font(style.font);
+
+        for glyph in glyphs {
+            self.push_quad(
+                vertices,
+                &binding,
+                [
+                    [anchor[0] + glyph.min[0], anchor[1] + glyph.min[1]],
+                    [anchor[0] + glyph.max[0], anchor[1] + glyph.max[1]],
+                ],
+                [glyph.uv_min, glyph.uv_max],
+                style.colour,
+                mode,
+            );
+        }
+    }
+
+    /// The profiler's averages as a bar per system and pass over everything else, labelled if
+    /// the profiler has an overlay font
+    fn push_profiler_overlay(
+        &mut self,
+        vertices: &mut Vec<UiVertex>,
+        renderer: &mut renderer::Renderer,
+    ) {
+        let profiler = renderer.profiler();
+        let frame_time = profiler.frame_time();
+        let font = profiler.overlay_font();
+
+        let mut rows = vec![(
+            format!(
+                "frame {:.2} ms, {:.0} fps",
+                frame_time,
+                1000.0 / frame_time.max(0.001)
+            ),
+            None,
+        )];
+
+        if !profiler.gpu_supported() {
+            rows.push(("gpu timing not supported".to_string(), None));
+        }
+
+        // systems, then passes
+        for track in [ProfileTrack::Cpu, ProfileTrack::Gpu] {
+            for stats in profiler.stats().iter().filter(|stats| stats.track == track) {
+                let prefix = match track {
+                    ProfileTrack::Cpu => "cpu",
+                    ProfileTrack::Gpu => "gpu",
+                };
+
+                rows.push((
+                    format!("{} {} {:.2} ms", prefix, stats.name, stats.average()),
+                    Some((track, stats.average())),
+                ));
+            }
+        }
+
+        let [x, y] = OVERLAY_POSITION;
+        let width = OVERLAY_LABEL_WIDTH + OVERLAY_BAR_WIDTH + OVERLAY_PADDING * 2.0;
+        let height = rows.len() as f32 * OVERLAY_ROW_HEIGHT + OVERLAY_PADDING * 2.0;
+
+        self.push_quad(
+            vertices,
+            &UiBinding::White,
+            [[x, y], [x + width, y + height]],
+            [[0.0, 0.0], [1.0, 1.0]],
+            [0.0, 0.0, 0.0, 0.6],
+            MODE_COLOUR,
+        );
+
+        for (row, (label, bar)) in rows.iter().enumerate() {
+            let top = y + OVERLAY_PADDING + row as f32 * OVERLAY_ROW_HEIGHT;
+
+            if let Some((track, milliseconds)) = bar {
+                let left = x + OVERLAY_PADDING + OVERLAY_LABEL_WIDTH;
+                let fraction = (milliseconds / OVERLAY_BUDGET_MS).min(1.0) as f32;
+                let colour = match track {
+                    ProfileTrack::Cpu => [0.4, 0.85, 0.4, 0.9],
+                    ProfileTrack::Gpu => [0.4, 0.6, 1.0, 0.9],
+                };
+
+                self.push_quad(
+                    vertices,
+                    &UiBinding::White,
+                    [
+                        [left, top + 3.0],
+                        [
+                            left + (OVERLAY_BAR_WIDTH * fraction).max(1.0),
+                            top + OVERLAY_ROW_HEIGHT - 3.0,
+                        ],
+                    ],
+                    [[0.0, 0.0], [1.0, 1.0]],
+                    colour,
+                    MODE_COLOUR,
+                );
+            }
+
+            if let Some(font) = font {
+                let style = TextStyle::new(font, OVERLAY_FONT_SIZE);
+                self.push_text(
+                    vertices,
+                    renderer,
+                    label,
+                    [x + OVERLAY_PADDING, top],
+                    &style,
+                );
+            }
+        }
+    }
+
+    /// Two triangles covering min to max, continuing the last batch if it uses the same texture
+    fn push_quad(
+        &mut self,
+        vertices: &mut Vec<UiVertex>,
+        binding: &UiBinding,
+        [min, max]: [[f32; 2]; 2],
+        [uv_min, uv_max]: [[f32; 2]; 2],
+        colour: [f32; 4],
+        mode: u32,
+    ) {
+        let start = vertices.len() as u32;
+
+        for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 0), (0, 1), (1, 1)] {
+            vertices.push(UiVertex {
+                position: [
+                    if x == 0 { min[0] } else { max[0] },
+                    if y == 0 { min[1] } else { max[1] },
+                ],
+                uv: [
+                    if x == 0 { uv_min[0] } else { uv_max[0] },
+                    if y == 0 { uv_min[1] } else { uv_max[1] },
+                ],
+                colour,
+                mode,
+            });
+        }
+
+        let end = vertices.len() as u32;
+
+        match self.batches.last_mut() {
+            Some(batch) if batch.binding == *binding => batch.vertices.end = end,
+            _ => self.batches.push(UiBatch {
+                binding: binding.clone(),
+                vertices: start..end,
+            }),
+        }
+    }
+}
+
+impl GraphPass for UiPass {
+    fn name(&self) -> &'static str {
+        "ui"
+    }
+
+    fn inputs(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    fn outputs(&self) -> Vec<&'static str> {
+        vec![render_graph::SURFACE]
+    }
+
+    fn prepare(
+        &mut self,
+        context: &PrepareContext,
+        world: &game::World,
+        renderer: &mut renderer::Renderer,
+    ) {
+        self.batches.clear();
+
+        let order = ui::draw_order(world);
+
+        if order.is_empty() && !renderer.profiler().overlay() {
+            return;
+        }
+
+        let device = renderer.device().clone();
+        let device = device.lock().unwrap();
+        let queue = renderer.queue().clone();
+        let queue = queue.lock().unwrap();
+
+        let mut resources = match self.resources.take() {
+            Some(resources) => resources,
+            None => Self::create_resources(&device, &queue),
+        };
+
+        let mut vertices = Vec::new();
+
+        for entity_id in order {
+            let node = match world.get_entity_component_by_type(entity_id, ComponentType::UiNode) {
+                Some(ComponentEnum::UiNode(node)) => node,
+                _ => continue,
+            };
+
+            let rect = node.rect;
+            let min = rect.position;
+            let max = [min[0] + rect.size[0], min[1] + rect.size[1]];
+
+            match &node.content {
+                UiContent::Text { text, style } => {
+                    // each line is aligned within the node's width
+                    let anchor = [min[0] + rect.size[0] * style.align.factor(), min[1]];
+
+                    self.push_text(&mut vertices, renderer, text, anchor, style);
+                }
+                UiContent::Image { path, .. } => {
+                    if let (Some(texture), Some(tint)) = (&node.texture, node.fill_colour()) {
+                        let binding = UiBinding::Image(path.clone());
+
+                        if !resources.bind_groups.contains_key(&binding) {
+                            let bind_group = Self::create_bind_group(
+                                &device,
+                                &resources,
+                                &texture.view,
+                                &texture.sampler,
+                            );
+                            resources.bind_groups.insert(binding.clone(), bind_group);
+                        }
+
+                        self.push_quad(
+                            &mut vertices,
+                            &binding,
+                            [min, max],
+                            [[0.0, 0.0], [1.0, 1.0]],
+                            tint,
+                            MODE_COLOUR,
+                        );
+                    }
+                }
+                _ => {
+                    if let Some(colour) = node.fill_colour() {
+                        self.push_quad(
+                            &mut vertices,
+                            &UiBinding::White,
+                            [min, max],
+                            [[0.0, 0.0], [1.0, 1.0]],
+                            colour,
+                            MODE_COLOUR,
+                        );
+                    }
+                }
+            }
+        }
+
+        if renderer.profiler().overlay() {
+            self.push_profiler_overlay(&mut vertices, renderer);
+        }
+
+        // glyphs may have been added to the atlases while laying out text
+        renderer.text_mut().upload(&device, &queue);
+
+        if !resources.bind_groups.contains_key(&UiBinding::White) {
+            let bind_group = Self::create_bind_group(
+                &device,
+                &resources,
+                &resources.white.view,
+                &resources.white.sampler,
+            );
+            resources.bind_groups.insert(UiBinding::White, bind_group);
+        }
+
+        for (index, font) in renderer.text().fonts().iter().enumerate() {
+            let binding = UiBinding::Font(index);
+
+            if let Some((_, view)) = &font.texture {
+                if !resources.bind_groups.contains_key(&binding) {
+                    let bind_group = Self::create_bind_group(
+                        &device,
+                        &resources,
+                        view,
+                        &resources.atlas_sampler,
+                    );
+                    resources.bind_groups.insert(binding, bind_group);
+                }
+            }
+        }
+
+        let surface_format = context.format(render_graph::SURFACE);
+        self.surface_format = Some(surface_format);
+
+        if !resources.pipelines.contains_key(&surface_format) {
+            let pipeline = Self::create_pipeline(&device, &resources, surface_format);
+            resources.pipelines.insert(surface_format, pipeline);
+        }
+
+        if vertices.len() > resources.capacity {
+            resources.capacity = vertices.len().next_power_of_two();
+            resources.vertex_buffer = Self::create_vertex_buffer(&device, resources.capacity);
+        }
+
+        queue.write_buffer(&resources.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+
+        let size = renderer.size();
+
+        queue.write_buffer(
+            &resources.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[UiUniforms {
+                screen: [
+                    size.width as f32,
+                    size.height as f32,
+                    if surface_format.is_srgb() { 0.0 } else { 1.0 },
+                    0.0,
+                ],
+            }]),
+        );
+
+        self.resources = Some(resources);
+    }
+
+    fn execute(
+        &mut self,
+        context: &mut PassContext,
+        _world: &game::World,
+        _renderer: &renderer::Renderer,
+    ) {
+        let (resources, surface_format) = match (&self.resources, self.surface_format) {
+            (Some(resources), Some(surface_format)) if !self.batches.is_empty() => {
+                (resources, surface_format)
+            }
+            _ => return,
+        };
+
+        let mut render_pass = context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Ui Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: context.view(render_graph::SURFACE),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: context.colour_load(render_graph::SURFACE, wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+        render_pass.set_pipeline(&resources.pipelines[&surface_format]);
+        render_pass.set_bind_group(0, &resources.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+
+        for batch in self.batches.iter() {
+            if let Some(bind_group) = resources.bind_groups.get(&batch.binding) {
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.draw(batch.vertices.clone(), 0..1);
+            }
+        }
+    }
+}
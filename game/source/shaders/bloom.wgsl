@@ -0,0 +1,91 @@
+// threshold, downsample and upsample steps of the bloom chain, @see passes/bloom_pass.rs
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) uv : vec2<f32>,
+};
+
+// @see BloomUniforms in core/hdr.rs
+struct Bloom {
+    // threshold, knee, radius
+    settings : vec4<f32>,
+    texel_size : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture : texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler : sampler;
+
+@group(0) @binding(2)
+var<uniform> bloom : Bloom;
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out : VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+fn sample(uv : vec2<f32>, offset : vec2<f32>) -> vec3<f32> {
+    return textureSample(source_texture, source_sampler, uv + offset * bloom.texel_size.xy).rgb;
+}
+
+// 13 tap filter from call of duty: advanced warfare, which avoids the flicker of a plain box filter
+fn downsample(uv : vec2<f32>) -> vec3<f32> {
+    let a = sample(uv, vec2<f32>(-2.0, -2.0));
+    let b = sample(uv, vec2<f32>(0.0, -2.0));
+    let c = sample(uv, vec2<f32>(2.0, -2.0));
+    let d = sample(uv, vec2<f32>(-2.0, 0.0));
+    let e = sample(uv, vec2<f32>(0.0, 0.0));
+    let f = sample(uv, vec2<f32>(2.0, 0.0));
+    let g = sample(uv, vec2<f32>(-2.0, 2.0));
+    let h = sample(uv, vec2<f32>(0.0, 2.0));
+    let i = sample(uv, vec2<f32>(2.0, 2.0));
+    let j = sample(uv, vec2<f32>(-1.0, -1.0));
+    let k = sample(uv, vec2<f32>(1.0, -1.0));
+    let l = sample(uv, vec2<f32>(-1.0, 1.0));
+    let m = sample(uv, vec2<f32>(1.0, 1.0));
+
+    return e * 0.125 + (a + c + g + i) * 0.03125 + (b + d + f + h) * 0.0625 + (j + k + l + m) * 0.125;
+}
+
+// keeps only the bright parts of the scene, with a soft knee around the threshold
+@fragment
+fn fs_prefilter(in : VertexOutput) -> @location(0) vec4<f32> {
+    let colour = downsample(in.uv);
+
+    let threshold = bloom.settings.x;
+    let knee = max(threshold * bloom.settings.y, 0.00001);
+    let brightness = max(colour.r, max(colour.g, colour.b));
+
+    var soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+
+    let contribution = max(soft, brightness - threshold) / max(brightness, 0.00001);
+
+    return vec4<f32>(colour * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in : VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(in.uv), 1.0);
+}
+
+// 9 tap tent filter, added onto the larger texture by the pipeline's blend state
+@fragment
+fn fs_upsample(in : VertexOutput) -> @location(0) vec4<f32> {
+    let radius = bloom.settings.z;
+
+    var colour = sample(in.uv, vec2<f32>(0.0, 0.0)) * 4.0;
+    colour += (sample(in.uv, vec2<f32>(-radius, 0.0)) + sample(in.uv, vec2<f32>(radius, 0.0)) + sample(in.uv, vec2<f32>(0.0, -radius)) + sample(in.uv, vec2<f32>(0.0, radius))) * 2.0;
+    colour += sample(in.uv, vec2<f32>(-radius, -radius)) + sample(in.uv, vec2<f32>(radius, -radius)) + sample(in.uv, vec2<f32>(-radius, radius)) + sample(in.uv, vec2<f32>(radius, radius));
+
+    return vec4<f32>(colour / 16.0, 1.0);
+}
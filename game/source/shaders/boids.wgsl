@@ -0,0 +1,84 @@
+// steers boids by their neighbours, @see systems/boids_system.rs
+
+// @see Boid in systems/boids_system.rs
+struct Boid {
+    // xyz position, w max speed
+    position: vec4<f32>,
+    // xyz velocity, w neighbour radius
+    velocity: vec4<f32>,
+    // separation, alignment, cohesion
+    weights: vec4<f32>,
+    // xyz change to the velocity, written here and read back - a change rather than the velocity
+    // itself, as the boid keeps moving while it's on its way back
+    result: vec4<f32>,
+};
+
+// @see BoidSettings in systems/boids_system.rs
+struct Settings {
+    count: u32,
+    delta_time: f32,
+};
+
+@group(0) @binding(0)
+var<storage, read_write> boids: array<Boid>;
+
+@group(0) @binding(1)
+var<uniform> settings: Settings;
+
+// @see WORKGROUP_SIZE in core/compute.rs
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+
+    if (index >= settings.count) {
+        return;
+    }
+
+    let boid = boids[index];
+    let position = boid.position.xyz;
+    let velocity = boid.velocity.xyz;
+    let radius = boid.velocity.w;
+
+    var separation = vec3<f32>(0.0);
+    var heading = vec3<f32>(0.0);
+    var centre = vec3<f32>(0.0);
+    var neighbours = 0.0;
+
+    for (var i = 0u; i < settings.count; i++) {
+        if (i == index) {
+            continue;
+        }
+
+        let other = boids[i];
+        let offset = position - other.position.xyz;
+        let distance_squared = dot(offset, offset);
+
+        if (distance_squared > radius * radius) {
+            continue;
+        }
+
+        // pushed harder the closer they are
+        separation += offset / max(distance_squared, 0.0001);
+        heading += other.velocity.xyz;
+        centre += other.position.xyz;
+        neighbours += 1.0;
+    }
+
+    var steering = separation * boid.weights.x;
+
+    if (neighbours > 0.0) {
+        steering += (heading / neighbours - velocity) * boid.weights.y;
+        steering += (centre / neighbours - position) * boid.weights.z;
+    }
+
+    var result = velocity + steering * settings.delta_time;
+    let speed = length(result);
+    let max_speed = boid.position.w;
+
+    if (speed > max_speed) {
+        result *= max_speed / speed;
+    }
+
+    // only this boid's result, the others are still being read
+    boids[index].result = vec4<f32>(result - velocity, 0.0);
+}
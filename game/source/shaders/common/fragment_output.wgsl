@@ -0,0 +1,20 @@
+// written by the material fragment shaders - the shaded colour, and the entity drawn for mouse
+// picking. custom fragment shaders return this too to be pickable, @see core/picking.rs
+
+#include "common/vertex_output.wgsl"
+
+struct FragmentOutput {
+    @location(0) colour : vec4<f32>,
+    // the entity id plus one, a byte per channel - only written by opaque materials
+    @location(1) entity_id : vec4<u32>,
+};
+
+fn fragment_output(in : VertexOutput, colour : vec4<f32>) -> FragmentOutput {
+    let id = in.entity_id;
+
+    var out : FragmentOutput;
+    out.colour = colour;
+    out.entity_id = vec4<u32>(id & 255u, (id >> 8u) & 255u, (id >> 16u) & 255u, id >> 24u);
+
+    return out;
+}
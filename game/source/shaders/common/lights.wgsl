@@ -0,0 +1,107 @@
+// scene lights and their shadow maps
+
+#include "common/uniforms.wgsl"
+
+// @see LightUniforms in core/renderer.rs
+struct Light {
+    // xyz position, w kind (0 directional, 1 point, 2 spot)
+    position : vec4<f32>,
+    // xyz direction, w range
+    direction : vec4<f32>,
+    // rgb colour, w intensity
+    colour : vec4<f32>,
+    // cos inner angle, cos outer angle
+    spot : vec4<f32>,
+    // first shadow map layer (negative for none), cascade count
+    shadow : vec4<f32>,
+};
+
+struct Lights {
+    ambient : vec4<f32>,
+    // intensity, highest mip level, enabled
+    environment : vec4<f32>,
+    count : u32,
+    lights : array<Light>,
+};
+
+// @see ShadowUniforms in core/shadows.rs
+struct Shadows {
+    view_projections : array<mat4x4<f32>, 8>,
+    // view distance at which each cascade ends
+    cascade_splits : vec4<f32>,
+    // depth bias, normal bias, texel size, pcf radius
+    settings : vec4<f32>,
+};
+
+@group(0) @binding(1)
+var<storage, read> lights : Lights;
+
+@group(0) @binding(2)
+var<uniform> shadows : Shadows;
+
+@group(0) @binding(3)
+var shadow_maps : texture_depth_2d_array;
+
+@group(0) @binding(4)
+var shadow_sampler : sampler_comparison;
+
+// smooth falloff to zero at the light's range
+fn attenuation(distance : f32, range : f32) -> f32 {
+    let ratio = distance / range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+
+    return window * window / (distance * distance + 1.0);
+}
+
+// fraction of light reaching a point, from the light's shadow maps
+fn shadow_factor(light : Light, position : vec3<f32>, normal : vec3<f32>) -> f32 {
+    if (light.shadow.x < 0.0) {
+        return 1.0;
+    }
+
+    var layer = i32(light.shadow.x);
+    let cascade_count = i32(light.shadow.y);
+
+    // pick the cascade by distance along the view direction
+    if (cascade_count > 1) {
+        let view_depth = -(uniforms.view * vec4<f32>(position, 1.0)).z;
+
+        if (view_depth > shadows.cascade_splits[cascade_count - 1]) {
+            return 1.0;
+        }
+
+        for (var cascade = 0; cascade < cascade_count - 1; cascade++) {
+            if (view_depth > shadows.cascade_splits[cascade]) {
+                layer += 1;
+            }
+        }
+    }
+
+    // push the lookup out along the normal to avoid self shadowing
+    let offset_position = position + normal * shadows.settings.y;
+    let clip = shadows.view_projections[layer] * vec4<f32>(offset_position, 1.0);
+    let ndc = clip.xyz / clip.w;
+
+    if (ndc.z > 1.0 || any(abs(ndc.xy) > vec2<f32>(1.0))) {
+        return 1.0;
+    }
+
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5);
+    let depth = ndc.z - shadows.settings.x;
+    let texel_size = shadows.settings.z;
+    let radius = i32(shadows.settings.w);
+
+    // percentage closer filtering over a square kernel
+    var lit = 0.0;
+    var samples = 0.0;
+
+    for (var x = -radius; x <= radius; x++) {
+        for (var y = -radius; y <= radius; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel_size;
+            lit += textureSampleCompareLevel(shadow_maps, shadow_sampler, uv + offset, layer, depth);
+            samples += 1.0;
+        }
+    }
+
+    return lit / samples;
+}
@@ -0,0 +1,18 @@
+// dithered cross-fades between levels of detail
+
+// whether a fragment of a level of detail being cross-faded is drawn - the level fading in covers
+// the fraction of pixels the fade is through, the level fading out the rest
+fn lod_visible(position : vec2<f32>, lod_fade : vec2<f32>) -> bool {
+    if (lod_fade.y == 0.0) {
+        return true;
+    }
+
+    // interleaved gradient noise, so the pattern doesn't tile visibly
+    let noise = fract(52.9829189 * fract(dot(position, vec2<f32>(0.06711056, 0.00583715))));
+
+    if (lod_fade.y == 1.0) {
+        return noise < lod_fade.x;
+    }
+
+    return noise >= lod_fade.x;
+}
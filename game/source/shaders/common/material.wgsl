@@ -0,0 +1,22 @@
+// material uniforms and base colour texture, bound by every material
+
+// @see MaterialUniforms in components/material_component.rs
+struct Material {
+    base_colour : vec4<f32>,
+    // specular strength, shininess, lit, alpha cutoff
+    lighting : vec4<f32>,
+    params : array<vec4<f32>, 4>,
+    // metallic, roughness, occlusion strength, normal scale
+    pbr : vec4<f32>,
+    emissive : vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> material : Material;
+
+@group(2) @binding(1)
+var base_colour_texture : texture_2d<f32>;
+
+@group(2) @binding(2)
+var base_colour_sampler : sampler;
+
@@ -0,0 +1,11 @@
+// per frame camera and time, shared by every material stage
+
+struct Uniforms {
+    time : vec4<f32>,
+    projection : mat4x4<f32>,
+    view : mat4x4<f32>,
+    camera_position : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms : Uniforms;
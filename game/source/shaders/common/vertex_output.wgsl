@@ -0,0 +1,14 @@
+// passed from vertex.wgsl to the material fragment shaders
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) color : vec3<f32>,
+    @location(1) uv : vec2<f32>,
+    @location(2) world_position : vec3<f32>,
+    @location(3) world_normal : vec3<f32>,
+    @location(4) @interpolate(flat) receives_shadows : u32,
+    // x: how far through a level of detail cross-fade, y: 0 not fading, 1 fading in, 2 fading out
+    @location(5) @interpolate(flat) lod_fade : vec2<f32>,
+    // the entity's id plus one, so 0 is nothing - @see common/fragment_output.wgsl
+    @location(6) @interpolate(flat) entity_id : u32,
+};
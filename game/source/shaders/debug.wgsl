@@ -0,0 +1,35 @@
+// unlit lines from DebugDraw, @see core/debug_draw.rs
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) colour: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) colour: vec3<f32>,
+};
+
+struct Uniforms {
+    time: vec4<f32>,
+    projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.projection * uniforms.view * vec4<f32>(in.position, 1.0);
+    out.colour = in.colour;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.colour, 1.0);
+}
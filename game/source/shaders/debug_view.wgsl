@@ -0,0 +1,119 @@
+// flat debug shading for the renderer's view modes, @see core/view_mode.rs
+//
+// the fragment entry points follow vs_main in vertex.wgsl, apart from the barycentric wireframe
+// which pulls its vertices from storage so it knows each one's corner of the triangle
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) world_normal: vec3<f32>,
+    @location(4) @interpolate(flat) receives_shadows: u32,
+};
+
+struct WireframeOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) barycentric: vec3<f32>,
+};
+
+struct Uniforms {
+    time: vec4<f32>,
+    projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+struct Transform {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    // x: receives shadows, y: entity id
+    flags: vec4<u32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var<uniform> transform: Transform;
+
+// the mesh's vertex buffer, as floats - @see Vertex in core/geometry.rs
+@group(2) @binding(0)
+var<storage, read> vertices: array<f32>;
+
+// the mesh's u16 index buffer, two to a word
+@group(2) @binding(1)
+var<storage, read> indices: array<u32>;
+
+const VERTEX_STRIDE: u32 = 12u;
+
+const WIREFRAME_COLOUR: vec3<f32> = vec3<f32>(0.9, 0.9, 0.9);
+
+@fragment
+fn fs_wireframe(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(WIREFRAME_COLOUR, 1.0);
+}
+
+// draw with one vertex per index, non-indexed
+@vertex
+fn vs_wireframe(@builtin(vertex_index) vertex_index: u32) -> WireframeOutput {
+    let index = (indices[vertex_index / 2u] >> ((vertex_index % 2u) * 16u)) & 0xffffu;
+    let base = index * VERTEX_STRIDE;
+    let position = vec3<f32>(vertices[base], vertices[base + 1u], vertices[base + 2u]);
+
+    var out: WireframeOutput;
+    out.clip_position = uniforms.projection * uniforms.view * transform.model * vec4<f32>(position, 1.0);
+
+    let corner = vertex_index % 3u;
+    out.barycentric = vec3<f32>(f32(corner == 0u), f32(corner == 1u), f32(corner == 2u));
+
+    return out;
+}
+
+@fragment
+fn fs_wireframe_barycentric(in: WireframeOutput) -> @location(0) vec4<f32> {
+    // about a pixel wide, whatever the triangle's size on screen
+    let width = fwidth(in.barycentric);
+    let edge = smoothstep(vec3<f32>(0.0), width * 1.5, in.barycentric);
+
+    if (min(edge.x, min(edge.y, edge.z)) > 0.99) {
+        discard;
+    }
+
+    return vec4<f32>(WIREFRAME_COLOUR, 1.0);
+}
+
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.world_normal) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    // recover the planes from the perspective projection's depth terms
+    let a = uniforms.projection[2][2];
+    let b = uniforms.projection[3][2];
+    let near = b / a;
+    let far = b / (a + 1.0);
+
+    let depth = -(uniforms.view * vec4<f32>(in.world_position, 1.0)).z;
+    let brightness = 1.0 - clamp((depth - near) / (far - near), 0.0, 1.0);
+
+    return vec4<f32>(vec3<f32>(brightness), 1.0);
+}
+
+// pcg hash, so neighbouring ids get unrelated colours
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+
+    return (word >> 22u) ^ word;
+}
+
+@fragment
+fn fs_entity_id(in: VertexOutput) -> @location(0) vec4<f32> {
+    let h = hash(transform.flags.y);
+    let colour = vec3<f32>(f32(h & 255u), f32((h >> 8u) & 255u), f32((h >> 16u) & 255u)) / 255.0;
+
+    return vec4<f32>(colour, 1.0);
+}
@@ -0,0 +1,32 @@
+// copies the multisampled scene depth into the single sampled depth buffer, keeping the nearest
+// sample so effects reading depth see the edges of what was drawn
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var depth_texture : texture_depth_multisampled_2d;
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out : VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in : VertexOutput) -> @builtin(frag_depth) f32 {
+    let position = vec2<i32>(in.clip_position.xy);
+    var depth = 1.0;
+
+    for (var sample = 0u; sample < textureNumSamples(depth_texture); sample++) {
+        depth = min(depth, textureLoad(depth_texture, position, i32(sample)));
+    }
+
+    return depth;
+}
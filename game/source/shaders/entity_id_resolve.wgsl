@@ -0,0 +1,25 @@
+// copies the multisampled entity ids into the single sampled target picks are read back from -
+// ids can't be averaged, so each pixel takes its first sample
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var entity_id_texture : texture_multisampled_2d<u32>;
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out : VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in : VertexOutput) -> @location(0) vec4<u32> {
+    return textureLoad(entity_id_texture, vec2<i32>(in.clip_position.xy), 0);
+}
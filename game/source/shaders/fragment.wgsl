@@ -1,12 +1,85 @@
-// @see ./vertex.wgsl
+#include "common/vertex_output.wgsl"
+#include "common/fragment_output.wgsl"
+#include "common/lights.wgsl"
+#include "common/material.wgsl"
+#include "common/lod.wgsl"
 
-struct VertexOutput {
-    @builtin(position) clip_position : vec4<f32>,
-    @location(0) color : vec3<f32>,
-};
+// blinn-phong contribution of every light, returned as diffuse and specular terms
+fn blinn_phong(position : vec3<f32>, normal : vec3<f32>, view_direction : vec3<f32>, receives_shadows : bool) -> array<vec3<f32>, 2> {
+    var diffuse = vec3<f32>(0.0);
+    var specular = vec3<f32>(0.0);
+
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        let kind = u32(light.position.w);
+
+        var light_direction : vec3<f32>;
+        var strength = light.colour.w;
+
+        if (kind == 0u) {
+            light_direction = -light.direction.xyz;
+        } else {
+            let to_light = light.position.xyz - position;
+            let distance = length(to_light);
+
+            light_direction = to_light / max(distance, 0.0001);
+            strength *= attenuation(distance, light.direction.w);
+
+            if (kind == 2u) {
+                let cos_angle = dot(-light_direction, light.direction.xyz);
+                strength *= smoothstep(light.spot.y, light.spot.x, cos_angle);
+            }
+        }
+
+        let n_dot_l = max(dot(normal, light_direction), 0.0);
+
+        if (n_dot_l <= 0.0) {
+            continue;
+        }
+
+        if (receives_shadows) {
+            strength *= shadow_factor(light, position, normal);
+        }
+
+        let half_vector = normalize(light_direction + view_direction);
+        let n_dot_h = max(dot(normal, half_vector), 0.0);
+
+        diffuse += light.colour.rgb * strength * n_dot_l;
+        specular += light.colour.rgb * strength * pow(n_dot_h, material.lighting.y) * material.lighting.x;
+    }
+
+    return array<vec3<f32>, 2>(diffuse, specular);
+}
 
 @fragment
-fn fs_main(in : VertexOutput) -> @location(0) vec4<f32> {
-    // Return color directly without any adjustments
-    return vec4<f32>(in.color, 1.0);
+fn fs_main(in : VertexOutput) -> FragmentOutput {
+    // tint the vertex colour by the material's base colour and texture
+    let texel = textureSample(base_colour_texture, base_colour_sampler, in.uv);
+    let albedo = vec4<f32>(in.color, 1.0) * material.base_colour * texel;
+
+#ifdef ALPHA_CUTOUT
+    // alpha tested cutout, only compiled into materials with a cutoff
+    if (albedo.a < material.lighting.w) {
+        discard;
+    }
+#endif
+
+    // dithered while cross-fading between levels of detail
+    if (!lod_visible(in.clip_position.xy, in.lod_fade)) {
+        discard;
+    }
+
+    if (material.lighting.z == 0.0) {
+        return fragment_output(in, albedo);
+    }
+
+    let normal = normalize(in.world_normal);
+    let view_direction = normalize(uniforms.camera_position.xyz - in.world_position);
+
+    let lighting = blinn_phong(in.world_position, normal, view_direction, in.receives_shadows == 1u);
+    let ambient = lights.ambient.rgb * lights.ambient.w;
+
+    let colour = albedo.rgb * (ambient + lighting[0]) + lighting[1];
+
+    return fragment_output(in, vec4<f32>(colour, albedo.a));
 }
@@ -0,0 +1,32 @@
+// downsamples one mip level into the next with a fullscreen triangle
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) uv : vec2<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture : texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler : sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    var out : VertexOutput;
+
+    // one triangle covering the whole target
+    let x = f32((vertex_index << 1u) & 2u);
+    let y = f32(vertex_index & 2u);
+
+    out.clip_position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    out.uv = vec2<f32>(x, y);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in : VertexOutput) -> @location(0) vec4<f32> {
+    // linear filtering at the texel corner averages the 2x2 block from the previous level
+    return textureSample(source_texture, source_sampler, in.uv);
+}
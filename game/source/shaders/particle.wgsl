@@ -0,0 +1,100 @@
+// camera facing particle quads, one instance per particle, @see passes/particle_pass.rs
+
+struct Uniforms {
+    time: vec4<f32>,
+    projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+// @see Particle in core/particles.rs
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+// @see ParticleUniforms in core/particles.rs
+struct Emitter {
+    // xyz gravity, w seconds to simulate
+    gravity: vec4<f32>,
+    count: u32,
+    // curves over the particle's life, @see CURVE_SAMPLES
+    colours: array<vec4<f32>, 16>,
+    sizes: array<vec4<f32>, 4>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -1 to 1 across the quad
+    @location(0) corner: vec2<f32>,
+    @location(1) colour: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var<storage, read> particles: array<Particle>;
+
+@group(1) @binding(1)
+var<uniform> emitter: Emitter;
+
+fn size_sample(index: u32) -> f32 {
+    return emitter.sizes[index / 4u][index % 4u];
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    let particle = particles[instance_index];
+
+    // two triangles
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    var out: VertexOutput;
+    out.corner = corner;
+
+    // dead particles collapse to nothing
+    if (particle.age >= particle.lifetime) {
+        out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+        out.colour = vec4<f32>(0.0);
+        return out;
+    }
+
+    // blend between the two nearest baked curve samples
+    let life = clamp(particle.age / particle.lifetime, 0.0, 1.0) * 15.0;
+    let first = u32(floor(life));
+    let second = min(first + 1u, 15u);
+    let t = fract(life);
+
+    let colour = mix(emitter.colours[first], emitter.colours[second], t);
+    let size = mix(size_sample(first), size_sample(second), t);
+
+    let view_position = uniforms.view * vec4<f32>(particle.position, 1.0)
+        + vec4<f32>(corner * size * 0.5, 0.0, 0.0);
+
+    out.clip_position = uniforms.projection * view_position;
+    out.colour = colour;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // a soft round sprite
+    let falloff = 1.0 - smoothstep(0.5, 1.0, length(in.corner));
+
+    return vec4<f32>(in.colour.rgb, in.colour.a * falloff);
+}
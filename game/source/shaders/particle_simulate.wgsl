@@ -0,0 +1,48 @@
+// moves particles simulated on the gpu, @see systems/particle_bufferer_system.rs
+
+// @see Particle in core/particles.rs
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+// @see ParticleUniforms in core/particles.rs
+struct Emitter {
+    // xyz gravity, w seconds to simulate
+    gravity: vec4<f32>,
+    count: u32,
+    colours: array<vec4<f32>, 16>,
+    sizes: array<vec4<f32>, 4>,
+};
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<Particle>;
+
+@group(0) @binding(1)
+var<uniform> emitter: Emitter;
+
+// @see WORKGROUP_SIZE in core/particles.rs
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+
+    if (index >= emitter.count) {
+        return;
+    }
+
+    var particle = particles[index];
+
+    if (particle.age >= particle.lifetime) {
+        return;
+    }
+
+    let delta_time = emitter.gravity.w;
+
+    particle.velocity += emitter.gravity.xyz * delta_time;
+    particle.position += particle.velocity * delta_time;
+    particle.age += delta_time;
+
+    particles[index] = particle;
+}
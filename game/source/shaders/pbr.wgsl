@@ -0,0 +1,213 @@
+// metallic-roughness pbr, @see ./fragment.wgsl for the blinn-phong equivalent
+
+#include "common/vertex_output.wgsl"
+#include "common/fragment_output.wgsl"
+#include "common/lights.wgsl"
+#include "common/material.wgsl"
+#include "common/lod.wgsl"
+
+@group(0) @binding(5)
+var environment_map : texture_cube<f32>;
+
+@group(0) @binding(6)
+var environment_sampler : sampler;
+
+@group(2) @binding(3)
+var metallic_roughness_texture : texture_2d<f32>;
+
+@group(2) @binding(4)
+var metallic_roughness_sampler : sampler;
+
+@group(2) @binding(5)
+var normal_texture : texture_2d<f32>;
+
+@group(2) @binding(6)
+var normal_sampler : sampler;
+
+@group(2) @binding(7)
+var occlusion_texture : texture_2d<f32>;
+
+@group(2) @binding(8)
+var occlusion_sampler : sampler;
+
+@group(2) @binding(9)
+var emissive_texture : texture_2d<f32>;
+
+@group(2) @binding(10)
+var emissive_sampler : sampler;
+
+const PI : f32 = 3.14159265359;
+
+// ggx / trowbridge-reitz normal distribution
+fn distribution_ggx(n_dot_h : f32, roughness : f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * denominator * denominator);
+}
+
+// smith-schlick geometry term for both the light and view directions
+fn geometry_smith(n_dot_v : f32, n_dot_l : f32, roughness : f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = r * r / 8.0;
+
+    let view = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let light = n_dot_l / (n_dot_l * (1.0 - k) + k);
+
+    return view * light;
+}
+
+fn fresnel_schlick(cos_theta : f32, f0 : vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// analytic approximation of the split-sum brdf lookup (karis)
+fn environment_brdf(f0 : vec3<f32>, roughness : f32, n_dot_v : f32) -> vec3<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+
+    return f0 * ab.x + ab.y;
+}
+
+// perturb the surface normal by the normal map, building the tangent frame from screen space derivatives
+fn perturb_normal(normal : vec3<f32>, sampled : vec3<f32>, dp_dx : vec3<f32>, dp_dy : vec3<f32>, duv_dx : vec2<f32>, duv_dy : vec2<f32>) -> vec3<f32> {
+    let dp2_perp = cross(dp_dy, normal);
+    let dp1_perp = cross(normal, dp_dx);
+    let tangent = dp2_perp * duv_dx.x + dp1_perp * duv_dy.x;
+    let bitangent = dp2_perp * duv_dx.y + dp1_perp * duv_dy.y;
+
+    let scale = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 0.00000001));
+
+    // meshes without uvs have no usable tangent frame
+    if (scale > 100000.0) {
+        return normal;
+    }
+
+    var tangent_normal = sampled * 2.0 - 1.0;
+    tangent_normal = vec3<f32>(tangent_normal.xy * material.pbr.w, tangent_normal.z);
+
+    return normalize(mat3x3<f32>(tangent * scale, bitangent * scale, normal) * tangent_normal);
+}
+
+// cook-torrance contribution of every light
+fn direct_lighting(position : vec3<f32>, normal : vec3<f32>, view_direction : vec3<f32>, albedo : vec3<f32>, metallic : f32, roughness : f32, f0 : vec3<f32>, receives_shadows : bool) -> vec3<f32> {
+    var radiance = vec3<f32>(0.0);
+    let n_dot_v = max(dot(normal, view_direction), 0.0001);
+
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        let kind = u32(light.position.w);
+
+        var light_direction : vec3<f32>;
+        var strength = light.colour.w;
+
+        if (kind == 0u) {
+            light_direction = -light.direction.xyz;
+        } else {
+            let to_light = light.position.xyz - position;
+            let distance = length(to_light);
+
+            light_direction = to_light / max(distance, 0.0001);
+            strength *= attenuation(distance, light.direction.w);
+
+            if (kind == 2u) {
+                let cos_angle = dot(-light_direction, light.direction.xyz);
+                strength *= smoothstep(light.spot.y, light.spot.x, cos_angle);
+            }
+        }
+
+        let n_dot_l = max(dot(normal, light_direction), 0.0);
+
+        if (n_dot_l <= 0.0) {
+            continue;
+        }
+
+        if (receives_shadows) {
+            strength *= shadow_factor(light, position, normal);
+        }
+
+        let half_vector = normalize(light_direction + view_direction);
+        let n_dot_h = max(dot(normal, half_vector), 0.0);
+        let h_dot_v = max(dot(half_vector, view_direction), 0.0);
+
+        let fresnel = fresnel_schlick(h_dot_v, f0);
+        let specular = distribution_ggx(n_dot_h, roughness) * geometry_smith(n_dot_v, n_dot_l, roughness) * fresnel / (4.0 * n_dot_v * n_dot_l + 0.0001);
+        let diffuse = (1.0 - fresnel) * (1.0 - metallic) * albedo / PI;
+
+        // scaled by pi so light intensities match the blinn-phong shader
+        radiance += (diffuse + specular) * light.colour.rgb * strength * n_dot_l * PI;
+    }
+
+    return radiance;
+}
+
+@fragment
+fn fs_main(in : VertexOutput) -> FragmentOutput {
+    // derivatives and samples are taken up front, while control flow is still uniform
+    let dp_dx = dpdx(in.world_position);
+    let dp_dy = dpdy(in.world_position);
+    let duv_dx = dpdx(in.uv);
+    let duv_dy = dpdy(in.uv);
+
+    let base_texel = textureSample(base_colour_texture, base_colour_sampler, in.uv);
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, in.uv);
+    let normal_texel = textureSample(normal_texture, normal_sampler, in.uv).xyz;
+    let occlusion_texel = textureSample(occlusion_texture, occlusion_sampler, in.uv).r;
+    let emissive_texel = textureSample(emissive_texture, emissive_sampler, in.uv).rgb;
+
+    let albedo = vec4<f32>(in.color, 1.0) * material.base_colour * base_texel;
+
+#ifdef ALPHA_CUTOUT
+    // alpha tested cutout, only compiled into materials with a cutoff
+    if (albedo.a < material.lighting.w) {
+        discard;
+    }
+#endif
+
+    // dithered while cross-fading between levels of detail
+    if (!lod_visible(in.clip_position.xy, in.lod_fade)) {
+        discard;
+    }
+
+    if (material.lighting.z == 0.0) {
+        return fragment_output(in, albedo);
+    }
+
+    // metallic in the blue channel, roughness in green, as in gltf
+    let metallic = clamp(material.pbr.x * metallic_roughness.b, 0.0, 1.0);
+    let roughness = clamp(material.pbr.y * metallic_roughness.g, 0.04, 1.0);
+    let occlusion = mix(1.0, occlusion_texel, material.pbr.z);
+
+    let geometric_normal = normalize(in.world_normal);
+    let normal = perturb_normal(geometric_normal, normal_texel, dp_dx, dp_dy, duv_dx, duv_dy);
+    let view_direction = normalize(uniforms.camera_position.xyz - in.world_position);
+    let n_dot_v = max(dot(normal, view_direction), 0.0001);
+
+    let f0 = mix(vec3<f32>(0.04), albedo.rgb, metallic);
+
+    var colour = direct_lighting(in.world_position, normal, view_direction, albedo.rgb, metallic, roughness, f0, in.receives_shadows == 1u);
+
+    // image based lighting from the environment, or flat ambient without one
+    if (lights.environment.z > 0.0) {
+        let max_mip = lights.environment.y;
+        let reflection = reflect(-view_direction, normal);
+
+        let irradiance = textureSampleLevel(environment_map, environment_sampler, normal, max_mip).rgb;
+        let prefiltered = textureSampleLevel(environment_map, environment_sampler, reflection, roughness * max_mip).rgb;
+
+        let specular_colour = environment_brdf(f0, roughness, n_dot_v);
+        let diffuse_colour = albedo.rgb * (1.0 - metallic) * (1.0 - specular_colour);
+
+        colour += (diffuse_colour * irradiance + specular_colour * prefiltered) * lights.environment.x * occlusion;
+    } else {
+        colour += lights.ambient.rgb * lights.ambient.w * albedo.rgb * occlusion;
+    }
+
+    colour += material.emissive.rgb * emissive_texel;
+
+    return fragment_output(in, vec4<f32>(colour, albedo.a));
+}
@@ -0,0 +1,183 @@
+// post processing effects, one fragment entry point each, @see core/post.rs
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) uv : vec2<f32>,
+};
+
+// @see PostUniforms in core/post.rs
+struct Post {
+    params : array<vec4<f32>, 2>,
+    // texel width, texel height, gamma encode
+    output_target : vec4<f32>,
+    // near, far
+    depth : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture : texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler : sampler;
+
+@group(0) @binding(2)
+var depth_texture : texture_depth_2d;
+
+@group(0) @binding(3)
+var lut_texture : texture_2d<f32>;
+
+@group(0) @binding(4)
+var<uniform> post : Post;
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out : VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+fn linear_to_srgb(colour : vec3<f32>) -> vec3<f32> {
+    return pow(clamp(colour, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.2));
+}
+
+fn srgb_to_linear(colour : vec3<f32>) -> vec3<f32> {
+    return pow(clamp(colour, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2));
+}
+
+// srgb targets encode on write, the surface might not
+fn output(colour : vec3<f32>) -> vec4<f32> {
+    if (post.output_target.z > 0.0) {
+        return vec4<f32>(linear_to_srgb(colour), 1.0);
+    }
+
+    return vec4<f32>(colour, 1.0);
+}
+
+@fragment
+fn fs_copy(in : VertexOutput) -> @location(0) vec4<f32> {
+    return output(textureSample(source_texture, source_sampler, in.uv).rgb);
+}
+
+// view distance of the surface under a pixel, from wgpu's 0 to 1 perspective depth
+fn view_distance(depth : f32) -> f32 {
+    let near = post.depth.x;
+    let far = post.depth.y;
+
+    return far * near / (far - depth * (far - near));
+}
+
+@fragment
+fn fs_fog(in : VertexOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(source_texture, source_sampler, in.uv).rgb;
+    let depth = textureLoad(depth_texture, vec2<i32>(in.clip_position.xy), 0);
+
+    // nothing was drawn here, leave the background alone
+    if (depth >= 1.0) {
+        return output(colour);
+    }
+
+    let distance = max(view_distance(depth) - post.params[1].y, 0.0);
+    let fog = 1.0 - exp(-post.params[1].x * distance);
+
+    return output(mix(colour, post.params[0].rgb, fog));
+}
+
+@fragment
+fn fs_colour_grading(in : VertexOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(source_texture, source_sampler, in.uv).rgb;
+
+    // luts are authored against srgb values
+    let graded = linear_to_srgb(colour);
+    let size = f32(textureDimensions(lut_texture).y);
+
+    let blue = graded.b * (size - 1.0);
+    let slice = floor(blue);
+    let next_slice = min(slice + 1.0, size - 1.0);
+
+    let x = graded.r * (size - 1.0) + 0.5;
+    let y = (graded.g * (size - 1.0) + 0.5) / size;
+
+    let a = textureSampleLevel(lut_texture, source_sampler, vec2<f32>((slice * size + x) / (size * size), y), 0.0).rgb;
+    let b = textureSampleLevel(lut_texture, source_sampler, vec2<f32>((next_slice * size + x) / (size * size), y), 0.0).rgb;
+
+    let lookup = srgb_to_linear(mix(a, b, blue - slice));
+
+    return output(mix(colour, lookup, post.params[0].x));
+}
+
+@fragment
+fn fs_vignette(in : VertexOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(source_texture, source_sampler, in.uv).rgb;
+
+    let intensity = post.params[0].x;
+    let radius = post.params[0].y;
+    let softness = post.params[0].z;
+
+    // 1.0 at the corners
+    let distance = length(in.uv - vec2<f32>(0.5)) * sqrt(2.0);
+    let vignette = smoothstep(radius, radius - softness, distance);
+
+    return output(colour * mix(1.0, vignette, intensity));
+}
+
+fn luma(colour : vec3<f32>) -> f32 {
+    // perceptual luma, so edges are found the way they look rather than by linear energy
+    return dot(sqrt(colour), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fs_fxaa(in : VertexOutput) -> @location(0) vec4<f32> {
+    let texel = post.output_target.xy;
+    let span_max = post.params[0].x;
+    let reduce_mul = post.params[0].y;
+    let reduce_min = post.params[0].z;
+
+    let rgb_nw = textureSample(source_texture, source_sampler, in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb;
+    let rgb_ne = textureSample(source_texture, source_sampler, in.uv + vec2<f32>(1.0, -1.0) * texel).rgb;
+    let rgb_sw = textureSample(source_texture, source_sampler, in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb;
+    let rgb_se = textureSample(source_texture, source_sampler, in.uv + vec2<f32>(1.0, 1.0) * texel).rgb;
+    let rgb_m = textureSample(source_texture, source_sampler, in.uv).rgb;
+
+    let luma_nw = luma(rgb_nw);
+    let luma_ne = luma(rgb_ne);
+    let luma_sw = luma(rgb_sw);
+    let luma_se = luma(rgb_se);
+    let luma_m = luma(rgb_m);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blur along the edge, perpendicular to the luma gradient
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let direction_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * reduce_mul, reduce_min);
+    let inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+
+    direction = clamp(direction * inverse_direction_min, vec2<f32>(-span_max), vec2<f32>(span_max)) * texel;
+
+    let rgb_a = 0.5 * (
+        textureSample(source_texture, source_sampler, in.uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        textureSample(source_texture, source_sampler, in.uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        textureSample(source_texture, source_sampler, in.uv + direction * -0.5).rgb +
+        textureSample(source_texture, source_sampler, in.uv + direction * 0.5).rgb
+    );
+
+    // the wider blur overshot the local contrast, so it crossed another edge
+    let luma_b = luma(rgb_b);
+
+    if (luma_b < luma_min || luma_b > luma_max) {
+        return output(rgb_a);
+    }
+
+    return output(rgb_b);
+}
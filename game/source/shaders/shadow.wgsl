@@ -0,0 +1,49 @@
+// renders mesh depth from a light's point of view
+
+#include "common/material.wgsl"
+
+struct VertexInput {
+    @location(0) position : vec3<f32>,
+    @location(3) uv : vec2<f32>,
+};
+
+struct Transform {
+    model : mat4x4<f32>,
+    normal : mat4x4<f32>,
+    flags : vec4<u32>,
+};
+
+struct CutoutOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) uv : vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> light_view_projection : mat4x4<f32>;
+
+@group(1) @binding(0)
+var<uniform> transform : Transform;
+
+@vertex
+fn vs_main(model : VertexInput) -> @builtin(position) vec4<f32> {
+    return light_view_projection * transform.model * vec4<f32>(model.position, 1.0);
+}
+
+// materials with an alpha cutoff leave holes in their shadows where they're cut out
+@vertex
+fn vs_cutout(model : VertexInput) -> CutoutOutput {
+    var out : CutoutOutput;
+    out.clip_position = light_view_projection * transform.model * vec4<f32>(model.position, 1.0);
+    out.uv = model.uv;
+    return out;
+}
+
+@fragment
+fn fs_cutout(in : CutoutOutput) {
+    let texel = textureSample(base_colour_texture, base_colour_sampler, in.uv);
+    let alpha = material.base_colour.a * texel.a;
+
+    if (alpha < material.lighting.w) {
+        discard;
+    }
+}
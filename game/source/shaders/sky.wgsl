@@ -0,0 +1,78 @@
+// the sky behind the scene, drawn as a fullscreen triangle on the far plane, @see core/sky.rs
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+// @see SkyUniforms in passes/sky_pass.rs
+struct Sky {
+    // inverse of the projection and the view without its translation, so ndc maps to a direction
+    inverse_view_projection: mat4x4<f32>,
+    // x mode (0 colour, 1 gradient, 2 cubemap), y intensity, z gradient exponent
+    settings: vec4<f32>,
+    // rgb, for the colour mode the colour itself
+    zenith: vec4<f32>,
+    horizon: vec4<f32>,
+    ground: vec4<f32>,
+    // xyz direction towards the sun, w cosine of its angular radius - above 1.0 for no sun
+    sun_direction: vec4<f32>,
+    sun_colour: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> sky: Sky;
+
+@group(0) @binding(1)
+var sky_texture: texture_cube<f32>;
+
+@group(0) @binding(2)
+var sky_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    // depth 1.0, so only pixels nothing has been drawn over pass the depth test
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+
+    return out;
+}
+
+fn gradient(direction: vec3<f32>) -> vec3<f32> {
+    let exponent = sky.settings.z;
+    var colour: vec3<f32>;
+
+    if (direction.y >= 0.0) {
+        colour = mix(sky.horizon.rgb, sky.zenith.rgb, pow(direction.y, exponent));
+    } else {
+        colour = mix(sky.horizon.rgb, sky.ground.rgb, pow(-direction.y, exponent));
+    }
+
+    // a disc with a soft edge, only above the horizon
+    let sun = dot(direction, normalize(sky.sun_direction.xyz));
+    let edge = sky.sun_direction.w;
+    let disc = smoothstep(edge - 0.0005, edge, sun) * step(0.0, direction.y);
+
+    return mix(colour, sky.sun_colour.rgb, disc);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = sky.inverse_view_projection * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(world.xyz / world.w);
+
+    let mode = u32(sky.settings.x);
+    var colour = sky.zenith.rgb;
+
+    if (mode == 1u) {
+        colour = gradient(direction);
+    } else if (mode == 2u) {
+        colour = textureSampleLevel(sky_texture, sky_sampler, direction, 0.0).rgb;
+    }
+
+    return vec4<f32>(colour * sky.settings.y, 1.0);
+}
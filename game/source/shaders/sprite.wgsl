@@ -0,0 +1,53 @@
+// 2d sprites and tilemap chunks from the sprite pass, seen through the renderer's orthographic camera
+
+// @see SpriteVertex in core/tilemap.rs
+struct VertexInput {
+    // world units on the xy plane
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) colour: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) colour: vec4<f32>,
+};
+
+// @see SpriteUniforms in passes/sprite_pass.rs
+struct Sprites {
+    view_projection: mat4x4<f32>,
+    // x 1.0 if the output needs gamma encoding
+    settings: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> sprites: Sprites;
+
+@group(1) @binding(0)
+var sprite_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var sprite_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = sprites.view_projection * vec4<f32>(in.position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.colour = in.colour;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var colour = in.colour * textureSample(sprite_texture, sprite_sampler, in.uv);
+
+    // srgb targets encode on write, the surface might not
+    if (sprites.settings.x > 0.0) {
+        colour = vec4<f32>(pow(clamp(colour.rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.2)), colour.a);
+    }
+
+    return colour;
+}
@@ -0,0 +1,95 @@
+// glyph quads from the text pass, @see core/text.rs
+
+struct VertexInput {
+    // pixels from the top left for screen text, a world position for labels
+    @location(0) anchor: vec3<f32>,
+    // labels only - the corner's offset from the anchor in view space, in world units
+    @location(1) offset: vec2<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) colour: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) colour: vec4<f32>,
+};
+
+struct Uniforms {
+    time: vec4<f32>,
+    projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    camera_position: vec4<f32>,
+};
+
+// @see TextUniforms in passes/text_pass.rs
+struct Text {
+    // surface width, height, 1.0 if the output needs gamma encoding
+    screen: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var atlas_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var atlas_sampler: sampler;
+
+@group(1) @binding(2)
+var<uniform> text: Text;
+
+@vertex
+fn vs_screen(in: VertexInput) -> VertexOutput {
+    let position = in.anchor.xy / text.screen.xy * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.colour = in.colour;
+
+    return out;
+}
+
+// billboarded, so labels always face the camera
+@vertex
+fn vs_world(in: VertexInput) -> VertexOutput {
+    let view_position = uniforms.view * vec4<f32>(in.anchor, 1.0) + vec4<f32>(in.offset, 0.0, 0.0);
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.projection * view_position;
+    out.uv = in.uv;
+    out.colour = in.colour;
+
+    return out;
+}
+
+// srgb targets encode on write, the surface might not
+fn output(colour: vec4<f32>, coverage: f32) -> vec4<f32> {
+    var rgb = colour.rgb;
+
+    if (text.screen.z > 0.0) {
+        rgb = pow(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.2));
+    }
+
+    return vec4<f32>(rgb, colour.a * coverage);
+}
+
+@fragment
+fn fs_bitmap(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas_texture, atlas_sampler, in.uv).r;
+
+    return output(in.colour, coverage);
+}
+
+@fragment
+fn fs_sdf(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = textureSample(atlas_texture, atlas_sampler, in.uv).r;
+
+    // about a pixel of anti-aliasing at any scale
+    let width = max(fwidth(distance) * 0.7, 0.0001);
+    let coverage = smoothstep(0.5 - width, 0.5 + width, distance);
+
+    return output(in.colour, coverage);
+}
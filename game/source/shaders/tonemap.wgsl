@@ -0,0 +1,116 @@
+// resolves the hdr scene and bloom onto the surface, @see passes/tonemap_pass.rs
+
+struct VertexOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) uv : vec2<f32>,
+};
+
+// @see TonemapUniforms in core/hdr.rs
+struct Tonemap {
+    // exposure, bloom intensity, gamma encode
+    settings : vec4<f32>,
+    // 0 none, 1 reinhard, 2 aces, 3 agx
+    tonemapper : u32,
+};
+
+@group(0) @binding(0)
+var hdr_texture : texture_2d<f32>;
+
+@group(0) @binding(1)
+var bloom_texture : texture_2d<f32>;
+
+@group(0) @binding(2)
+var linear_sampler : sampler;
+
+@group(0) @binding(3)
+var<uniform> tonemap : Tonemap;
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index : u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out : VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+fn reinhard(colour : vec3<f32>) -> vec3<f32> {
+    return colour / (1.0 + colour);
+}
+
+fn aces(colour : vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+
+    return clamp((colour * (a * colour + b)) / (colour * (c * colour + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn agx_contrast(x : vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+// agx returns display encoded values, so they're linearised again for the surface
+fn agx(colour : vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var value = inset * colour;
+    value = clamp(log2(max(value, vec3<f32>(0.0000001))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    value = (value - min_ev) / (max_ev - min_ev);
+    value = agx_contrast(value);
+    value = outset * value;
+
+    return pow(max(value, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+@fragment
+fn fs_main(in : VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(hdr_texture, linear_sampler, in.uv).rgb;
+    let bloom = textureSample(bloom_texture, linear_sampler, in.uv).rgb;
+
+    let exposed = (hdr + bloom * tonemap.settings.y) * tonemap.settings.x;
+
+    var colour = exposed;
+
+    switch tonemap.tonemapper {
+        case 1u: {
+            colour = reinhard(exposed);
+        }
+        case 2u: {
+            colour = aces(exposed);
+        }
+        case 3u: {
+            colour = agx(exposed);
+        }
+        default: {}
+    }
+
+    colour = clamp(colour, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // srgb surfaces encode on write, anything else needs it done here
+    if (tonemap.settings.z > 0.0) {
+        colour = pow(colour, vec3<f32>(1.0 / 2.2));
+    }
+
+    return vec4<f32>(colour, 1.0);
+}
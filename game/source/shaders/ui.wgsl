@@ -0,0 +1,68 @@
+// screen space ui quads from the ui pass, @see core/ui.rs
+
+struct VertexInput {
+    // pixels from the top left of the window
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) colour: vec4<f32>,
+    // 0 for colour textures, 1 for bitmap glyph coverage, 2 for sdf glyphs
+    @location(3) mode: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) colour: vec4<f32>,
+    @location(2) @interpolate(flat) mode: u32,
+};
+
+// @see UiUniforms in passes/ui_pass.rs
+struct Ui {
+    // surface width, height, 1.0 if the output needs gamma encoding
+    screen: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ui: Ui;
+
+@group(1) @binding(0)
+var ui_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var ui_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let position = in.position / ui.screen.xy * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.colour = in.colour;
+    out.mode = in.mode;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(ui_texture, ui_sampler, in.uv);
+
+    // sampled before branching, so derivatives stay in uniform control flow
+    let width = max(fwidth(texel.r) * 0.7, 0.0001);
+
+    var colour = in.colour * texel;
+
+    if (in.mode == 1u) {
+        colour = vec4<f32>(in.colour.rgb, in.colour.a * texel.r);
+    } else if (in.mode == 2u) {
+        colour = vec4<f32>(in.colour.rgb, in.colour.a * smoothstep(0.5 - width, 0.5 + width, texel.r));
+    }
+
+    // srgb targets encode on write, the surface might not
+    if (ui.screen.z > 0.0) {
+        colour = vec4<f32>(pow(clamp(colour.rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.2)), colour.a);
+    }
+
+    return colour;
+}
@@ -1,25 +1,23 @@
+#include "common/vertex_output.wgsl"
+#include "common/uniforms.wgsl"
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
     @location(2) should_wave: u32,
+    @location(3) uv: vec2<f32>,
+    @location(4) normal: vec3<f32>,
 };
 
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
-};
-
-struct Uniforms {
-    time: vec4<f32>,
-    projection: mat4x4<f32>,
-    view: mat4x4<f32>,
+struct Transform {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    // x: receives shadows, y: entity id, z: lod fade as f32 bits, w: lod fade mode
+    flags: vec4<u32>,
 };
 
-@group(0) @binding(0)
-var<uniform> uniforms: Uniforms;
-
 @group(1) @binding(0)
-var<uniform> transform: mat4x4<f32>;
+var<uniform> transform: Transform;
 
 @vertex
 fn vs_main(
@@ -35,10 +33,17 @@ fn vs_main(
         position.x += sin(uniforms.time[0] * 4.0 + position.y * 2.0) * 0.1 * sin(position.y * 2.0) * 0.5;
     }
     
+    let world_position = transform.model * vec4<f32>(position, 1.0);
 
-    out.clip_position = uniforms.projection * uniforms.view * transform * vec4<f32>(position, 1.0);
+    out.clip_position = uniforms.projection * uniforms.view * world_position;
     
     out.color = model.color;
+    out.uv = model.uv;
+    out.world_position = world_position.xyz;
+    out.world_normal = (transform.normal * vec4<f32>(model.normal, 0.0)).xyz;
+    out.receives_shadows = transform.flags.x;
+    out.lod_fade = vec2<f32>(bitcast<f32>(transform.flags.z), f32(transform.flags.w));
+    out.entity_id = transform.flags.y + 1u;
     
     return out;
-}
\ No newline at end of file
+}
@@ -0,0 +1,122 @@
+use crate::core::compute;
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::{game, renderer};
+use crate::utils::log;
+
+const TYPES: [ComponentType; 3] = [
+    ComponentType::Boid,
+    ComponentType::Transform,
+    ComponentType::Movement,
+];
+
+/// one boid in the compute buffer - must match Boid in boids.wgsl
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Boid {
+    position: [f32; 4],
+    velocity: [f32; 4],
+    weights: [f32; 4],
+    result: [f32; 4],
+}
+
+/// must match Settings in boids.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct BoidSettings {
+    count: u32,
+    delta_time: f32,
+    _padding: [u32; 2],
+}
+
+/// Flocks boids on the gpu - their steering is read back a frame or so later, and added to their
+/// movement before MovementSystem moves them
+pub struct BoidsSystem {}
+
+impl game::System for BoidsSystem {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let compute = renderer.compute_mut();
+
+        // the last dispatch's steering, added to the velocities the boids have now rather than
+        // replacing them with ones from the frame they were gathered in
+        compute.scatter::<Boid>("boids", world, &TYPES, |components, boid| {
+            let max_speed = components.iter().find_map(|component| match component {
+                ComponentEnum::Boid(settings) => Some(settings.max_speed),
+                _ => None,
+            });
+
+            for component in components.iter_mut() {
+                if let ComponentEnum::Movement(movement) = component {
+                    let steered = glam::Vec3::from_array(movement.velocity)
+                        + glam::Vec3::new(boid.result[0], boid.result[1], boid.result[2]);
+
+                    movement.velocity = match max_speed {
+                        Some(max_speed) => steered.clamp_length_max(max_speed).to_array(),
+                        None => steered.to_array(),
+                    };
+                }
+            }
+        });
+
+        // one dispatch in flight at a time
+        if compute.reading_back("boids") {
+            return;
+        }
+
+        let count = world.get_entities_with_components(&TYPES).len() as u32;
+
+        if count == 0 {
+            return;
+        }
+
+        if let Err(e) = compute.load_kernel("boids", include_str!("../shaders/boids.wgsl")) {
+            log::error(&e);
+            return;
+        }
+
+        compute.gather("boids", world, &TYPES, |components| {
+            let mut boid = Boid::default();
+
+            for component in components {
+                match component {
+                    ComponentEnum::Boid(settings) => {
+                        boid.position[3] = settings.max_speed;
+                        boid.velocity[3] = settings.neighbour_radius;
+                        boid.weights = [
+                            settings.separation,
+                            settings.alignment,
+                            settings.cohesion,
+                            0.0,
+                        ];
+                    }
+                    ComponentEnum::Transform(transform) => {
+                        boid.position[..3].copy_from_slice(&transform.position);
+                    }
+                    ComponentEnum::Movement(movement) => {
+                        boid.velocity[..3].copy_from_slice(&movement.velocity);
+                    }
+                    _ => {}
+                }
+            }
+
+            boid
+        });
+
+        compute.write_buffer(
+            "boid_settings",
+            &[BoidSettings {
+                count,
+                delta_time: world.state().delta_time,
+                _padding: [0; 2],
+            }],
+        );
+
+        let workgroups = [count.div_ceil(compute::WORKGROUP_SIZE), 1, 1];
+
+        if let Err(e) = compute
+            .dispatch("boids", &["boids", "boid_settings"], workgroups)
+            .and_then(|_| compute.read_back("boids"))
+        {
+            log::error(&e);
+        }
+    }
+}
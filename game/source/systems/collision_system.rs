@@ -1,13 +1,16 @@
-use crate::components::{collider_component, mesh_component, transform_component};
-use crate::core::game::{ComponentEnum, ComponentType};
+use crate::components::{
+    collider_component, mesh_component, tilemap_component, transform_component,
+};
+use crate::core::game::{ComponentEnum, ComponentType, EntityId};
 use crate::core::geometry;
+use crate::core::tilemap::TileContact;
 use crate::core::{game, renderer};
 
 pub struct CollisionSystem {}
 
 impl CollisionSystem {
     /// calculate the aabb for a mesh with its transform applied
-    fn calculate_aabb(
+    pub fn calculate_aabb(
         mesh_component: &mesh_component::MeshComponent,
         transform_component: &transform_component::TransformComponent,
     ) -> geometry::BoundingBox {
@@ -33,6 +36,143 @@ impl CollisionSystem {
         geometry::BoundingBox { min, max }
     }
 
+    /// calculate the aabb for a box collider around its transform
+    fn calculate_box_aabb(
+        half_extents: geometry::Vector3,
+        transform_component: &transform_component::TransformComponent,
+    ) -> geometry::BoundingBox {
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+
+        for i in 0..3 {
+            let extent = half_extents[i] * transform_component.scale[i].abs();
+            min[i] = transform_component.position[i] - extent;
+            max[i] = transform_component.position[i] + extent;
+        }
+
+        geometry::BoundingBox { min, max }
+    }
+
+    /// push an aabb out of the solid tiles of every tilemap, one tile at a time along the axis it
+    /// overlaps least - never into a solid neighbour, so runs of tiles act as one surface and boxes
+    /// slide along them without catching on the seams. returns how far it moved, and the tiles it
+    /// touched
+    fn resolve_tiles(
+        world: &game::World,
+        tilemaps: &[EntityId],
+        aabb: &geometry::BoundingBox,
+    ) -> ([f32; 2], Vec<TileContact>) {
+        let mut min = [aabb.min[0], aabb.min[1]];
+        let mut max = [aabb.max[0], aabb.max[1]];
+        let mut offset = [0.0, 0.0];
+        let mut contacts = Vec::new();
+
+        for &tilemap_id in tilemaps {
+            let tilemap =
+                match world.get_entity_component_by_type(tilemap_id, ComponentType::Tilemap) {
+                    Some(ComponentEnum::Tilemap(tilemap)) => tilemap,
+                    _ => continue,
+                };
+
+            let transform =
+                match world.get_entity_component_by_type(tilemap_id, ComponentType::Transform) {
+                    Some(ComponentEnum::Transform(transform)) => transform,
+                    _ => continue,
+                };
+
+            let [first, last] =
+                match tilemap.tiles_overlapping(transform.position, transform.scale, [min, max]) {
+                    Some(range) => range,
+                    None => continue,
+                };
+
+            for y in first[1]..=last[1] {
+                for x in first[0]..=last[0] {
+                    if !tilemap.is_solid(x, y) {
+                        continue;
+                    }
+
+                    let [tile_min, tile_max] =
+                        tilemap.tile_bounds(transform.position, transform.scale, x, y);
+
+                    // an earlier push may have moved it clear already
+                    let overlap = [
+                        max[0].min(tile_max[0]) - min[0].max(tile_min[0]),
+                        max[1].min(tile_max[1]) - min[1].max(tile_min[1]),
+                    ];
+
+                    if overlap[0] <= 0.0 || overlap[1] <= 0.0 {
+                        continue;
+                    }
+
+                    let signs = [0, 1].map(|axis| {
+                        let centre = (min[axis] + max[axis]) * 0.5;
+                        let tile_centre = (tile_min[axis] + tile_max[axis]) * 0.5;
+
+                        if centre < tile_centre {
+                            -1.0
+                        } else {
+                            1.0
+                        }
+                    });
+
+                    // the edge between two solid tiles is inside the surface, not on it
+                    let open = [
+                        !Self::neighbour_solid(tilemap, x, y, [signs[0], 0.0]),
+                        !Self::neighbour_solid(tilemap, x, y, [0.0, signs[1]]),
+                    ];
+
+                    let axis = match open {
+                        [true, false] => 0,
+                        [false, true] => 1,
+                        _ if overlap[0] < overlap[1] => 0,
+                        _ => 1,
+                    };
+                    let sign = signs[axis];
+
+                    min[axis] += overlap[axis] * sign;
+                    max[axis] += overlap[axis] * sign;
+                    offset[axis] += overlap[axis] * sign;
+
+                    let mut normal = [0.0, 0.0];
+                    normal[axis] = sign;
+
+                    contacts.push(TileContact {
+                        tilemap: tilemap_id,
+                        tile: [x, y],
+                        normal,
+                        depth: overlap[axis],
+                    });
+                }
+            }
+        }
+
+        (offset, contacts)
+    }
+
+    /// whether the tile next to a tile, one step along a direction, is solid
+    fn neighbour_solid(
+        tilemap: &tilemap_component::TilemapComponent,
+        x: u32,
+        y: u32,
+        direction: [f32; 2],
+    ) -> bool {
+        let step = |tile: u32, direction: f32| {
+            if direction < 0.0 {
+                tile.checked_sub(1)
+            } else if direction > 0.0 {
+                tile.checked_add(1)
+            } else {
+                Some(tile)
+            }
+        };
+
+        match (step(x, direction[0]), step(y, direction[1])) {
+            (Some(x), Some(y)) => tilemap.is_solid(x, y),
+            _ => false,
+        }
+    }
+
     /// check if two bounding boxes intersect
     fn bounding_boxes_intersect(a: &geometry::BoundingBox, b: &geometry::BoundingBox) -> bool {
         for i in 0..3 {
@@ -54,6 +194,33 @@ impl game::System for CollisionSystem {
             ComponentType::Mesh,
         ]);
 
+        // box colliders don't need a mesh
+        {
+            let box_entities = world
+                .get_entities_with_components(&[ComponentType::Collider, ComponentType::Transform]);
+
+            for entity_id in box_entities {
+                let mut components = world.get_entity_components_mut(
+                    entity_id,
+                    &[ComponentType::Collider, ComponentType::Transform],
+                );
+
+                // components come back in the order they were added to the entity
+                if let [ComponentEnum::Collider(collider), ComponentEnum::Transform(transform)]
+                | [ComponentEnum::Transform(transform), ComponentEnum::Collider(collider)] =
+                    components.as_mut_slice()
+                {
+                    if let (true, Some(half_extents)) =
+                        (collider.needs_aabb_update, collider.half_extents)
+                    {
+                        collider.aabb =
+                            Some(CollisionSystem::calculate_box_aabb(half_extents, transform));
+                        collider.needs_aabb_update = false;
+                    }
+                }
+            }
+        }
+
         // loop through all entities with colliders, and update their aabbs if needed
         {
             for &entity_id in entities.iter() {
@@ -79,6 +246,71 @@ impl game::System for CollisionSystem {
             }
         }
 
+        // push colliders on moving entities out of solid tiles, and stop them moving into them
+        {
+            let tilemaps = world
+                .get_entities_with_components(&[ComponentType::Tilemap, ComponentType::Transform]);
+            let colliders = world
+                .get_entities_with_components(&[ComponentType::Collider, ComponentType::Transform]);
+
+            for entity_id in colliders {
+                let resolved =
+                    match world.get_entity_component_by_type(entity_id, ComponentType::Collider) {
+                        Some(ComponentEnum::Collider(collider)) => match &collider.aabb {
+                            Some(aabb) if !tilemaps.is_empty() => {
+                                Some(CollisionSystem::resolve_tiles(world, &tilemaps, aabb))
+                            }
+                            _ => None,
+                        },
+                        _ => continue,
+                    };
+
+                let (offset, contacts) = resolved.unwrap_or_default();
+                let moves = world
+                    .get_entity_component_by_type(entity_id, ComponentType::Movement)
+                    .is_some();
+
+                // only things that move are pushed - tiles are static, and so is anything else
+                if moves && offset != [0.0, 0.0] {
+                    if let Some(ComponentEnum::Transform(transform)) = world
+                        .component_storage_mut()
+                        .get_component_mut(entity_id, |c| matches!(c, ComponentEnum::Transform(_)))
+                    {
+                        transform.translate([offset[0], offset[1], 0.0]);
+                    }
+
+                    if let Some(ComponentEnum::Movement(movement)) = world
+                        .component_storage_mut()
+                        .get_component_mut(entity_id, |c| matches!(c, ComponentEnum::Movement(_)))
+                    {
+                        for contact in contacts.iter() {
+                            for axis in 0..2 {
+                                if movement.velocity[axis] * contact.normal[axis] < 0.0 {
+                                    movement.velocity[axis] = 0.0;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(ComponentEnum::Collider(collider)) = world
+                    .component_storage_mut()
+                    .get_component_mut(entity_id, |c| matches!(c, ComponentEnum::Collider(_)))
+                {
+                    if moves {
+                        if let Some(aabb) = collider.aabb.as_mut() {
+                            for (axis, distance) in offset.iter().enumerate() {
+                                aabb.min[axis] += distance;
+                                aabb.max[axis] += distance;
+                            }
+                        }
+                    }
+
+                    collider.tile_contacts = contacts;
+                }
+            }
+        }
+
         // loop through all entities with colliders, and check for collisions
         {
             for &entity_id in entities.iter() {
@@ -0,0 +1,29 @@
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::{game, renderer};
+
+/// Starts the frame's debug drawing, and draws every collider's aabb when that's toggled on -
+/// runs before MeshRenderer so everything drawn this frame is shown
+pub struct DebugDrawSystem {}
+
+impl game::System for DebugDrawSystem {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let time = world.state().total_time;
+        renderer.debug_draw_mut().begin_frame(time);
+
+        if !renderer.debug_draw().draw_colliders {
+            return;
+        }
+
+        let colour = renderer.debug_draw().collider_colour;
+
+        for entity_id in world.get_entities_with_components(&[ComponentType::Collider]) {
+            if let Some(ComponentEnum::Collider(collider)) =
+                world.get_entity_component_by_type(entity_id, ComponentType::Collider)
+            {
+                if let Some(aabb) = &collider.aabb {
+                    renderer.debug_draw_mut().aabb(aabb, colour, 0.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::core::game::{ComponentEnum, ComponentType, EntityId};
+use crate::core::text::{FontId, TextAlign, TextStyle};
+use crate::core::{game, renderer};
+
+/// keys shown along the bottom of the window
+const CONTROLS: &str = "F1 colliders  F2 wireframe  F3 normals  F4 depth  F5 entity ids  \
+                        F6 profiler  F7 export trace  F8 tonemapper  F9 sky  F10 shadows  \
+                        F11 shader reload  F12 pause profiling  Esc quit";
+
+/// Draws the frame rate and the debug controls as screen text - runs before MeshRenderer so the
+/// text is drawn this frame
+pub struct HudSystem {
+    pub font: FontId,
+}
+
+impl game::System for HudSystem {
+    fn run(&self, _world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let size = renderer.size();
+        let [width, height] = [size.width as f32, size.height as f32];
+
+        let frame_time = renderer.profiler().frame_time();
+        let fps = if frame_time > 0.0 {
+            1000.0 / frame_time
+        } else {
+            0.0
+        };
+
+        let fps_style = TextStyle::new(self.font, 16.0)
+            .with_colour([1.0, 1.0, 0.6, 1.0])
+            .with_align(TextAlign::Right);
+
+        renderer.text_mut().draw(
+            &format!("{:.0} fps ({:.1} ms)", fps, frame_time),
+            [width - 8.0, 8.0],
+            &fps_style,
+        );
+
+        // last frame's, as the meshes haven't been drawn yet this frame
+        let stats = renderer.render_stats();
+
+        renderer.text_mut().draw(
+            &format!("{} drawn, {} culled", stats.drawn, stats.culled),
+            [width - 8.0, 28.0],
+            &fps_style,
+        );
+
+        // wraps onto more lines as the window narrows, kept clear of the bottom edge
+        let controls_style = TextStyle::new(self.font, 14.0)
+            .with_colour([1.0, 1.0, 1.0, 0.7])
+            .with_align(TextAlign::Centre)
+            .with_max_width((width - 16.0).max(1.0));
+
+        let [_, controls_height] = renderer.text().size(CONTROLS, &controls_style);
+
+        renderer.text_mut().draw(
+            CONTROLS,
+            [width * 0.5, height - controls_height - 8.0],
+            &controls_style,
+        );
+    }
+}
+
+/// Toggles the profiler overlay and collider drawing from the hud's buttons - runs after UiSystem,
+/// which sets whether they were clicked
+pub struct HudButtonSystem {
+    pub profiler_button: EntityId,
+    pub colliders_button: EntityId,
+}
+
+impl HudButtonSystem {
+    fn clicked(world: &game::World, button: EntityId) -> bool {
+        matches!(
+            world.get_entity_component_by_type(button, ComponentType::UiNode),
+            Some(ComponentEnum::UiNode(node)) if node.clicked
+        )
+    }
+}
+
+impl game::System for HudButtonSystem {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        if Self::clicked(world, self.profiler_button) {
+            let profiler = renderer.profiler_mut();
+            profiler.set_overlay(!profiler.overlay());
+        }
+
+        if Self::clicked(world, self.colliders_button) {
+            let debug_draw = renderer.debug_draw_mut();
+            debug_draw.draw_colliders = !debug_draw.draw_colliders;
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::components::light_component::LightKind;
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::{game, renderer, shadows};
+use glam::Vec3;
+
+/// System to gather light components into the renderer's light buffer each frame
+pub struct LightGatherer {}
+
+impl game::System for LightGatherer {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let entities =
+            world.get_entities_with_components(&[ComponentType::Light, ComponentType::Transform]);
+
+        let mut lights = Vec::with_capacity(entities.len());
+
+        // shadow casting lights claim layers of the shadow map array in turn
+        let mut shadow_view_projections: Vec<[f32; 16]> = Vec::new();
+        let mut cascade_splits: Vec<f32> = Vec::new();
+
+        for entity_id in entities {
+            let light = world.get_entity_component_by_type(entity_id, ComponentType::Light);
+            let transform = world.get_entity_component_by_type(entity_id, ComponentType::Transform);
+
+            if let (Some(ComponentEnum::Light(light)), Some(ComponentEnum::Transform(transform))) =
+                (light, transform)
+            {
+                // lights point down their local -Z axis
+                let direction = transform
+                    .model_matrix
+                    .transform_vector3(Vec3::NEG_Z)
+                    .normalize_or_zero();
+
+                let position = Vec3::from_array(transform.position);
+
+                // matrices for each shadow map this light renders into
+                let light_shadow_view_projections = match light.kind {
+                    _ if !light.casts_shadows => vec![],
+                    LightKind::Directional => {
+                        // only one directional light gets cascades, they share the split distances
+                        if !cascade_splits.is_empty() {
+                            vec![]
+                        } else {
+                            let (matrices, splits) = shadows::directional_cascades(
+                                renderer.camera(),
+                                renderer.aspect_ratio(),
+                                direction,
+                                renderer.shadow_settings(),
+                            );
+
+                            cascade_splits = splits;
+                            matrices
+                        }
+                    }
+                    LightKind::Spot { outer_angle, .. } => vec![shadows::spot_view_projection(
+                        position,
+                        direction,
+                        outer_angle,
+                        light.range,
+                    )],
+                    // @todo point light shadows need cube maps
+                    LightKind::Point => vec![],
+                };
+
+                let shadow = if !light_shadow_view_projections.is_empty()
+                    && shadow_view_projections.len() + light_shadow_view_projections.len()
+                        <= shadows::MAX_SHADOW_MAPS
+                {
+                    let first_layer = shadow_view_projections.len();
+
+                    shadow_view_projections.extend(
+                        light_shadow_view_projections
+                            .iter()
+                            .map(|matrix| matrix.to_cols_array()),
+                    );
+
+                    [
+                        first_layer as f32,
+                        light_shadow_view_projections.len() as f32,
+                        0.0,
+                        0.0,
+                    ]
+                } else {
+                    [-1.0, 0.0, 0.0, 0.0]
+                };
+
+                let (kind, spot) = match light.kind {
+                    LightKind::Directional => (renderer::LIGHT_KIND_DIRECTIONAL, [0.0; 4]),
+                    LightKind::Point => (renderer::LIGHT_KIND_POINT, [0.0; 4]),
+                    LightKind::Spot {
+                        inner_angle,
+                        outer_angle,
+                    } => (
+                        renderer::LIGHT_KIND_SPOT,
+                        [inner_angle.cos(), outer_angle.cos(), 0.0, 0.0],
+                    ),
+                };
+
+                lights.push(renderer::LightUniforms {
+                    position: [
+                        transform.position[0],
+                        transform.position[1],
+                        transform.position[2],
+                        kind,
+                    ],
+                    direction: [direction.x, direction.y, direction.z, light.range],
+                    colour: [
+                        light.colour[0],
+                        light.colour[1],
+                        light.colour[2],
+                        light.intensity,
+                    ],
+                    spot,
+                    shadow,
+                });
+            }
+        }
+
+        renderer.update_lights(&lights);
+        renderer.update_shadows(&shadow_view_projections, &cascade_splits);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::components::material_component;
+use crate::core::{game, renderer, texture};
+use crate::utils::log;
+
+/// System to buffer material uniforms and bind groups for rendering
+pub struct MaterialBufferer {}
+
+impl game::System for MaterialBufferer {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let material_components = world.get_components_by_type_mut(game::ComponentType::Material);
+
+        for (_, component) in material_components {
+            if let game::ComponentEnum::Material(material) = component {
+                if !material.needs_rebuffer {
+                    continue;
+                }
+
+                // the uniform buffer is reused when only the values change
+                if material.uniform_buffer.is_none() {
+                    material.uniform_buffer =
+                        Some(renderer.device().lock().unwrap().create_buffer(
+                            &wgpu::BufferDescriptor {
+                                label: Some("Material Uniform Buffer"),
+                                size: std::mem::size_of::<material_component::MaterialUniforms>()
+                                    as wgpu::BufferAddress,
+                                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                                mapped_at_creation: false,
+                            },
+                        ));
+                }
+
+                // resolve each slot's texture, falling back to the slot's default if it can't be loaded
+                let mut textures = Vec::with_capacity(material_component::MATERIAL_TEXTURE_COUNT);
+
+                for slot in material_component::MaterialTextureSlot::ALL {
+                    let default = renderer.default_material_texture(slot).clone();
+
+                    let options = texture::TextureOptions {
+                        colour_space: slot.colour_space(),
+                        ..material.texture_options
+                    };
+
+                    textures.push(match &material.textures[slot.index()] {
+                        Some(path) => match renderer.load_texture(path, options) {
+                            Ok(texture) => texture,
+                            Err(e) => {
+                                log::error(&e);
+                                default
+                            }
+                        },
+                        None => default,
+                    });
+                }
+
+                if let Some(uniform_buffer) = &material.uniform_buffer {
+                    material.bind_group =
+                        Some(renderer.create_material_bind_group(uniform_buffer, &textures));
+
+                    renderer.queue().lock().unwrap().write_buffer(
+                        uniform_buffer,
+                        0,
+                        bytemuck::cast_slice(&[material.uniforms()]),
+                    );
+                }
+
+                material.needs_rebuffer = false;
+            }
+        }
+    }
+}
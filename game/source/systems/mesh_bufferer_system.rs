@@ -3,53 +3,89 @@ use crate::core::{game, geometry, renderer};
 /// System to buffer meshes for rendering
 pub struct MeshBufferer {}
 
+impl MeshBufferer {
+    /// Vertex and index buffers filled with a mesh's data
+    fn create_buffers(
+        device: &wgpu::Device,
+        vertices: &[geometry::Vertex],
+        indices: &[u16],
+    ) -> (wgpu::Buffer, wgpu::Buffer) {
+        // storage too, so the debug wireframe can pull vertices without line polygons
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Vertex Buffer"),
+            size: std::mem::size_of_val(vertices) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+
+        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Index Buffer"),
+            size: std::mem::size_of_val(indices) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::INDEX
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+
+        // Write the data to the buffers
+        vertex_buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(bytemuck::cast_slice(vertices));
+        vertex_buffer.unmap();
+
+        index_buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(bytemuck::cast_slice(indices));
+        index_buffer.unmap();
+
+        (vertex_buffer, index_buffer)
+    }
+}
+
 impl game::System for MeshBufferer {
     fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
         let mesh_components = world.get_components_by_type_mut(game::ComponentType::Mesh);
 
         for (_, component) in mesh_components {
             if let game::ComponentEnum::Mesh(mesh_component) = component {
-                if mesh_component.vertex_buffer.is_none() && mesh_component.needs_rebuffer {
-                    let device = renderer.device();
-                    let locked_device = device.lock().unwrap();
+                if !mesh_component.needs_rebuffer {
+                    continue;
+                }
 
+                let device = renderer.device();
+                let locked_device = device.lock().unwrap();
+
+                if mesh_component.vertex_buffer.is_none() {
                     let vertices = mesh_component.last_vertices.as_ref().unwrap();
                     let indices = mesh_component.last_indices.as_ref().unwrap();
 
-                    let vertex_buffer = locked_device.create_buffer(&wgpu::BufferDescriptor {
-                        label: Some("Vertex Buffer"),
-                        size: (std::mem::size_of::<geometry::Vertex>() * vertices.len())
-                            as wgpu::BufferAddress,
-                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                        mapped_at_creation: true,
-                    });
-
-                    let index_buffer = locked_device.create_buffer(&wgpu::BufferDescriptor {
-                        label: Some("Index Buffer"),
-                        size: (std::mem::size_of::<u16>() * indices.len()) as wgpu::BufferAddress,
-                        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                        mapped_at_creation: true,
-                    });
-
-                    // Write the data to the buffers
-                    vertex_buffer
-                        .slice(..)
-                        .get_mapped_range_mut()
-                        .copy_from_slice(bytemuck::cast_slice(vertices.as_slice()));
-                    vertex_buffer.unmap();
-
-                    index_buffer
-                        .slice(..)
-                        .get_mapped_range_mut()
-                        .copy_from_slice(bytemuck::cast_slice(indices.as_slice()));
-                    index_buffer.unmap();
+                    let (vertex_buffer, index_buffer) =
+                        Self::create_buffers(&locked_device, vertices, indices);
 
                     mesh_component.vertex_buffer = Some(vertex_buffer);
                     mesh_component.index_buffer = Some(index_buffer);
-
-                    mesh_component.needs_rebuffer = false;
                     mesh_component.num_indices = indices.len() as u32;
                 }
+
+                // levels of detail are buffered alongside the mesh
+                for level in mesh_component.lods.iter_mut() {
+                    if level.vertex_buffer.is_some() {
+                        continue;
+                    }
+
+                    let (vertex_buffer, index_buffer) =
+                        Self::create_buffers(&locked_device, &level.vertices, &level.indices);
+
+                    level.vertex_buffer = Some(vertex_buffer);
+                    level.index_buffer = Some(index_buffer);
+                    level.num_indices = level.indices.len() as u32;
+                }
+
+                mesh_component.needs_rebuffer = false;
             }
         }
     }
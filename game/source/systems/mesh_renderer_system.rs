@@ -1,35 +1,271 @@
-use crate::core::{game, renderer};
-use glam::{Mat4, Vec3};
+use crate::components::material_component::MaterialPipelineKey;
+use crate::components::mesh_component::MeshComponent;
+use crate::components::transform_component::TransformComponent;
+use crate::core::render_graph::{self, DrawLists, MeshDraw};
+use crate::core::{game, geometry, renderer};
+use crate::systems::collision_system;
+use crate::utils::log;
+
+// how a level of detail is dithered while cross-fading, @see vertex.wgsl
+const LOD_FADE_NONE: u32 = 0;
+const LOD_FADE_IN: u32 = 1;
+const LOD_FADE_OUT: u32 = 2;
 
 pub struct MeshRenderer {}
 
+impl MeshRenderer {
+    /// The mesh's world space bounds, calculated again only if it's been moved since they were
+    /// last calculated - None for meshes without vertices on the cpu
+    fn update_bounds(
+        mesh: &mut MeshComponent,
+        transform: &TransformComponent,
+    ) -> Option<geometry::BoundingBox> {
+        mesh.last_vertices.as_ref()?;
+
+        let key = [transform.position, transform.rotation, transform.scale];
+
+        match mesh.bounds {
+            Some((bounds_key, aabb)) if bounds_key == key => Some(aabb),
+            _ => {
+                let aabb = collision_system::CollisionSystem::calculate_aabb(mesh, transform);
+                mesh.bounds = Some((key, aabb));
+                Some(aabb)
+            }
+        }
+    }
+
+    /// Move a mesh to the level of detail for its distance and screen size, starting a fade from
+    /// the level it was at if it has one
+    fn update_lod(mesh: &mut MeshComponent, distance: f32, screen_size: f32, delta_time: f32) {
+        let lod = mesh.select_lod(distance, screen_size);
+
+        if lod != mesh.lod {
+            mesh.previous_lod = if mesh.lod_fade_time > 0.0 {
+                Some(mesh.lod)
+            } else {
+                None
+            };
+            mesh.lod = lod;
+            mesh.lod_fade = 0.0;
+        }
+
+        if mesh.previous_lod.is_some() {
+            mesh.lod_fade += delta_time / mesh.lod_fade_time.max(f32::EPSILON);
+
+            if mesh.lod_fade >= 1.0 {
+                mesh.previous_lod = None;
+            }
+        }
+
+        if mesh.previous_lod.is_none() {
+            mesh.lod_fade = 1.0;
+        }
+    }
+}
+
 impl game::System for MeshRenderer {
     fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
         let state = world.state();
+        let time = [state.total_time, state.delta_time, 0.0, 0.0];
+        let delta_time = state.delta_time;
 
-        // Create view and projection matrices
-        let view = Mat4::look_at_rh(
-            Vec3::new(0.0, 0.0, 5.0), // camera position
-            Vec3::ZERO,               // look at point
-            Vec3::Y,                  // up vector
-        );
-
-        let projection = Mat4::perspective_rh(
-            45.0_f32.to_radians(),
-            renderer.size().width as f32 / renderer.size().height as f32,
-            0.1,
-            100.0,
-        );
+        // Create view and projection matrices from the renderer's camera
+        let camera = renderer.camera();
+        let camera_position = camera.position;
+        let frustum = camera.frustum(renderer.aspect_ratio());
+        let frustum_culling = renderer.frustum_culling();
+        let view = camera.view_matrix();
+        let projection = camera.projection_matrix(renderer.aspect_ratio());
+        // bounding sphere radius over distance to the fraction of the screen's height it covers
+        let screen_scale = 1.0 / (camera.fov_y * 0.5).tan();
 
         renderer.update_global_uniforms(renderer::GlobalUniforms {
-            time: [state.total_time, state.delta_time, 0.0, 0.0],
+            time,
             view: view.to_cols_array(),
             projection: projection.to_cols_array(),
+            camera_position: camera.position.extend(1.0).to_array(),
         });
 
+        // Get entities that have both Mesh and Transform components
+        let entities = world.get_entities_with_components(&[
+            game::ComponentType::Mesh,
+            game::ComponentType::Transform,
+        ]);
+
+        // bring each mesh's bounds up to date and pick its level of detail before anything is drawn
+        for &entity_id in entities.iter() {
+            let mut components = world.get_entity_components_mut(
+                entity_id,
+                &[game::ComponentType::Mesh, game::ComponentType::Transform],
+            );
+
+            // components come back in the order they were added to the entity
+            let (mesh, transform) = match components.as_mut_slice() {
+                [game::ComponentEnum::Mesh(mesh), game::ComponentEnum::Transform(transform)]
+                | [game::ComponentEnum::Transform(transform), game::ComponentEnum::Mesh(mesh)] => {
+                    (mesh, transform)
+                }
+                _ => continue,
+            };
+
+            let aabb = match Self::update_bounds(mesh, transform) {
+                Some(aabb) if !mesh.lods.is_empty() => aabb,
+                _ => continue,
+            };
+
+            let min = glam::Vec3::from_array(aabb.min);
+            let max = glam::Vec3::from_array(aabb.max);
+
+            let distance = ((min + max) * 0.5).distance(camera_position);
+            let radius = (max - min).length() * 0.5;
+            let screen_size = radius * screen_scale / distance.max(f32::EPSILON);
+
+            Self::update_lod(mesh, distance, screen_size, delta_time);
+        }
+
+        // pick up shader edits before the pipelines are used
+        renderer.reload_shaders();
+
+        // make sure every material variant we're about to draw has a pipeline
+        renderer.prepare_material_pipeline(&MaterialPipelineKey::default());
+
+        for &entity_id in entities.iter() {
+            if let Some(game::ComponentEnum::Material(material)) =
+                world.get_entity_component_by_type(entity_id, game::ComponentType::Material)
+            {
+                renderer.prepare_material_pipeline(&material.pipeline_key());
+            }
+        }
+
+        // a transform for each level drawn, two while cross-fading
+        let transform_slots = entities
+            .iter()
+            .map(|&entity_id| {
+                match world.get_entity_component_by_type(entity_id, game::ComponentType::Mesh) {
+                    Some(game::ComponentEnum::Mesh(mesh)) if mesh.previous_lod.is_some() => 2,
+                    _ => 1,
+                }
+            })
+            .sum();
+
+        renderer.reserve_transform_slots(transform_slots);
+
+        // write every buffered mesh's transform up front, so the graph's passes can share them
+        let mut draws = DrawLists::default();
+        let mut transparent_draws = Vec::new();
+        let mut stats = renderer::RenderStats::default();
+        let mut current_transform_offset = 0;
+
+        for entity_id in entities {
+            let mesh = world.get_entity_component_by_type(entity_id, game::ComponentType::Mesh);
+            let transform =
+                world.get_entity_component_by_type(entity_id, game::ComponentType::Transform);
+
+            if let (
+                Some(game::ComponentEnum::Mesh(mesh)),
+                Some(game::ComponentEnum::Transform(transform)),
+            ) = (mesh, transform)
+            {
+                if mesh.vertex_buffer.is_none() || mesh.index_buffer.is_none() {
+                    continue;
+                }
+
+                // while cross-fading, both levels are drawn with complementary dither patterns
+                let levels = match mesh.previous_lod {
+                    Some(previous_lod) => {
+                        vec![(mesh.lod, LOD_FADE_IN), (previous_lod, LOD_FADE_OUT)]
+                    }
+                    None => vec![(mesh.lod, LOD_FADE_NONE)],
+                };
+
+                let mut mesh_draws = Vec::with_capacity(levels.len());
+
+                for (lod, fade) in levels {
+                    let transform_offset = current_transform_offset;
+                    current_transform_offset +=
+                        renderer::Renderer::get_transform_aligned_size() as u32;
+
+                    // Update transform uniforms with the model matrix
+                    renderer.update_transform_uniforms_at_offset(
+                        renderer::TransformUniforms {
+                            model: transform.matrix_array(),
+                            normal: transform.normal_matrix_array(),
+                            flags: [
+                                mesh.receives_shadows as u32,
+                                entity_id,
+                                mesh.lod_fade.to_bits(),
+                                fade,
+                            ],
+                        },
+                        transform_offset as wgpu::BufferAddress,
+                    );
+
+                    mesh_draws.push(MeshDraw {
+                        entity_id,
+                        transform_offset,
+                        lod,
+                    });
+                }
+
+                let transparent = match world
+                    .get_entity_component_by_type(entity_id, game::ComponentType::Material)
+                {
+                    Some(game::ComponentEnum::Material(material)) => {
+                        material.blend_mode.is_transparent()
+                    }
+                    _ => false,
+                };
+
+                // off screen meshes can still cast shadows into view, from their current level -
+                // blended ones let light through, so cast none
+                if !transparent {
+                    draws.shadow_casters.push(mesh_draws[0]);
+                }
+
+                if let (true, Some((_, aabb))) = (frustum_culling, &mesh.bounds) {
+                    if !frustum.intersects_aabb(aabb) {
+                        stats.culled += 1;
+                        continue;
+                    }
+                }
+
+                stats.drawn += 1;
+
+                if transparent {
+                    let distance = glam::Vec3::from_slice(&transform.position)
+                        .distance_squared(camera_position);
+
+                    for draw in mesh_draws {
+                        transparent_draws.push((draw, distance));
+                    }
+                } else {
+                    draws.opaque.extend(mesh_draws);
+                }
+            }
+        }
+
+        // blended surfaces are drawn furthest first so nearer ones composite over them
+        transparent_draws.sort_by(|a, b| b.1.total_cmp(&a.1));
+        draws.transparent = transparent_draws
+            .into_iter()
+            .map(|(draw, _)| draw)
+            .collect();
+
+        renderer.set_render_stats(stats);
+
+        // the graph is taken out of the renderer while it runs, so its passes can borrow the renderer
+        let mut graph = std::mem::take(renderer.render_graph_mut());
+
+        if let Err(e) = graph.prepare(world, renderer) {
+            log::error(&e);
+        }
+
         let output = match renderer.surface().get_current_texture() {
             Ok(output) => output,
-            Err(_) => return,
+            Err(_) => {
+                *renderer.render_graph_mut() = graph;
+                return;
+            }
         };
 
         let view = output
@@ -42,89 +278,37 @@ impl game::System for MeshRenderer {
             },
         );
 
-        let mut current_transform_offset = 0;
+        // earlier frames' gpu timings come back while this one is recorded
+        let device = renderer.device().clone();
+        renderer
+            .profiler_mut()
+            .begin_gpu_frame(&device.lock().unwrap());
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &renderer.depth_view(),
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            render_pass.set_pipeline(renderer.render_pipeline());
-            render_pass.set_bind_group(0, renderer.global_bind_group(), &[]);
-
-            // Get entities that have both Mesh and Transform components
-            let entities = world.get_entities_with_components(&[
-                game::ComponentType::Mesh,
-                game::ComponentType::Transform,
-            ]);
-
-            for entity_id in entities {
-                let mesh = world.get_entity_component_by_type(entity_id, game::ComponentType::Mesh);
-                let transform =
-                    world.get_entity_component_by_type(entity_id, game::ComponentType::Transform);
-
-                if let (
-                    Some(game::ComponentEnum::Mesh(mesh)),
-                    Some(game::ComponentEnum::Transform(transform)),
-                ) = (mesh, transform)
-                {
-                    if let (Some(vertex_buffer), Some(index_buffer)) =
-                        (&mesh.vertex_buffer, &mesh.index_buffer)
-                    {
-                        // Update transform uniforms with the model matrix
-                        renderer.update_transform_uniforms_at_offset(
-                            renderer::TransformUniforms {
-                                model: transform.matrix_array(),
-                            },
-                            current_transform_offset as wgpu::BufferAddress,
-                        );
-
-                        render_pass.set_bind_group(
-                            1,
-                            renderer.transform_bind_group(),
-                            &[current_transform_offset],
-                        );
-                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                        render_pass
-                            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                        render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
-
-                        current_transform_offset +=
-                            renderer::Renderer::get_transform_aligned_size() as u32;
-                    }
-                }
-            }
+        match graph.execute(&mut encoder, world, renderer, &view, &draws) {
+            Ok(timed) => renderer
+                .profiler_mut()
+                .resolve_gpu_frame(&mut encoder, timed),
+            Err(e) => log::error(&e),
+        }
+
+        // picks copy out of the entity ids before the next frame reuses the texture
+        if let Some(entity_ids) = graph.texture(render_graph::ENTITY_IDS) {
+            renderer
+                .picking_mut()
+                .copy_requests(&mut encoder, entity_ids);
         }
 
+        *renderer.render_graph_mut() = graph;
+
         renderer
             .queue()
             .lock()
             .unwrap()
             .submit(std::iter::once(encoder.finish()));
 
+        renderer.profiler_mut().map_gpu_frame();
+        renderer.picking_mut().map_requests();
+
         output.present();
 
         renderer.window().request_redraw();
@@ -1,4 +1,15 @@
+pub mod boids_system;
 pub mod collision_system;
+pub mod debug_draw_system;
+pub mod hud_system;
+pub mod light_gatherer_system;
+pub mod material_bufferer_system;
 pub mod mesh_bufferer_system;
 pub mod mesh_renderer_system;
 pub mod movement_system;
+pub mod particle_bufferer_system;
+pub mod particle_system;
+pub mod sprite_system;
+pub mod sprite_walker_system;
+pub mod tilemap_bufferer_system;
+pub mod ui_system;
@@ -0,0 +1,133 @@
+use crate::components::particle_emitter_component::{ParticleBlend, ParticleSimulation};
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::particles::{self, Particle, ParticleUniforms};
+use crate::core::{game, renderer};
+use std::mem;
+
+/// Copies emitters' particles and curves to the gpu, and runs the simulation shader for the ones
+/// simulated there - runs before MeshRenderer so the particle pass draws this frame's particles
+pub struct ParticleBufferer {}
+
+impl game::System for ParticleBufferer {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let device = renderer.device().clone();
+        let device = device.lock().unwrap();
+        let queue = renderer.queue().clone();
+        let queue = queue.lock().unwrap();
+        let gpu = renderer.particle_gpu();
+        let camera_position = renderer.camera().position;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Particle Simulate Encoder"),
+        });
+        let mut dispatched = false;
+
+        for (_, component) in world.get_components_by_type_mut(ComponentType::ParticleEmitter) {
+            if let ComponentEnum::ParticleEmitter(emitter) = component {
+                let capacity = emitter.max_particles;
+
+                // the ring starts over in a new buffer
+                if emitter.buffers.as_ref().map(|buffers| buffers.capacity) != Some(capacity) {
+                    emitter.buffers = Some(gpu.create_buffers(&device, capacity));
+                    emitter.next_slot = 0;
+                }
+
+                let buffers = match emitter.buffers.as_mut() {
+                    Some(buffers) => buffers,
+                    None => continue,
+                };
+
+                let simulate_time = match emitter.simulation {
+                    ParticleSimulation::Cpu => {
+                        // max_particles may have been lowered below the live particles
+                        emitter.particles.truncate(capacity as usize);
+
+                        // blended particles are drawn back to front, added ones in any order
+                        if emitter.blend == ParticleBlend::Alpha {
+                            emitter.particles.sort_by(|a, b| {
+                                let a = camera_position.distance_squared(a.position.into());
+                                let b = camera_position.distance_squared(b.position.into());
+                                b.total_cmp(&a)
+                            });
+                        }
+
+                        queue.write_buffer(
+                            &buffers.particles,
+                            0,
+                            bytemuck::cast_slice(&emitter.particles),
+                        );
+                        buffers.instance_count = emitter.particles.len() as u32;
+
+                        0.0
+                    }
+                    ParticleSimulation::Gpu => {
+                        // never read back, so there's no sorting them - @see ParticleBlend::Alpha
+
+                        // new particles replace the oldest slots, in up to two runs around the ring
+                        let spawns = mem::take(&mut emitter.pending_spawns);
+                        let mut remaining = if capacity > 0 { spawns.as_slice() } else { &[] };
+
+                        while !remaining.is_empty() {
+                            let run = remaining.len().min((capacity - emitter.next_slot) as usize);
+
+                            queue.write_buffer(
+                                &buffers.particles,
+                                (emitter.next_slot as usize * mem::size_of::<Particle>())
+                                    as wgpu::BufferAddress,
+                                bytemuck::cast_slice(&remaining[..run]),
+                            );
+
+                            emitter.next_slot = (emitter.next_slot + run as u32) % capacity;
+                            remaining = &remaining[run..];
+                        }
+
+                        buffers.instance_count = capacity;
+
+                        mem::take(&mut emitter.pending_time)
+                    }
+                };
+
+                let colours = emitter.colour.bake();
+                let sizes = emitter.size.bake();
+
+                let uniforms = ParticleUniforms {
+                    gravity: [
+                        emitter.gravity[0],
+                        emitter.gravity[1],
+                        emitter.gravity[2],
+                        simulate_time,
+                    ],
+                    count: buffers.instance_count,
+                    _padding: [0; 3],
+                    colours,
+                    sizes: [0, 1, 2, 3]
+                        .map(|row| [0, 1, 2, 3].map(|column| sizes[row * 4 + column])),
+                };
+
+                queue.write_buffer(&buffers.uniforms, 0, bytemuck::cast_slice(&[uniforms]));
+
+                if emitter.simulation == ParticleSimulation::Gpu && capacity > 0 {
+                    let mut compute_pass =
+                        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some("Particle Simulate Pass"),
+                            timestamp_writes: None,
+                        });
+
+                    compute_pass.set_pipeline(&gpu.simulate_pipeline);
+                    compute_pass.set_bind_group(0, &buffers.compute_bind_group, &[]);
+                    compute_pass.dispatch_workgroups(
+                        capacity.div_ceil(particles::WORKGROUP_SIZE),
+                        1,
+                        1,
+                    );
+
+                    dispatched = true;
+                }
+            }
+        }
+
+        if dispatched {
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+}
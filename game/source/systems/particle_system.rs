@@ -0,0 +1,165 @@
+use crate::components::particle_emitter_component::{ParticleEmitterComponent, ParticleSimulation};
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::particles::Particle;
+use crate::core::{game, renderer};
+use glam::Vec3;
+
+/// shortest time between a burst's repeats, in seconds
+const MIN_BURST_INTERVAL: f32 = 0.01;
+
+/// most times one burst fires in a frame
+const MAX_BURST_REPEATS_PER_FRAME: u32 = 8;
+
+/// Spawns particles from emitters, and moves the ones simulated on the cpu
+pub struct ParticleSystem {}
+
+impl ParticleSystem {
+    /// A new particle at the origin, heading somewhere within the emitter's cone
+    fn spawn(emitter: &mut ParticleEmitterComponent, origin: Vec3) -> Particle {
+        let axis = Vec3::from_array(emitter.direction).normalize_or_zero();
+        let axis = if axis == Vec3::ZERO { Vec3::Y } else { axis };
+
+        // uniform over the cap of the cone
+        let cos_theta = 1.0 - emitter.random() * (1.0 - emitter.cone_angle.cos());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = emitter.random() * std::f32::consts::TAU;
+
+        let (tangent, bitangent) = axis.any_orthonormal_pair();
+        let direction =
+            axis * cos_theta + (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta;
+
+        let [min_speed, max_speed] = emitter.speed;
+        let speed = min_speed + (max_speed - min_speed) * emitter.random();
+
+        let [min_lifetime, max_lifetime] = emitter.lifetime;
+        let lifetime = min_lifetime + (max_lifetime - min_lifetime) * emitter.random();
+
+        Particle {
+            position: origin.to_array(),
+            age: 0.0,
+            velocity: (direction * speed).to_array(),
+            lifetime: lifetime.max(f32::EPSILON),
+        }
+    }
+
+    /// How many particles the spawn rate and bursts call for over this frame
+    fn spawn_count(emitter: &mut ParticleEmitterComponent, delta_time: f32) -> u32 {
+        emitter.age += delta_time;
+
+        if !emitter.emitting {
+            return 0;
+        }
+
+        emitter.spawn_accumulator += emitter.spawn_rate * delta_time;
+        let mut count = emitter.spawn_accumulator.floor() as u32;
+        emitter.spawn_accumulator -= count as f32;
+
+        emitter.bursts_fired.resize(emitter.bursts.len(), 0);
+
+        for (burst, fired) in emitter.bursts.iter().zip(emitter.bursts_fired.iter_mut()) {
+            let interval = burst
+                .interval
+                .map(|interval| interval.max(MIN_BURST_INTERVAL));
+            let mut repeats = 0;
+
+            // a long frame can cover several repeats
+            loop {
+                let next = match interval {
+                    Some(interval) => burst.time + interval * *fired as f32,
+                    None if *fired == 0 => burst.time,
+                    None => break,
+                };
+
+                if emitter.age < next {
+                    break;
+                }
+
+                // far behind, after a hitch - skip the missed repeats rather than firing them all
+                if repeats == MAX_BURST_REPEATS_PER_FRAME {
+                    if let Some(interval) = interval {
+                        *fired = ((emitter.age - burst.time) / interval).floor() as u32 + 1;
+                    }
+
+                    break;
+                }
+
+                count += burst.count;
+                *fired += 1;
+                repeats += 1;
+            }
+        }
+
+        count
+    }
+}
+
+impl game::System for ParticleSystem {
+    fn run(&self, world: &mut game::World, _renderer: &mut renderer::Renderer) {
+        let delta_time = world.state().delta_time;
+
+        let entities = world.get_entities_with_components(&[
+            ComponentType::ParticleEmitter,
+            ComponentType::Transform,
+        ]);
+
+        for entity_id in entities {
+            let mut components = world.get_entity_components_mut(
+                entity_id,
+                &[ComponentType::ParticleEmitter, ComponentType::Transform],
+            );
+
+            // components come back in the order they were added to the entity
+            let (emitter, origin) = match components.as_mut_slice() {
+                [ComponentEnum::ParticleEmitter(emitter), ComponentEnum::Transform(transform)]
+                | [ComponentEnum::Transform(transform), ComponentEnum::ParticleEmitter(emitter)] => {
+                    (emitter, Vec3::from_array(transform.position))
+                }
+                _ => continue,
+            };
+
+            let count = Self::spawn_count(emitter, delta_time);
+
+            match emitter.simulation {
+                ParticleSimulation::Cpu => {
+                    let gravity = Vec3::from_array(emitter.gravity);
+
+                    for particle in emitter.particles.iter_mut() {
+                        let velocity = Vec3::from_array(particle.velocity) + gravity * delta_time;
+                        let position = Vec3::from_array(particle.position) + velocity * delta_time;
+
+                        particle.velocity = velocity.to_array();
+                        particle.position = position.to_array();
+                        particle.age += delta_time;
+                    }
+
+                    emitter
+                        .particles
+                        .retain(|particle| particle.age < particle.lifetime);
+
+                    let room =
+                        (emitter.max_particles as usize).saturating_sub(emitter.particles.len());
+
+                    for _ in 0..(count as usize).min(room) {
+                        let particle = Self::spawn(emitter, origin);
+                        emitter.particles.push(particle);
+                    }
+                }
+                ParticleSimulation::Gpu => {
+                    emitter.pending_time += delta_time;
+
+                    for _ in 0..count {
+                        let particle = Self::spawn(emitter, origin);
+                        emitter.pending_spawns.push(particle);
+                    }
+
+                    // only the newest fit in the ring
+                    let overflow = emitter
+                        .pending_spawns
+                        .len()
+                        .saturating_sub(emitter.max_particles as usize);
+                    emitter.pending_spawns.drain(..overflow);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::components::sprite_animation_component::SpriteAnimationComponent;
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::{game, renderer, texture};
+use crate::utils::log;
+
+/// Loads sprites' textures, and steps sprite animations on to their next frames
+pub struct SpriteSystem {}
+
+impl SpriteSystem {
+    /// Move an animation on by some time, returning the region of the frame it's on
+    fn advance(animation: &mut SpriteAnimationComponent, delta_time: f32) -> Option<[u32; 4]> {
+        let clip = animation
+            .current
+            .as_ref()
+            .and_then(|name| animation.clips.get(name))?;
+
+        if clip.frames.is_empty() {
+            return None;
+        }
+
+        if animation.playing && !animation.finished {
+            animation.frame_time += delta_time * animation.speed;
+
+            // several frames can pass in one long update
+            while animation.frame_time >= clip.duration(animation.frame) {
+                animation.frame_time -= clip.duration(animation.frame);
+
+                if animation.frame + 1 < clip.frames.len() {
+                    animation.frame += 1;
+                } else if clip.looping {
+                    animation.frame = 0;
+                } else {
+                    animation.finished = true;
+                    animation.frame_time = 0.0;
+                    break;
+                }
+            }
+        }
+
+        clip.frames
+            .get(animation.frame.min(clip.frames.len() - 1))
+            .copied()
+    }
+}
+
+impl game::System for SpriteSystem {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        let delta_time = world.state().delta_time;
+
+        for (_, component) in world.get_components_by_type_mut(ComponentType::Sprite) {
+            if let ComponentEnum::Sprite(sprite) = component {
+                if !sprite.needs_texture {
+                    continue;
+                }
+
+                let options = texture::TextureOptions {
+                    generate_mipmaps: false,
+                    sampler: if sprite.pixelated {
+                        texture::SamplerOptions::pixelated()
+                    } else {
+                        texture::SamplerOptions {
+                            address_mode: wgpu::AddressMode::ClampToEdge,
+                            ..Default::default()
+                        }
+                    },
+                    ..Default::default()
+                };
+
+                match renderer.load_texture(&sprite.texture, options) {
+                    Ok(texture) => sprite.loaded_texture = Some(texture),
+                    Err(e) => log::error(&e),
+                }
+
+                sprite.needs_texture = false;
+            }
+        }
+
+        let entities = world
+            .get_entities_with_components(&[ComponentType::Sprite, ComponentType::SpriteAnimation]);
+
+        for entity_id in entities {
+            let mut components = world.get_entity_components_mut(
+                entity_id,
+                &[ComponentType::Sprite, ComponentType::SpriteAnimation],
+            );
+
+            // components come back in the order they were added to the entity
+            if let [ComponentEnum::Sprite(sprite), ComponentEnum::SpriteAnimation(animation)]
+            | [ComponentEnum::SpriteAnimation(animation), ComponentEnum::Sprite(sprite)] =
+                components.as_mut_slice()
+            {
+                if let Some(region) = Self::advance(animation, delta_time) {
+                    sprite.region = Some(region);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::sprite_animation_component::SpriteClip;
+
+    fn animation(looping: bool) -> SpriteAnimationComponent {
+        let frames = vec![[0, 0, 8, 8], [8, 0, 8, 8], [16, 0, 8, 8]];
+
+        SpriteAnimationComponent::new()
+            .with_clip("idle", SpriteClip::new(vec![[0, 8, 8, 8]], 1.0, true))
+            .with_clip("attack", SpriteClip::new(frames, 0.1, looping))
+    }
+
+    #[test]
+    fn advance_steps_through_several_frames_in_one_update() {
+        let mut animation = animation(true);
+        animation.play("attack");
+
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.05),
+            Some([0, 0, 8, 8])
+        );
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.2),
+            Some([16, 0, 8, 8])
+        );
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.1),
+            Some([0, 0, 8, 8])
+        );
+        assert!(!animation.finished);
+    }
+
+    #[test]
+    fn one_shot_clips_stay_on_their_last_frame() {
+        let mut animation = animation(false);
+        animation.play("attack");
+
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 1.0),
+            Some([16, 0, 8, 8])
+        );
+        assert!(animation.finished);
+
+        // playing the same clip again, as a system might every frame, doesn't restart it
+        animation.play("attack");
+
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.0),
+            Some([16, 0, 8, 8])
+        );
+        assert!(animation.finished);
+    }
+
+    #[test]
+    fn playing_another_clip_starts_it_from_the_first_frame() {
+        let mut animation = animation(false);
+        animation.play("attack");
+        SpriteSystem::advance(&mut animation, 1.0);
+
+        animation.play("idle");
+
+        assert_eq!(animation.frame, 0);
+        assert!(!animation.finished);
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.5),
+            Some([0, 8, 8, 8])
+        );
+
+        animation.play("attack");
+
+        assert_eq!(
+            SpriteSystem::advance(&mut animation, 0.0),
+            Some([0, 0, 8, 8])
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::{game, renderer};
+
+/// slower than this sideways, in world units per second, counts as standing still
+const WALK_SPEED: f32 = 1.0;
+
+/// Plays the walk clip of animated sprites that are moving sideways and the idle clip of those
+/// that aren't, flipping them to face the way they're going - runs before SpriteSystem
+pub struct SpriteWalkerSystem {}
+
+impl game::System for SpriteWalkerSystem {
+    fn run(&self, world: &mut game::World, _renderer: &mut renderer::Renderer) {
+        let entities = world.get_entities_with_components(&[
+            ComponentType::Sprite,
+            ComponentType::SpriteAnimation,
+            ComponentType::Movement,
+        ]);
+
+        for entity_id in entities {
+            let velocity =
+                match world.get_entity_component_by_type(entity_id, ComponentType::Movement) {
+                    Some(ComponentEnum::Movement(movement)) => movement.velocity[0],
+                    _ => continue,
+                };
+
+            let walking = velocity.abs() >= WALK_SPEED;
+
+            for component in world.get_entity_components_mut(
+                entity_id,
+                &[ComponentType::Sprite, ComponentType::SpriteAnimation],
+            ) {
+                match component {
+                    // keep facing the same way when stopping
+                    ComponentEnum::Sprite(sprite) if walking => sprite.flip_x = velocity < 0.0,
+                    ComponentEnum::SpriteAnimation(animation) => {
+                        animation.play(if walking { "walk" } else { "idle" })
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
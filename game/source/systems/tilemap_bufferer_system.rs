@@ -0,0 +1,197 @@
+use crate::components::tilemap_component::TilemapComponent;
+use crate::core::game::{ComponentEnum, ComponentType};
+use crate::core::texture::{self, Texture};
+use crate::core::tilemap::{SpriteVertex, CHUNK_SIZE};
+use crate::core::{game, renderer};
+use crate::utils::log;
+use std::mem;
+
+/// Loads tilesets and meshes tilemap chunks whose tiles changed since the last frame into their
+/// vertex buffers - runs before MeshRenderer so edits show up the frame they're made
+pub struct TilemapBufferer {}
+
+impl TilemapBufferer {
+    /// Quads for the non-empty tiles in one chunk, in world space
+    fn mesh_chunk(
+        tilemap: &TilemapComponent,
+        texture: &Texture,
+        position: [f32; 3],
+        scale: [f32; 3],
+        chunk_x: u32,
+        chunk_y: u32,
+    ) -> Vec<SpriteVertex> {
+        let columns = (texture.width / tilemap.tile_size[0].max(1)).max(1);
+        let uv_size = [
+            tilemap.tile_size[0] as f32 / texture.width as f32,
+            tilemap.tile_size[1] as f32 / texture.height as f32,
+        ];
+
+        let mut vertices = Vec::new();
+
+        for y in chunk_y * CHUNK_SIZE..((chunk_y + 1) * CHUNK_SIZE).min(tilemap.height) {
+            for x in chunk_x * CHUNK_SIZE..((chunk_x + 1) * CHUNK_SIZE).min(tilemap.width) {
+                let tile = match tilemap.tile(x, y) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+
+                let [min, max] = tilemap.tile_bounds(position, scale, x, y);
+                let uv_min = [
+                    (tile % columns) as f32 * uv_size[0],
+                    (tile / columns) as f32 * uv_size[1],
+                ];
+
+                // texture rows run downwards, world y runs up
+                for (corner_x, corner_y) in [(0, 0), (1, 0), (0, 1), (0, 1), (1, 0), (1, 1)] {
+                    vertices.push(SpriteVertex {
+                        position: [
+                            if corner_x == 0 { min[0] } else { max[0] },
+                            if corner_y == 0 { min[1] } else { max[1] },
+                        ],
+                        uv: [
+                            uv_min[0] + corner_x as f32 * uv_size[0],
+                            uv_min[1] + (1 - corner_y) as f32 * uv_size[1],
+                        ],
+                        colour: tilemap.tint,
+                    });
+                }
+            }
+        }
+
+        vertices
+    }
+}
+
+impl game::System for TilemapBufferer {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        for (_, component) in world.get_components_by_type_mut(ComponentType::Tilemap) {
+            if let ComponentEnum::Tilemap(tilemap) = component {
+                if !tilemap.needs_texture {
+                    continue;
+                }
+
+                let options = texture::TextureOptions {
+                    generate_mipmaps: false,
+                    sampler: if tilemap.pixelated {
+                        texture::SamplerOptions::pixelated()
+                    } else {
+                        texture::SamplerOptions {
+                            address_mode: wgpu::AddressMode::ClampToEdge,
+                            ..Default::default()
+                        }
+                    },
+                    ..Default::default()
+                };
+
+                match renderer.load_texture(&tilemap.tileset, options) {
+                    Ok(texture) => tilemap.loaded_texture = Some(texture),
+                    Err(e) => log::error(&e),
+                }
+
+                tilemap.needs_texture = false;
+
+                // uvs depend on the tileset's size
+                for chunk in tilemap.chunks.iter_mut() {
+                    chunk.dirty = true;
+                }
+            }
+        }
+
+        let device = renderer.device().lock().unwrap();
+        let queue = renderer.queue().lock().unwrap();
+
+        let entities =
+            world.get_entities_with_components(&[ComponentType::Tilemap, ComponentType::Transform]);
+
+        for entity_id in entities {
+            let mut components = world.get_entity_components_mut(
+                entity_id,
+                &[ComponentType::Tilemap, ComponentType::Transform],
+            );
+
+            // components come back in the order they were added to the entity
+            let (tilemap, transform) = match components.as_mut_slice() {
+                [ComponentEnum::Tilemap(tilemap), ComponentEnum::Transform(transform)]
+                | [ComponentEnum::Transform(transform), ComponentEnum::Tilemap(tilemap)] => {
+                    (tilemap, transform)
+                }
+                _ => continue,
+            };
+
+            let texture = match &tilemap.loaded_texture {
+                Some(texture) => texture.clone(),
+                None => continue,
+            };
+
+            let meshed_transform = (transform.position, transform.scale);
+
+            // every chunk moves with the tilemap
+            if tilemap.meshed_transform != Some(meshed_transform) {
+                for chunk in tilemap.chunks.iter_mut() {
+                    chunk.dirty = true;
+                }
+
+                tilemap.meshed_transform = Some(meshed_transform);
+            }
+
+            let [chunks_x, chunks_y] = tilemap.chunk_counts();
+
+            for chunk_y in 0..chunks_y {
+                for chunk_x in 0..chunks_x {
+                    let index = tilemap.chunk_index(chunk_x, chunk_y);
+
+                    if !tilemap.chunks[index].dirty {
+                        continue;
+                    }
+
+                    let vertices = Self::mesh_chunk(
+                        tilemap,
+                        &texture,
+                        transform.position,
+                        transform.scale,
+                        chunk_x,
+                        chunk_y,
+                    );
+
+                    let [min, _] = tilemap.tile_bounds(
+                        transform.position,
+                        transform.scale,
+                        chunk_x * CHUNK_SIZE,
+                        chunk_y * CHUNK_SIZE,
+                    );
+                    let [_, max] = tilemap.tile_bounds(
+                        transform.position,
+                        transform.scale,
+                        ((chunk_x + 1) * CHUNK_SIZE).min(tilemap.width) - 1,
+                        ((chunk_y + 1) * CHUNK_SIZE).min(tilemap.height) - 1,
+                    );
+
+                    let chunk = &mut tilemap.chunks[index];
+                    chunk.bounds = [min, max];
+                    chunk.vertex_count = vertices.len() as u32;
+                    chunk.dirty = false;
+
+                    if vertices.is_empty() {
+                        continue;
+                    }
+
+                    // buffers only grow, so repeated edits don't reallocate
+                    if chunk.vertex_buffer.is_none() || vertices.len() > chunk.capacity {
+                        chunk.capacity = vertices.len().next_power_of_two();
+                        chunk.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                            label: Some("Tilemap Chunk Vertex Buffer"),
+                            size: (chunk.capacity * mem::size_of::<SpriteVertex>())
+                                as wgpu::BufferAddress,
+                            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                            mapped_at_creation: false,
+                        }));
+                    }
+
+                    if let Some(vertex_buffer) = &chunk.vertex_buffer {
+                        queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+                    }
+                }
+            }
+        }
+    }
+}
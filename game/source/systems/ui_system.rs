@@ -0,0 +1,140 @@
+use crate::components::ui_node_component::UiContent;
+use crate::core::game::{ComponentEnum, ComponentType, EntityId};
+use crate::core::ui::{self, LayoutNode, UiInteraction};
+use crate::core::{game, renderer, texture};
+use crate::utils::log;
+use std::collections::HashMap;
+
+/// Lays out ui nodes in the window, and updates buttons from the mouse
+pub struct UiSystem {}
+
+impl UiSystem {
+    /// Load the textures of image nodes that haven't been loaded yet
+    fn load_textures(world: &mut game::World, renderer: &mut renderer::Renderer) {
+        for (_, component) in world.get_components_by_type_mut(ComponentType::UiNode) {
+            if let ComponentEnum::UiNode(node) = component {
+                if !node.needs_texture {
+                    continue;
+                }
+
+                if let UiContent::Image { path, .. } = &node.content {
+                    let options = texture::TextureOptions {
+                        generate_mipmaps: false,
+                        sampler: texture::SamplerOptions {
+                            address_mode: wgpu::AddressMode::ClampToEdge,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+
+                    match renderer.load_texture(path, options) {
+                        Ok(texture) => node.texture = Some(texture),
+                        Err(e) => log::error(&e),
+                    }
+                }
+
+                node.needs_texture = false;
+            }
+        }
+    }
+}
+
+impl game::System for UiSystem {
+    fn run(&self, world: &mut game::World, renderer: &mut renderer::Renderer) {
+        Self::load_textures(world, renderer);
+
+        let order = ui::draw_order(world);
+        let indices: HashMap<EntityId, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(index, &entity_id)| (entity_id, index))
+            .collect();
+
+        let mut nodes: Vec<LayoutNode> = Vec::with_capacity(order.len());
+        let mut roots = Vec::new();
+
+        for &entity_id in order.iter() {
+            if let Some(ComponentEnum::UiNode(node)) =
+                world.get_entity_component_by_type(entity_id, ComponentType::UiNode)
+            {
+                let content_size = match &node.content {
+                    UiContent::Text { text, style } => renderer.text().size(text, style),
+                    UiContent::Image { .. } => match &node.texture {
+                        Some(texture) => [texture.width as f32, texture.height as f32],
+                        None => [0.0, 0.0],
+                    },
+                    _ => [0.0, 0.0],
+                };
+
+                match node.parent.and_then(|parent| indices.get(&parent)) {
+                    // parents come first in the draw order, so are already in the list
+                    Some(&parent) => {
+                        let child = nodes.len();
+                        nodes[parent].children.push(child);
+                    }
+                    None => roots.push(nodes.len()),
+                }
+
+                nodes.push(LayoutNode {
+                    style: node.style.clone(),
+                    content_size,
+                    children: Vec::new(),
+                    rect: ui::UiRect::default(),
+                });
+            }
+        }
+
+        let size = renderer.size();
+        ui::layout(&mut nodes, &roots, [size.width as f32, size.height as f32]);
+
+        let mouse = world.state().mouse;
+
+        // the top most node under the cursor takes the mouse
+        let mut hovered_button = None;
+        let mut over_ui = false;
+
+        for (index, &entity_id) in order.iter().enumerate() {
+            if let Some(ComponentEnum::UiNode(node)) =
+                world.get_entity_component_by_type(entity_id, ComponentType::UiNode)
+            {
+                if nodes[index].rect.contains(mouse.position) && node.fill_colour().is_some() {
+                    over_ui = true;
+
+                    // panels over a button block it
+                    hovered_button = match node.content {
+                        UiContent::Button { .. } => Some(entity_id),
+                        _ => None,
+                    };
+                }
+            }
+        }
+
+        world.state().mouse.over_ui = over_ui;
+
+        for (entity_id, component) in world.get_components_by_type_mut(ComponentType::UiNode) {
+            if let ComponentEnum::UiNode(node) = component {
+                if let Some(&index) = indices.get(&entity_id) {
+                    node.rect = nodes[index].rect;
+                }
+
+                node.clicked = false;
+
+                if hovered_button != Some(entity_id) {
+                    node.interaction = UiInteraction::None;
+                    continue;
+                }
+
+                let was_pressed = node.interaction == UiInteraction::Pressed || mouse.left_pressed;
+
+                node.interaction = if mouse.left_released && was_pressed {
+                    node.clicked = true;
+                    UiInteraction::Hovered
+                } else if mouse.left_down && was_pressed {
+                    UiInteraction::Pressed
+                } else {
+                    UiInteraction::Hovered
+                };
+            }
+        }
+    }
+}
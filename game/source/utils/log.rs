@@ -5,3 +5,7 @@ pub fn error(message: &str) {
 pub fn warn(message: &str) {
     println!("Warning: {}", message);
 }
+
+pub fn info(message: &str) {
+    println!("Info: {}", message);
+}